@@ -1,10 +1,7 @@
 use jen::generator::Generator;
-use runiq::{Filter, QuickFilter};
+use runiq::{QuickFilter, RuniqIteratorExt};
 
 fn main() {
-    // First we create a filter to detect duplicates
-    let mut filter = QuickFilter::default();
-
     // As well as a template to generate random inputs
     let template = "{{ integer(start=1, end=6) }}";
 
@@ -14,11 +11,11 @@ fn main() {
         .take(20)
         .collect::<Vec<_>>();
 
-    // And filter uniques
+    // And filter uniques using a filter to detect duplicates
     let outputs = inputs
         .iter()
-        .filter(|v| filter.detect(v.as_bytes()))
-        .map(|v| v.to_owned())
+        .unique_via(QuickFilter::default())
+        .cloned()
         .collect::<Vec<_>>();
 
     // Before we print the before/after to the console
@@ -0,0 +1,45 @@
+#ifndef RUNIQ_H
+#define RUNIQ_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque filter handle, created via runiq_filter_new. */
+typedef struct RuniqFilter RuniqFilter;
+
+/* Creates a filter by name (such as "quick"), or NULL on failure. */
+RuniqFilter *runiq_filter_new(const char *kind);
+
+/* Returns true if the input has not been seen by the filter before. */
+bool runiq_filter_detect(RuniqFilter *filter, const uint8_t *bytes, size_t len);
+
+/* Releases a filter; NULL is ignored. */
+void runiq_filter_free(RuniqFilter *filter);
+
+/* Version of the RuniqPlugin layout expected by runiq. */
+#define RUNIQ_PLUGIN_ABI_VERSION 1
+
+/*
+ * Table of filter functions provided by a plugin, which must export
+ * `const RuniqPlugin *runiq_plugin(void)` to be loaded via --plugin.
+ */
+typedef struct RuniqPlugin {
+    uint32_t abi_version;
+    void *(*create)(uint64_t seed);
+    bool (*detect)(void *state, const uint8_t *bytes, size_t len);
+    bool (*contains)(const void *state, const uint8_t *bytes, size_t len);
+    void (*clear)(void *state);
+    uint64_t (*len)(const void *state);
+    void (*destroy)(void *state);
+} RuniqPlugin;
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
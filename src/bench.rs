@@ -0,0 +1,195 @@
+//! Benchmark module used to compare the available filters.
+//!
+//! Inputs are read into memory once, and then passed through every
+//! filter in turn. Throughput and peak memory are tracked for each
+//! filter, alongside how the number of uniques found differs from an
+//! exact filter, to make it easier to choose between them.
+use cli_table::format::{Border, Justify, Separator};
+use cli_table::{print_stdout, Cell, Style, Table};
+use format_num::NumberFormat;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::input::{self, Format};
+use crate::options::Options;
+use runiq::Filters;
+
+/// Allocator tracking the memory in use, to measure peak memory.
+///
+/// Allocations are only counted while a filter is being measured, so
+/// the rest of the program only pays for a single flag check.
+struct Tracking;
+
+/// Whether allocations are currently being counted.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Number of bytes allocated since tracking was last started.
+static CURRENT: AtomicIsize = AtomicIsize::new(0);
+
+/// Highest number of bytes allocated since tracking was last started.
+static PEAK: AtomicIsize = AtomicIsize::new(0);
+
+#[global_allocator]
+static ALLOCATOR: Tracking = Tracking;
+
+/// Implement `GlobalAlloc` to pass through to the system allocator.
+unsafe impl GlobalAlloc for Tracking {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            track(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        track(-(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            track(new_size as isize - layout.size() as isize);
+        }
+        new_ptr
+    }
+}
+
+/// Adjusts the number of bytes allocated, if tracking is active.
+#[inline]
+fn track(delta: isize) {
+    if ACTIVE.load(Ordering::Relaxed) {
+        let current = CURRENT.fetch_add(delta, Ordering::Relaxed) + delta;
+        PEAK.fetch_max(current, Ordering::Relaxed);
+    }
+}
+
+/// All filters compared by the benchmark, with the exact filter first.
+const FILTERS: [Filters; 7] = [
+    Filters::Simple,
+    Filters::Quick,
+    Filters::Quick128,
+    Filters::Compact,
+    Filters::Cuckoo,
+    Filters::Sorted,
+    Filters::Disk,
+];
+
+/// Result of passing all entries through a single filter.
+struct Measurement {
+    filter: Filters,
+    elapsed: Duration,
+    memory: usize,
+    uniques: u64,
+}
+
+/// Runs all inputs through every filter, printing a table of results.
+pub fn run(options: &Options) -> io::Result<()> {
+    let inputs = input::expand(&options.inputs, options.recursive)?;
+    let format = Format::Lines(options.record_delimiter.clone());
+
+    // read all entries up front, so reading is excluded from timings
+    let mut data = Vec::new();
+    let mut ends = Vec::new();
+
+    for name in &inputs {
+        let mut records = format.open(name, options.buffer_size).map_err(|err| {
+            io::Error::new(err.kind(), format!("cannot open '{}': {}", name, err))
+        })?;
+
+        while let Some(record) = records.next_record()? {
+            data.extend_from_slice(record);
+            ends.push(data.len());
+        }
+    }
+
+    let mut measurements = Vec::with_capacity(FILTERS.len());
+
+    for kind in FILTERS.iter().copied() {
+        // only track memory allocated by the filter itself
+        CURRENT.store(0, Ordering::Relaxed);
+        PEAK.store(0, Ordering::Relaxed);
+        ACTIVE.store(true, Ordering::Relaxed);
+
+        let mut filter = crate::create_filter(kind, options)?;
+        let mut uniques = 0;
+        let mut start = 0;
+
+        let timer = Instant::now();
+
+        for end in ends.iter().copied() {
+            uniques += filter.detect(&data[start..end]) as u64;
+            start = end;
+        }
+
+        let elapsed = timer.elapsed();
+
+        ACTIVE.store(false, Ordering::Relaxed);
+        let memory = PEAK.load(Ordering::Relaxed).max(0) as usize;
+
+        drop(filter);
+
+        measurements.push(Measurement {
+            filter: kind,
+            elapsed,
+            memory,
+            uniques,
+        });
+    }
+
+    print(&measurements, ends.len(), data.len());
+
+    Ok(())
+}
+
+/// Prints all measurements to stdout.
+fn print(measurements: &[Measurement], entries: usize, size: usize) {
+    let num = NumberFormat::new();
+    let exact = measurements[0].uniques as i128;
+
+    let rows = measurements.iter().map(|measurement| {
+        let seconds = measurement.elapsed.as_secs_f64().max(f64::MIN_POSITIVE);
+        let throughput = (size as f64 / seconds) as u64;
+        let difference = measurement.uniques as i128 - exact;
+
+        vec![
+            format!("{:?}", measurement.filter).cell(),
+            format!("{:.3}s", measurement.elapsed.as_secs_f64())
+                .cell()
+                .justify(Justify::Right),
+            num.format(",.0", entries as f64 / seconds)
+                .cell()
+                .justify(Justify::Right),
+            format!("{}/s", bytesize::to_string(throughput, false))
+                .cell()
+                .justify(Justify::Right),
+            bytesize::to_string(measurement.memory as u64, false)
+                .cell()
+                .justify(Justify::Right),
+            num.format(",.0", measurement.uniques as f64)
+                .cell()
+                .justify(Justify::Right),
+            format!("{:+}", difference).cell().justify(Justify::Right),
+        ]
+    });
+
+    let table = rows
+        .table()
+        .title(vec![
+            "Filter".cell().bold(true),
+            "Time".cell().bold(true),
+            "Entries/s".cell().bold(true),
+            "Throughput".cell().bold(true),
+            "Peak Memory".cell().bold(true),
+            "Uniques".cell().bold(true),
+            "Difference".cell().bold(true),
+        ])
+        .border(Border::builder().build())
+        .separator(Separator::builder().build());
+
+    print_stdout(table).expect("unable to print bench table")
+}
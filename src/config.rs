@@ -0,0 +1,177 @@
+//! Configuration module used to provide defaults for options.
+//!
+//! Every option can be given a default through an environment variable
+//! named after the option with a `RUNIQ_` prefix (such as `RUNIQ_FILTER`),
+//! or through a TOML file at `~/.config/runiq/config.toml`. Flags on the
+//! command line take precedence over the environment, which in turn takes
+//! precedence over the configuration file.
+use clap::builder::BoolishValueParser;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use toml::{Table, Value};
+
+use std::env;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::PathBuf;
+
+/// Options which can only be provided on the command line.
+const EXCLUDED: [&str; 2] = ["help", "inputs"];
+
+/// Applies defaults from the environment and configuration to a parser.
+///
+/// Defaults are applied as the default values of each option, so they
+/// never conflict with (or require) other options in the way a flag on
+/// the command line would. Flags which are given a default also accept
+/// an explicit value (such as `--ignore-case=false`) to disable them.
+///
+/// Any options named in `skipped` are left without a default, and the
+/// names of all options which were given a default are returned.
+pub fn apply(parser: &mut Command, skipped: &[String]) -> Result<Vec<String>, String> {
+    let ids = parser
+        .get_arguments()
+        .map(|arg| arg.get_id().to_string())
+        .filter(|id| !EXCLUDED.contains(&id.as_str()))
+        .collect::<Vec<_>>();
+
+    // read the configuration, which may not exist at all
+    let (path, config) = match read()? {
+        Some(config) => config,
+        None => (PathBuf::new(), Table::new()),
+    };
+
+    let mut defaults = Vec::new();
+
+    for (key, value) in config {
+        if !ids.contains(&key) {
+            return Err(format!("unknown option '{}' in '{}'", key, path.display()));
+        }
+
+        let value = match value {
+            Value::String(value) => value,
+            Value::Integer(value) => value.to_string(),
+            Value::Float(value) => value.to_string(),
+            Value::Boolean(value) => value.to_string(),
+            _ => {
+                return Err(format!(
+                    "invalid value for '{}' in '{}'",
+                    key,
+                    path.display()
+                ))
+            }
+        };
+
+        defaults.push((key, value));
+    }
+
+    // the environment always takes precedence over the configuration
+    for id in &ids {
+        let name = variable(id);
+        if let Some(value) = env::var_os(&name) {
+            let value = value
+                .into_string()
+                .map_err(|_| format!("invalid value for '{}' in the environment", name))?;
+
+            defaults.retain(|(key, _)| key != id);
+            defaults.push((id.clone(), value));
+        }
+    }
+
+    defaults.retain(|(key, _)| !skipped.contains(key));
+
+    let mut configured = Vec::with_capacity(defaults.len());
+
+    for (id, value) in defaults {
+        *parser = mem::take(parser).mut_arg(&id, |arg| default(arg, value));
+        configured.push(id);
+    }
+
+    Ok(configured)
+}
+
+/// Finds configured defaults which conflict with options on the command line.
+///
+/// A default should never stop an option from being provided, so these
+/// defaults should be skipped and the arguments parsed again.
+pub fn conflicting(parser: &Command, matches: &ArgMatches, configured: &[String]) -> Vec<String> {
+    let source = |arg: &Arg| matches.value_source(arg.get_id().as_str());
+
+    let provided = parser
+        .get_arguments()
+        .filter(|arg| source(arg) == Some(ValueSource::CommandLine))
+        .collect::<Vec<_>>();
+
+    parser
+        .get_arguments()
+        .filter(|arg| configured.iter().any(|id| arg.get_id() == id.as_str()))
+        .filter(|arg| source(arg) == Some(ValueSource::DefaultValue))
+        .filter(|arg| {
+            provided.iter().any(|other| {
+                let conflicts = |a: &Arg, b: &Arg| {
+                    parser
+                        .get_arg_conflicts_with(a)
+                        .iter()
+                        .any(|conflict| conflict.get_id() == b.get_id())
+                };
+                conflicts(arg, other) || conflicts(other, arg)
+            })
+        })
+        .map(|arg| arg.get_id().to_string())
+        .collect()
+}
+
+/// Sets the default value of an option.
+///
+/// Flags are converted to accept an optional boolean value, as otherwise
+/// there would be no way to disable a flag which defaults to being set.
+fn default(arg: Arg, value: String) -> Arg {
+    let arg = match arg.get_action() {
+        ArgAction::SetTrue => arg
+            .action(ArgAction::Set)
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_missing_value("true")
+            .value_parser(BoolishValueParser::new()),
+        _ => arg,
+    };
+    arg.default_value(value)
+}
+
+/// Creates the name of the environment variable for an option.
+fn variable(id: &str) -> String {
+    format!("RUNIQ_{}", id.to_uppercase().replace('-', "_"))
+}
+
+/// Reads the configuration file, if one exists.
+///
+/// The path can be overridden with `RUNIQ_CONFIG`, in which case the file
+/// must exist. Otherwise the file is looked up in `$XDG_CONFIG_HOME`, or
+/// in `~/.config`, and is skipped if it does not exist.
+fn read() -> Result<Option<(PathBuf, Table)>, String> {
+    let (path, required) = match env::var_os("RUNIQ_CONFIG") {
+        Some(path) => (PathBuf::from(path), true),
+        None => match env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        {
+            Some(dir) => (dir.join("runiq").join("config.toml"), false),
+            None => return Ok(None),
+        },
+    };
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound && !required => return Ok(None),
+        Err(err) => return Err(format!("cannot read '{}': {}", path.display(), err)),
+    };
+
+    match contents.parse::<Table>() {
+        Ok(config) => Ok(Some((path, config))),
+        Err(err) => Err(format!(
+            "cannot parse '{}': {}",
+            path.display(),
+            err.message()
+        )),
+    }
+}
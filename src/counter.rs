@@ -0,0 +1,162 @@
+//! Counter module used to track the occurrences of entries.
+//!
+//! Counting requires memory for every distinct entry seen, so this
+//! is only used when a mode explicitly needs occurrence counts (such
+//! as emitting counts, or thresholds on counts).
+use identity_hash::BuildIdentityHasher;
+use xxhash_rust::xxh3::xxh3_64_with_seed;
+
+use std::collections::HashMap;
+
+/// Counter struct to track occurrences of entries.
+///
+/// Entries are tracked via their hash, and can optionally retain the
+/// first occurrence of each entry so that they can be emitted once
+/// all input has been read (in the order they were first seen). Every
+/// occurrence can also be retained, for modes which emit all of them.
+#[derive(Debug, Default)]
+pub struct Counter {
+    indices: HashMap<u64, usize, BuildIdentityHasher<u64>>,
+    counts: Vec<u64>,
+    entries: Vec<Vec<u8>>,
+    occurrences: Vec<(usize, Vec<u8>)>,
+    retain: bool,
+    retain_all: bool,
+    seed: u64,
+}
+
+impl Counter {
+    /// Creates a new `Counter`, optionally retaining entries.
+    pub fn new(seed: u64, retain: bool) -> Counter {
+        Counter {
+            retain,
+            seed,
+            ..Counter::default()
+        }
+    }
+
+    /// Creates a new `Counter` retaining every occurrence of each entry.
+    pub fn retaining_all(seed: u64) -> Counter {
+        Counter {
+            retain_all: true,
+            seed,
+            ..Counter::default()
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more distinct keys.
+    pub fn reserve(&mut self, additional: usize) {
+        self.indices.reserve(additional);
+        self.counts.reserve(additional);
+        if self.retain {
+            self.entries.reserve(additional);
+        }
+    }
+
+    /// Increments the count of a key, returning the new count.
+    ///
+    /// The provided entry is only stored (when retaining) on the first
+    /// occurrence of the key, as all other occurrences are duplicates.
+    pub fn increment(&mut self, key: &[u8], input: &[u8]) -> u64 {
+        let hash = xxh3_64_with_seed(key, self.seed);
+        let next = self.counts.len();
+        let index = *self.indices.entry(hash).or_insert(next);
+
+        if index == next {
+            self.counts.push(0);
+            if self.retain {
+                self.entries.push(input.to_vec());
+            }
+        }
+
+        if self.retain_all {
+            self.occurrences.push((index, input.to_vec()));
+        }
+
+        self.counts[index] += 1;
+        self.counts[index]
+    }
+
+    /// Consumes the counter, returning all entries with their counts.
+    ///
+    /// Entries are returned in the order they were first seen, and will
+    /// only be returned if the counter was created to retain entries.
+    pub fn into_entries(self) -> impl Iterator<Item = (u64, Vec<u8>)> {
+        self.counts.into_iter().zip(self.entries)
+    }
+
+    /// Consumes the counter, returning the occurrences of each entry.
+    ///
+    /// Groups are returned in the order each entry was first seen, with
+    /// the occurrences of each group in the order they were seen. These
+    /// are only returned if the counter was created to retain them all.
+    pub fn into_groups(self) -> impl Iterator<Item = Vec<Vec<u8>>> {
+        let mut groups = vec![Vec::new(); self.counts.len()];
+        for (index, entry) in self.occurrences {
+            groups[index].push(entry);
+        }
+        groups.into_iter()
+    }
+
+    /// Consumes the counter, returning every occurrence with its count.
+    ///
+    /// Occurrences are returned in the order they were seen, and will
+    /// only be returned if the counter was created to retain them all.
+    pub fn into_occurrences(self) -> impl Iterator<Item = (u64, Vec<u8>)> {
+        let counts = self.counts;
+        self.occurrences
+            .into_iter()
+            .map(move |(index, entry)| (counts[index], entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Counter;
+
+    #[test]
+    fn counting_entries() {
+        let mut counter = Counter::new(0, true);
+
+        assert_eq!(counter.increment(b"a", b"a1"), 1);
+        assert_eq!(counter.increment(b"b", b"b1"), 1);
+        assert_eq!(counter.increment(b"a", b"a2"), 2);
+
+        assert_eq!(
+            counter.into_entries().collect::<Vec<_>>(),
+            vec![(2, b"a1".to_vec()), (1, b"b1".to_vec())]
+        );
+    }
+
+    #[test]
+    fn counting_occurrences() {
+        let mut counter = Counter::retaining_all(0);
+
+        counter.increment(b"a", b"a1");
+        counter.increment(b"b", b"b1");
+        counter.increment(b"a", b"a2");
+
+        assert_eq!(
+            counter.into_occurrences().collect::<Vec<_>>(),
+            vec![
+                (2, b"a1".to_vec()),
+                (1, b"b1".to_vec()),
+                (2, b"a2".to_vec())
+            ]
+        );
+    }
+
+    #[test]
+    fn grouping_occurrences() {
+        let mut counter = Counter::retaining_all(0);
+
+        counter.increment(b"a", b"a1");
+        counter.increment(b"b", b"b1");
+        counter.increment(b"a", b"a2");
+
+        assert_eq!(
+            counter.into_groups().collect::<Vec<_>>(),
+            vec![vec![b"a1".to_vec(), b"a2".to_vec()], vec![b"b1".to_vec()]]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//! Deny module used to build and load filters of entries to drop.
+//!
+//! Deny filters are immutable filters built ahead of time from a set of
+//! entries, via `runiq build-filter`. They are then consumed via `--deny`
+//! to drop any entries found in the filter before filtering duplicates.
+use crate::input::{self, Format};
+use crate::options::Options;
+use crate::output::Staged;
+use runiq::XorFilter;
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+/// Builds a deny filter from all inputs, writing it to the output path.
+pub fn build(options: &Options) -> io::Result<()> {
+    let inputs = input::expand(&options.inputs, options.recursive)?;
+    let format = Format::Lines(options.record_delimiter.clone());
+    let mut entries = Vec::new();
+
+    for name in &inputs {
+        let mut records = format.open(name, options.buffer_size).map_err(|err| {
+            io::Error::new(err.kind(), format!("cannot open '{}': {}", name, err))
+        })?;
+
+        while let Some(record) = records.next_record()? {
+            entries.push(record.to_vec());
+        }
+    }
+
+    let filter = XorFilter::build(&entries);
+    let path = options.output.as_ref().expect("output must be provided");
+
+    // write via a staged file, so a failure never leaves a partial filter
+    let staged = Staged::new(Path::new(path));
+    let file = File::create(staged.temp()).map_err(|err| cannot("create", path, err))?;
+    let mut writer = BufWriter::new(file);
+
+    filter
+        .save(&mut writer)
+        .and_then(|_| writer.into_inner().map_err(|err| err.into_error()))
+        .and_then(|file| file.sync_all())
+        .map_err(|err| cannot("save", path, err))?;
+
+    staged.persist()
+}
+
+/// Loads a deny filter from a path.
+pub fn load(path: &str) -> io::Result<XorFilter> {
+    let file = File::open(path).map_err(|err| cannot("open", path, err))?;
+    XorFilter::load(BufReader::new(file)).map_err(|err| cannot("load", path, err))
+}
+
+/// Creates an error for an operation on a deny filter.
+fn cannot(action: &str, path: &str, err: io::Error) -> io::Error {
+    let message = format!("cannot {} '{}': {}", action, path, err);
+    io::Error::new(err.kind(), message)
+}
@@ -0,0 +1,68 @@
+//! Digest module used to emit the hashes of entries.
+//!
+//! Digests are written as lowercase hex, so that they can be used as
+//! content keys by downstream systems. Hashes are never seeded, so the
+//! digest of an entry is stable across runs.
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Hex digits used when encoding digests.
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+/// Hash algorithm used to create digests.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Algorithm {
+    /// 64-bit xxh3, the fastest of all algorithms.
+    Xxh3,
+
+    /// 256-bit SHA-2.
+    Sha256,
+
+    /// 128-bit MD5.
+    Md5,
+}
+
+impl Algorithm {
+    /// Writes the digest of an input to a buffer, as lowercase hex.
+    pub fn write_hex(&self, input: &[u8], buffer: &mut Vec<u8>) {
+        match self {
+            Algorithm::Xxh3 => hex(&xxh3_64(input).to_be_bytes(), buffer),
+            Algorithm::Sha256 => hex(&Sha256::digest(input), buffer),
+            Algorithm::Md5 => hex(&Md5::digest(input), buffer),
+        }
+    }
+}
+
+/// Encodes bytes as lowercase hex into a buffer.
+fn hex(bytes: &[u8], buffer: &mut Vec<u8>) {
+    for byte in bytes {
+        buffer.push(HEX[(byte >> 4) as usize]);
+        buffer.push(HEX[(byte & 0xF) as usize]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Algorithm;
+
+    /// Creates the digest of an input as a string.
+    fn digest(algorithm: Algorithm, input: &[u8]) -> String {
+        let mut buffer = Vec::new();
+        algorithm.write_hex(input, &mut buffer);
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn digests_of_entries() {
+        assert_eq!(digest(Algorithm::Xxh3, b"").len(), 16);
+        assert_eq!(
+            digest(Algorithm::Sha256, b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            digest(Algorithm::Md5, b"abc"),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! C bindings for use of filters from other languages.
+//!
+//! Filters are created by name via `runiq_filter_new`, and handed back
+//! as an opaque pointer which must be released via `runiq_filter_free`.
+//! A matching header can be found in `include/runiq.h`.
+//!
+//! Custom filters can also be provided to the CLI as plugins, which are
+//! dynamic libraries exporting a `runiq_plugin` function returning a
+//! `RuniqPlugin` table. Plugins written in Rust can use `export_plugin!`
+//! to generate this function from any `Filter` implementation.
+use crate::filters::{Filter, Filters};
+
+use std::ffi::{c_void, CStr};
+use std::io;
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+
+/// Version of the `RuniqPlugin` layout, bumped on any incompatible change.
+pub const RUNIQ_PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Table of functions exported by a filter plugin.
+///
+/// Each function receives the state pointer returned by `create`, which
+/// is released via `destroy` once the filter is no longer needed.
+#[repr(C)]
+pub struct RuniqPlugin {
+    /// The `RUNIQ_PLUGIN_ABI_VERSION` the plugin was built against.
+    pub abi_version: u32,
+    /// Creates the state of a filter using the provided hash seed.
+    pub create: unsafe extern "C" fn(seed: u64) -> *mut c_void,
+    /// Detects if an input is unique, returning `true` if so.
+    pub detect: unsafe extern "C" fn(state: *mut c_void, bytes: *const u8, len: usize) -> bool,
+    /// Checks if an input has been seen, without recording it.
+    pub contains: unsafe extern "C" fn(state: *const c_void, bytes: *const u8, len: usize) -> bool,
+    /// Clears all inputs recorded by the filter.
+    pub clear: unsafe extern "C" fn(state: *mut c_void),
+    /// Retrieves the number of unique inputs recorded.
+    pub len: unsafe extern "C" fn(state: *const c_void) -> u64,
+    /// Releases the state of a filter.
+    pub destroy: unsafe extern "C" fn(state: *mut c_void),
+}
+
+impl RuniqPlugin {
+    /// Creates a plugin table for a Rust `Filter` implementation.
+    ///
+    /// The `create` function must return a pointer to a boxed `F`, such
+    /// as via `Box::into_raw`. This is used by `export_plugin!`.
+    pub const fn of<F: Filter>(create: unsafe extern "C" fn(u64) -> *mut c_void) -> Self {
+        Self {
+            abi_version: RUNIQ_PLUGIN_ABI_VERSION,
+            create,
+            detect: plugin_detect::<F>,
+            contains: plugin_contains::<F>,
+            clear: plugin_clear::<F>,
+            len: plugin_len::<F>,
+            destroy: plugin_destroy::<F>,
+        }
+    }
+}
+
+/// Exports a `Filter` implementation as a plugin for the CLI.
+///
+/// This generates the `runiq_plugin` function loaded by `--plugin`, using
+/// a constructor which receives the hash seed provided to the CLI. The
+/// crate must be built with a `crate-type` of `cdylib`.
+///
+/// ```ignore
+/// runiq::export_plugin!(MyFilter, MyFilter::with_seed);
+/// ```
+#[macro_export]
+macro_rules! export_plugin {
+    ($filter:ty, $create:expr) => {
+        #[no_mangle]
+        pub extern "C" fn runiq_plugin() -> *const $crate::ffi::RuniqPlugin {
+            unsafe extern "C" fn create(seed: u64) -> *mut ::std::ffi::c_void {
+                let create: fn(u64) -> $filter = $create;
+                ::std::boxed::Box::into_raw(::std::boxed::Box::new(create(seed))) as *mut _
+            }
+            static PLUGIN: $crate::ffi::RuniqPlugin =
+                $crate::ffi::RuniqPlugin::of::<$filter>(create);
+            &PLUGIN
+        }
+    };
+}
+
+/// Filter implementation backed by a plugin's function table.
+pub struct PluginFilter {
+    plugin: &'static RuniqPlugin,
+    state: *mut c_void,
+}
+
+impl PluginFilter {
+    /// Creates a new `PluginFilter` from a plugin table and hash seed.
+    ///
+    /// # Safety
+    ///
+    /// All functions within the table must be safe to call with the
+    /// state returned by `create`, as described on `RuniqPlugin`.
+    pub unsafe fn new(plugin: &'static RuniqPlugin, seed: u64) -> io::Result<Self> {
+        if plugin.abi_version != RUNIQ_PLUGIN_ABI_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "plugin version {} does not match version {}",
+                    plugin.abi_version, RUNIQ_PLUGIN_ABI_VERSION
+                ),
+            ));
+        }
+
+        let state = (plugin.create)(seed);
+        if state.is_null() {
+            return Err(io::Error::other("plugin could not create a filter"));
+        }
+
+        Ok(Self { plugin, state })
+    }
+}
+
+impl Filter for PluginFilter {
+    /// Detects a unique value via the plugin.
+    #[inline]
+    fn detect(&mut self, input: &[u8]) -> bool {
+        unsafe { (self.plugin.detect)(self.state, input.as_ptr(), input.len()) }
+    }
+
+    /// Checks for a previously seen value via the plugin.
+    #[inline]
+    fn contains(&self, input: &[u8]) -> bool {
+        unsafe { (self.plugin.contains)(self.state, input.as_ptr(), input.len()) }
+    }
+
+    /// Removes all values from the filter via the plugin.
+    fn clear(&mut self) {
+        unsafe { (self.plugin.clear)(self.state) }
+    }
+
+    /// Retrieves the number of unique values via the plugin.
+    fn len(&self) -> u64 {
+        unsafe { (self.plugin.len)(self.state) }
+    }
+}
+
+impl Drop for PluginFilter {
+    /// Releases the state of the filter via the plugin.
+    fn drop(&mut self) {
+        unsafe { (self.plugin.destroy)(self.state) }
+    }
+}
+
+/// Opaque filter handle passed across the C boundary.
+pub struct RuniqFilter(Box<dyn Filter>);
+
+/// Creates a new filter from the name of a filter kind.
+///
+/// Names match those accepted by the CLI. A null pointer is returned
+/// if the name is not recognised, or the filter cannot be created.
+///
+/// # Safety
+///
+/// The `kind` pointer must be a valid, nul terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn runiq_filter_new(kind: *const c_char) -> *mut RuniqFilter {
+    if kind.is_null() {
+        return ptr::null_mut();
+    }
+
+    let name = match CStr::from_ptr(kind).to_str() {
+        Ok(name) => name,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let filter: Box<dyn Filter> = match Filters::from_name(name) {
+        None => return ptr::null_mut(),
+        Some(kind) => match kind.try_into_filter() {
+            Ok(filter) => filter,
+            Err(_) => return ptr::null_mut(),
+        },
+    };
+
+    Box::into_raw(Box::new(RuniqFilter(filter)))
+}
+
+/// Detects if an input is unique, returning `true` if so.
+///
+/// # Safety
+///
+/// The `filter` pointer must have been returned by `runiq_filter_new`
+/// and not yet freed, and `bytes` must be valid for reads of `len`
+/// bytes (it may be null if `len` is zero).
+#[no_mangle]
+pub unsafe extern "C" fn runiq_filter_detect(
+    filter: *mut RuniqFilter,
+    bytes: *const u8,
+    len: usize,
+) -> bool {
+    (*filter).0.detect(input(bytes, len))
+}
+
+/// Releases a filter created by `runiq_filter_new`.
+///
+/// # Safety
+///
+/// The `filter` pointer must have been returned by `runiq_filter_new`
+/// and not yet freed. Null pointers are ignored.
+#[no_mangle]
+pub unsafe extern "C" fn runiq_filter_free(filter: *mut RuniqFilter) {
+    if !filter.is_null() {
+        drop(Box::from_raw(filter));
+    }
+}
+
+/// Detects a unique value within the state of a plugin.
+unsafe extern "C" fn plugin_detect<F: Filter>(
+    state: *mut c_void,
+    bytes: *const u8,
+    len: usize,
+) -> bool {
+    (*(state as *mut F)).detect(input(bytes, len))
+}
+
+/// Checks for a previously seen value within the state of a plugin.
+unsafe extern "C" fn plugin_contains<F: Filter>(
+    state: *const c_void,
+    bytes: *const u8,
+    len: usize,
+) -> bool {
+    (*(state as *const F)).contains(input(bytes, len))
+}
+
+/// Clears the state of a plugin.
+unsafe extern "C" fn plugin_clear<F: Filter>(state: *mut c_void) {
+    (*(state as *mut F)).clear()
+}
+
+/// Retrieves the number of unique values within the state of a plugin.
+unsafe extern "C" fn plugin_len<F: Filter>(state: *const c_void) -> u64 {
+    (*(state as *const F)).len()
+}
+
+/// Releases the state of a plugin.
+unsafe extern "C" fn plugin_destroy<F: Filter>(state: *mut c_void) {
+    drop(Box::from_raw(state as *mut F))
+}
+
+/// Creates an input slice from a pointer and length, which may be null.
+unsafe fn input<'a>(bytes: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(bytes, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::QuickFilter;
+    use std::ffi::CString;
+
+    #[test]
+    fn filter_lifecycle() {
+        let kind = CString::new("quick").unwrap();
+
+        unsafe {
+            let filter = runiq_filter_new(kind.as_ptr());
+            assert!(!filter.is_null());
+
+            let ins1 = runiq_filter_detect(filter, b"input1".as_ptr(), 6);
+            let ins2 = runiq_filter_detect(filter, b"input1".as_ptr(), 6);
+            let ins3 = runiq_filter_detect(filter, ptr::null(), 0);
+
+            assert!(ins1);
+            assert!(!ins2);
+            assert!(ins3);
+
+            runiq_filter_free(filter);
+        }
+    }
+
+    #[test]
+    fn unknown_filter_kind() {
+        let kind = CString::new("missing").unwrap();
+
+        unsafe {
+            assert!(runiq_filter_new(kind.as_ptr()).is_null());
+            assert!(runiq_filter_new(ptr::null()).is_null());
+        }
+    }
+
+    #[test]
+    fn plugin_filter_detection() {
+        unsafe extern "C" fn create(seed: u64) -> *mut c_void {
+            Box::into_raw(Box::new(QuickFilter::with_seed(seed))) as *mut _
+        }
+        static PLUGIN: RuniqPlugin = RuniqPlugin::of::<QuickFilter>(create);
+
+        let mut filter = unsafe { PluginFilter::new(&PLUGIN, 1) }.unwrap();
+
+        assert!(filter.detect(b"input1"));
+        assert!(!filter.detect(b"input1"));
+        assert!(filter.contains(b"input1"));
+        assert_eq!(filter.len(), 1);
+
+        filter.clear();
+
+        assert!(filter.is_empty());
+    }
+}
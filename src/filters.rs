@@ -5,17 +5,60 @@
 //!
 //! Please see the struct documentation for further information on
 //! each filter, including their runtime characteristics.
+#[cfg(feature = "std")]
 use growable_bloom_filter::{GrowableBloom, GrowableBloomBuilder};
 use identity_hash::BuildIdentityHasher;
+#[cfg(feature = "std")]
 use strum_macros::EnumString;
-use xxhash_rust::xxh3::xxh3_64;
+#[cfg(feature = "std")]
+use unicode_normalization::UnicodeNormalization;
+#[cfg(feature = "std")]
+use xxhash_rust::xxh3::xxh3_128_with_seed;
+use xxhash_rust::xxh3::xxh3_64_with_seed;
 
-use std::collections::HashSet;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, vec::Vec};
+use core::hash::{BuildHasher, Hasher};
+use core::mem;
+#[cfg(not(feature = "std"))]
+use hashbrown::HashSet;
+
+#[cfg(feature = "std")]
+use std::any::Any;
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet, VecDeque};
+#[cfg(feature = "std")]
+use std::convert::TryInto;
+#[cfg(feature = "std")]
+use std::env;
+#[cfg(feature = "std")]
+use std::fs::{self, File, OpenOptions};
+#[cfg(feature = "std")]
+use std::hash::BuildHasherDefault;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Seek, SeekFrom, Write};
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "std")]
+use std::process;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use std::sync::{Mutex, PoisonError};
+#[cfg(feature = "std")]
+use std::thread;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
 
 /// Trait for any type which can be used to filter unique values.
 ///
-/// The filter only supports a single operation to detect a unique input
-/// which will provide the ability to check/insert in a single operation.
+/// The filter primarily supports a single operation to detect a unique
+/// input which will provide the ability to check/insert in a single
+/// operation. Filters can optionally support saving and loading their
+/// state, to continue filtering across separate runs.
+///
+/// When the `serde` feature is enabled, the `SimpleFilter`, `QuickFilter`
+/// and `CompactFilter` types can also be serialized via `serde`.
 pub trait Filter {
     /// Detects a unique value.
     ///
@@ -23,6 +66,100 @@ pub trait Filter {
     /// was added to the internal filter or not (i.e. `true` if
     /// this is the first time the value has been seen).
     fn detect(&mut self, input: &[u8]) -> bool;
+
+    /// Detects a batch of unique values.
+    ///
+    /// This is equivalent to calling `detect` on each value in order,
+    /// but allows filters to avoid per-call overhead (such as hashing
+    /// all values up front, before checking any of them).
+    fn detect_batch(&mut self, inputs: &[&[u8]]) -> Vec<bool> {
+        inputs.iter().map(|input| self.detect(input)).collect()
+    }
+
+    /// Detects a unique value, returning any I/O errors raised.
+    ///
+    /// Filters backed by storage (such as the `DiskFilter`) panic when
+    /// detection fails, so they override this to return the error; all
+    /// other filters simply use `detect`.
+    #[cfg(feature = "std")]
+    fn try_detect(&mut self, input: &[u8]) -> io::Result<bool> {
+        Ok(self.detect(input))
+    }
+
+    /// Checks whether a value has been seen, without inserting it.
+    ///
+    /// This is the same check made by `detect`, so for filters which can
+    /// produce false positives, this can also produce false positives.
+    fn contains(&self, input: &[u8]) -> bool;
+
+    /// Removes all values from the filter.
+    ///
+    /// Filters keep any allocated memory where possible, so clearing is
+    /// cheaper than creating a new filter when filtering multiple batches.
+    fn clear(&mut self);
+
+    /// Retrieves the number of unique values in the filter.
+    ///
+    /// For filters which can produce false positives this is an estimate,
+    /// and filters which forget values only count the values remembered.
+    fn len(&self) -> u64;
+
+    /// Checks whether the filter contains no values.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Retrieves the approximate memory used by the filter, in bytes.
+    ///
+    /// This is an estimate based on the size of the structures held by
+    /// the filter. Filters which do not keep their values in memory will
+    /// return `None`.
+    fn memory(&self) -> Option<u64> {
+        None
+    }
+
+    /// Reserves capacity for at least `additional` more unique values.
+    ///
+    /// This is only a hint, used to avoid repeatedly growing the filter
+    /// when the number of uniques is known ahead of time. Filters which
+    /// cannot grow in advance will ignore it.
+    fn reserve(&mut self, _additional: usize) {}
+
+    /// Writes the state of the filter to a writer.
+    ///
+    /// Filters which do not support saving state will return an error
+    /// with a kind of `io::ErrorKind::Unsupported`.
+    #[cfg(feature = "std")]
+    fn save_state(&self, _writer: &mut dyn Write) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Replaces the state of the filter with state from a reader.
+    ///
+    /// State must have been written by `save_state` on a filter of the
+    /// same type. Filters which do not support loading state will return
+    /// an error with a kind of `io::ErrorKind::Unsupported`.
+    #[cfg(feature = "std")]
+    fn load_state(&mut self, _reader: &mut dyn Read) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Trait for filters which can be merged with another filter.
+///
+/// Merging adds all values of another filter of the same type, so that
+/// filters built independently (such as per thread, or per shard) can be
+/// combined into a single filter. Bloom filters can't be merged once they
+/// have grown, so the `CompactFilter` does not support merging.
+pub trait MergeableFilter: Filter {
+    /// Merges all values from another filter into this filter.
+    ///
+    /// # Panics
+    ///
+    /// Hashes are only comparable when using the same seed, so filters
+    /// which hash values will panic if they were created with different
+    /// seeds.
+    fn merge(&mut self, other: Self);
 }
 
 /// Basic filter implementation backed by a `HashSet`.
@@ -33,15 +170,74 @@ pub trait Filter {
 /// efficient, but it is guaranteed to be completely accurate when
 /// calculating unique collisions in inputs.
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleFilter {
     inner: HashSet<Vec<u8>>,
 }
 
+impl SimpleFilter {
+    /// Creates a new `SimpleFilter` with space for `capacity` uniques.
+    ///
+    /// The filter will not need to grow until this many uniques are found.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut filter = Self::default();
+        filter.inner.reserve(capacity);
+        filter
+    }
+}
+
 /// Implement all trait methods.
 impl Filter for SimpleFilter {
     fn detect(&mut self, input: &[u8]) -> bool {
         self.inner.insert(input.to_vec())
     }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        self.inner.contains(input)
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    fn len(&self) -> u64 {
+        self.inner.len() as u64
+    }
+
+    fn memory(&self) -> Option<u64> {
+        let values = self.inner.iter().map(Vec::capacity).sum::<usize>() as u64;
+        Some(table_memory::<Vec<u8>>(self.inner.capacity()) + values)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+
+    #[cfg(feature = "std")]
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u64(writer, self.inner.len() as u64)?;
+        for value in &self.inner {
+            write_bytes(writer, value)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let len = read_u64(reader)?;
+        self.inner.clear();
+        for _ in 0..len {
+            self.inner.insert(read_bytes(reader)?);
+        }
+        Ok(())
+    }
+}
+
+/// Implement merging via set union.
+impl MergeableFilter for SimpleFilter {
+    fn merge(&mut self, other: Self) {
+        self.inner.extend(other.inner)
+    }
 }
 
 /// Digest filter implementation backed by a `HashSet`.
@@ -53,165 +249,3066 @@ impl Filter for SimpleFilter {
 /// It's also a little faster due to some improved efficiency
 /// when comparing values in the set itself, but it's not of any
 /// real consequence and is barely noticeable.
-#[derive(Clone, Debug, Default)]
-pub struct QuickFilter {
+///
+/// Values are hashed using xxh3 by default, although any hash function
+/// can be used by creating the filter via `QuickFilter::with_hasher`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuickFilter<S = Xxh3Builder> {
     inner: HashSet<u64, BuildIdentityHasher<u64>>,
+    hasher: S,
 }
 
-/// Implement all trait methods.
-impl Filter for QuickFilter {
-    fn detect(&mut self, input: &[u8]) -> bool {
-        self.inner.insert(xxh3_64(input))
+impl QuickFilter {
+    /// Creates a new `QuickFilter` using a custom hash seed.
+    ///
+    /// Seeding the hash makes it much harder for an adversary to craft
+    /// inputs which collide; the default seed is `0`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_hasher(Xxh3Builder::with_seed(seed))
+    }
+
+    /// Creates a new `QuickFilter` with space for `capacity` uniques.
+    ///
+    /// The filter will not need to grow until this many uniques are found.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut filter = Self::default();
+        filter.inner.reserve(capacity);
+        filter
     }
 }
 
-/// Uniq filter implementation to only remove consecutive duplicates.
-///
-/// This is the fastest filter (although not by much), and the best in
-/// terms of memory efficiency as it only requires a single value stored
-/// in the filter memory at once. It operates in the same was as the Unix
-/// `uniq` utility, and thus requires your data be sorted prior to any
-/// execution.
-///
-/// Remember that repeatedly running Runiq on the same input would be
-/// a good candidate for sorting your data initially and then making
-/// use of this filter to optimize memory usage going forward.
-#[derive(Clone, Debug, Default)]
-pub struct SortedFilter {
-    inner: Vec<u8>,
+impl<S: BuildHasher> QuickFilter<S> {
+    /// Creates a new `QuickFilter` using a custom hash function.
+    ///
+    /// Inputs are written to the hasher as raw bytes, so any `BuildHasher`
+    /// can be used (such as `RandomState` for a keyed SipHash). Saving and
+    /// loading state is only supported when using the default hasher.
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            inner: HashSet::default(),
+            hasher,
+        }
+    }
+
+    /// Hashes an input using the configured hash function.
+    fn hash(&self, input: &[u8]) -> u64 {
+        let mut hasher = self.hasher.build_hasher();
+        hasher.write(input);
+        hasher.finish()
+    }
+}
+
+impl Default for QuickFilter {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
 }
 
 /// Implement all trait methods.
-impl Filter for SortedFilter {
+impl<S: BuildHasher + 'static> Filter for QuickFilter<S> {
     fn detect(&mut self, input: &[u8]) -> bool {
-        // check for consec collision
-        if input == &self.inner[..] {
-            return false;
+        let hash = self.hash(input);
+        self.inner.insert(hash)
+    }
+
+    fn detect_batch(&mut self, inputs: &[&[u8]]) -> Vec<bool> {
+        let hashes: Vec<u64> = inputs.iter().map(|input| self.hash(input)).collect();
+        hashes
+            .into_iter()
+            .map(|hash| self.inner.insert(hash))
+            .collect()
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        self.inner.contains(&self.hash(input))
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    fn len(&self) -> u64 {
+        self.inner.len() as u64
+    }
+
+    fn memory(&self) -> Option<u64> {
+        Some(table_memory::<u64>(self.inner.capacity()))
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+
+    #[cfg(feature = "std")]
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        // only the seed of the default hasher can be restored on load
+        let seed = match (&self.hasher as &dyn Any).downcast_ref::<Xxh3Builder>() {
+            Some(hasher) => hasher.seed,
+            None => return Err(io::ErrorKind::Unsupported.into()),
+        };
+        write_u64(writer, seed)?;
+        write_u64(writer, self.inner.len() as u64)?;
+        for hash in &self.inner {
+            write_u64(writer, *hash)?;
         }
+        Ok(())
+    }
 
-        // overwrite the previous value
-        self.inner = input.to_vec();
-        true
+    #[cfg(feature = "std")]
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let hasher = match (&mut self.hasher as &mut dyn Any).downcast_mut::<Xxh3Builder>() {
+            Some(hasher) => hasher,
+            None => return Err(io::ErrorKind::Unsupported.into()),
+        };
+        hasher.seed = read_u64(reader)?;
+        let len = read_u64(reader)?;
+        self.inner.clear();
+        for _ in 0..len {
+            self.inner.insert(read_u64(reader)?);
+        }
+        Ok(())
     }
 }
 
-/// Bitset filter backed by a scalable Bloom Filter.
-///
-/// This filter operates with the least amount of memory, with a cost
-/// of speed (roughly 60-70% of the speed of the `QuickFilter`, using
-/// only 25% of the memory).
+/// Implement merging via set union.
+impl<S: BuildHasher + PartialEq + 'static> MergeableFilter for QuickFilter<S> {
+    fn merge(&mut self, other: Self) {
+        assert!(
+            self.hasher == other.hasher,
+            "filters must use the same seed"
+        );
+        self.inner.extend(other.inner)
+    }
+}
+
+/// Digest filter implementation backed by a `HashSet` of 128-bit hashes.
 ///
-/// The backing bloom filter initializes with `1e6` bits by default, with
-/// `1e-7` probability of collisions. This is roughly comparable to the
-/// collision rate of the digest filter, so this should be chosen when
-/// memory is critical.
-#[derive(Debug)]
-pub struct CompactFilter {
-    inner: GrowableBloom,
+/// This works in the same way as the `QuickFilter`, but stores 128-bit
+/// hashes rather than 64-bit hashes. This doubles the memory required per
+/// entry, but makes collisions negligible even across tens of billions of
+/// entries (where 64-bit hashes can no longer be relied on).
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct Quick128Filter {
+    inner: HashSet<u128, BuildHasherDefault<DigestHasher>>,
+    seed: u64,
 }
 
-impl Default for CompactFilter {
-    fn default() -> Self {
+#[cfg(feature = "std")]
+impl Quick128Filter {
+    /// Creates a new `Quick128Filter` using a custom hash seed.
+    pub fn with_seed(seed: u64) -> Self {
         Self {
-            inner: GrowableBloomBuilder::new()
-                .estimated_insertions(1_000_000)
-                .desired_error_ratio(1e-8)
-                .growth_factor(2)
-                .tightening_ratio(0.5)
-                .build(),
+            seed,
+            ..Self::default()
         }
     }
 }
 
 /// Implement all trait methods.
-impl Filter for CompactFilter {
+#[cfg(feature = "std")]
+impl Filter for Quick128Filter {
     fn detect(&mut self, input: &[u8]) -> bool {
-        self.inner.insert(xxh3_64(input))
+        self.inner.insert(xxh3_128_with_seed(input, self.seed))
+    }
+
+    fn detect_batch(&mut self, inputs: &[&[u8]]) -> Vec<bool> {
+        let hashes: Vec<u128> = inputs
+            .iter()
+            .map(|input| xxh3_128_with_seed(input, self.seed))
+            .collect();
+        hashes
+            .into_iter()
+            .map(|hash| self.inner.insert(hash))
+            .collect()
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        self.inner.contains(&xxh3_128_with_seed(input, self.seed))
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    fn len(&self) -> u64 {
+        self.inner.len() as u64
+    }
+
+    fn memory(&self) -> Option<u64> {
+        Some(table_memory::<u128>(self.inner.capacity()))
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u64(writer, self.seed)?;
+        write_u64(writer, self.inner.len() as u64)?;
+        for hash in &self.inner {
+            writer.write_all(&hash.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let seed = read_u64(reader)?;
+        let len = read_u64(reader)?;
+        let mut bytes = [0; 16];
+        self.inner.clear();
+        for _ in 0..len {
+            reader.read_exact(&mut bytes)?;
+            self.inner.insert(u128::from_le_bytes(bytes));
+        }
+        self.seed = seed;
+        Ok(())
     }
 }
 
-/// Enum to store all possible variants of filters.
+/// Implement merging via set union.
+#[cfg(feature = "std")]
+impl MergeableFilter for Quick128Filter {
+    fn merge(&mut self, other: Self) {
+        assert_eq!(self.seed, other.seed, "filters must use the same seed");
+        self.inner.extend(other.inner)
+    }
+}
+
+/// Hasher for values which are already hashed, such as 128-bit digests.
 ///
-/// This will implement the `Into` trait in order to create a new
-/// boxed filter from a filter kind to keep conversion contained.
-#[derive(Copy, Clone, Debug, EnumString)]
-#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
-pub enum Filters {
-    /// Hashed comparisons with more efficient throughput.
-    Quick,
+/// The low bits of a digest are already uniformly distributed, so there is
+/// no need to hash them again when storing them in a set.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Default)]
+struct DigestHasher(u64);
 
-    /// Naive comparisons of strings within a set.
-    Simple,
+/// Implement `Hasher` to pass digests through.
+#[cfg(feature = "std")]
+impl Hasher for DigestHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        // digests are written via write_u128, so this is only a fallback
+        self.0 = xxh3_64_with_seed(bytes, self.0);
+    }
 
-    /// Adjacent comparisons on sorted data (similar to uniq).
-    Sorted,
+    fn write_u128(&mut self, value: u128) {
+        self.0 = value as u64;
+    }
 
-    /// Bloom filter comparisons with compact memory usage.
-    Compact,
+    fn finish(&self) -> u64 {
+        self.0
+    }
 }
 
-/// Implement `From` to convert to `Filter`.
-impl From<Filters> for Box<dyn Filter> {
-    /// Creates a new `Filter` type based on the enum value.
-    fn from(kind: Filters) -> Self {
-        match kind {
-            Filters::Quick => Box::<QuickFilter>::default(),
-            Filters::Simple => Box::<SimpleFilter>::default(),
-            Filters::Compact => Box::<CompactFilter>::default(),
-            Filters::Sorted => Box::<SortedFilter>::default(),
-        }
+/// Hash function used by default in the `QuickFilter`, based on xxh3.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Xxh3Builder {
+    seed: u64,
+}
+
+impl Xxh3Builder {
+    /// Creates a new `Xxh3Builder` using a custom hash seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self { seed }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Implement `BuildHasher` to create seeded hashers.
+impl BuildHasher for Xxh3Builder {
+    type Hasher = Xxh3Hasher;
 
-    #[test]
-    fn naive_filter_detection() {
-        let mut filter = SimpleFilter::default();
+    fn build_hasher(&self) -> Self::Hasher {
+        Xxh3Hasher { hash: self.seed }
+    }
+}
 
-        let ins1 = filter.detect(b"input1");
-        let ins2 = filter.detect(b"input1");
+/// Hasher created by an `Xxh3Builder`.
+///
+/// Each write is hashed in a single pass, using the hash of any previous
+/// writes as the seed, so a single write matches hashing the input directly.
+#[derive(Copy, Clone, Debug)]
+pub struct Xxh3Hasher {
+    hash: u64,
+}
 
-        assert!(ins1);
-        assert!(!ins2);
+/// Implement `Hasher` to hash via xxh3.
+impl Hasher for Xxh3Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.hash = xxh3_64_with_seed(bytes, self.hash);
     }
 
-    #[test]
-    fn digest_filter_detection() {
-        let mut filter = QuickFilter::default();
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
 
-        let ins1 = filter.detect(b"input1");
-        let ins2 = filter.detect(b"input1");
+/// Digest filter implementation which can be shared across threads.
+///
+/// Hashes are stored in the same way as the `QuickFilter`, but are split
+/// across a number of shards (based on a prefix of the hash), each behind
+/// a separate lock. This allows detection via a shared reference, and as
+/// threads will rarely contend for the same shard, detection scales with
+/// the number of threads far better than a single filter behind a lock.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ConcurrentFilter {
+    shards: Vec<Mutex<HashSet<u64, BuildIdentityHasher<u64>>>>,
+    seed: u64,
+}
 
-        assert!(ins1);
-        assert!(!ins2);
+#[cfg(feature = "std")]
+impl ConcurrentFilter {
+    /// Creates a new `ConcurrentFilter` using a custom hash seed.
+    ///
+    /// The number of shards is based on the parallelism available.
+    pub fn with_seed(seed: u64) -> Self {
+        let threads = thread::available_parallelism().map_or(1, usize::from);
+        Self::with_shards(threads * 4, seed)
     }
 
-    #[test]
-    fn sorted_filter_detection() {
-        let mut filter = SortedFilter::default();
+    /// Creates a new `ConcurrentFilter` with a number of shards.
+    ///
+    /// The number of shards is rounded up to the next power of two.
+    pub fn with_shards(shards: usize, seed: u64) -> Self {
+        let shards = (0..shards.max(1).next_power_of_two())
+            .map(|_| Mutex::default())
+            .collect();
 
-        let ins1 = filter.detect(b"input1");
-        let ins2 = filter.detect(b"input1");
-        let ins3 = filter.detect(b"input2");
-        let ins4 = filter.detect(b"input1");
+        Self { shards, seed }
+    }
 
-        assert!(ins1);
-        assert!(!ins2);
-        assert!(ins3);
-        assert!(ins4);
+    /// Detects a unique value via a shared reference.
+    pub fn detect(&self, input: &[u8]) -> bool {
+        let hash = xxh3_64_with_seed(input, self.seed);
+        self.shard(hash)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(hash)
     }
 
-    #[test]
-    fn bloom_filter_detection() {
-        let mut filter = CompactFilter::default();
+    /// Retrieves the shard responsible for a hash.
+    fn shard(&self, hash: u64) -> &Mutex<HashSet<u64, BuildIdentityHasher<u64>>> {
+        let bits = self.shards.len().trailing_zeros();
+        let index = hash.checked_shr(64 - bits).unwrap_or(0);
+        &self.shards[index as usize]
+    }
 
-        let ins1 = filter.detect(b"input1");
-        let ins2 = filter.detect(b"input1");
+    /// Retrieves mutable access to all shards, ignoring any poisoning.
+    fn shards_mut(
+        &mut self,
+    ) -> impl Iterator<Item = &mut HashSet<u64, BuildIdentityHasher<u64>>> + '_ {
+        self.shards
+            .iter_mut()
+            .map(|shard| shard.get_mut().unwrap_or_else(PoisonError::into_inner))
+    }
+}
 
-        assert!(ins1);
-        assert!(!ins2);
+#[cfg(feature = "std")]
+impl Default for ConcurrentFilter {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+/// Implement all trait methods.
+#[cfg(feature = "std")]
+impl Filter for ConcurrentFilter {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        ConcurrentFilter::detect(self, input)
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        let hash = xxh3_64_with_seed(input, self.seed);
+        self.shard(hash)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(&hash)
+    }
+
+    fn clear(&mut self) {
+        self.shards_mut().for_each(HashSet::clear)
+    }
+
+    fn len(&self) -> u64 {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap_or_else(PoisonError::into_inner).len() as u64)
+            .sum()
+    }
+
+    fn memory(&self) -> Option<u64> {
+        let shards = self.shards.iter().map(|shard| {
+            let shard = shard.lock().unwrap_or_else(PoisonError::into_inner);
+            table_memory::<u64>(shard.capacity())
+        });
+        Some(shards.sum())
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        let additional = additional / self.shards.len() + 1;
+        self.shards_mut()
+            .for_each(|shard| shard.reserve(additional))
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u64(writer, self.seed)?;
+        write_u64(writer, self.len())?;
+        for shard in &self.shards {
+            for hash in shard.lock().unwrap_or_else(PoisonError::into_inner).iter() {
+                write_u64(writer, *hash)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let seed = read_u64(reader)?;
+        let len = read_u64(reader)?;
+        self.clear();
+        self.seed = seed;
+        for _ in 0..len {
+            let hash = read_u64(reader)?;
+            self.shard(hash)
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(hash);
+        }
+        Ok(())
+    }
+}
+
+/// Digest filter implementation which counts occurrences of values.
+///
+/// Hashes are stored in the same way as the `QuickFilter`, alongside a
+/// count of how many times each value has been detected. This requires
+/// an extra 8 bytes per unique value, but allows `count` to be used to
+/// check how many times a value has been seen.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct CountingFilter {
+    inner: HashMap<u64, u64, BuildIdentityHasher<u64>>,
+    seed: u64,
+}
+
+#[cfg(feature = "std")]
+impl CountingFilter {
+    /// Creates a new `CountingFilter` using a custom hash seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Self::default()
+        }
+    }
+
+    /// Retrieves the number of times a value has been detected.
+    pub fn count(&self, input: &[u8]) -> u64 {
+        let hash = xxh3_64_with_seed(input, self.seed);
+        self.inner.get(&hash).copied().unwrap_or(0)
+    }
+}
+
+/// Implement all trait methods.
+#[cfg(feature = "std")]
+impl Filter for CountingFilter {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        let count = self
+            .inner
+            .entry(xxh3_64_with_seed(input, self.seed))
+            .or_insert(0);
+        *count += 1;
+        *count == 1
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        self.count(input) > 0
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    fn len(&self) -> u64 {
+        self.inner.len() as u64
+    }
+
+    fn memory(&self) -> Option<u64> {
+        Some(table_memory::<(u64, u64)>(self.inner.capacity()))
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u64(writer, self.seed)?;
+        write_u64(writer, self.inner.len() as u64)?;
+        for (hash, count) in &self.inner {
+            write_u64(writer, *hash)?;
+            write_u64(writer, *count)?;
+        }
+        Ok(())
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let seed = read_u64(reader)?;
+        let len = read_u64(reader)?;
+        self.inner.clear();
+        for _ in 0..len {
+            let hash = read_u64(reader)?;
+            self.inner.insert(hash, read_u64(reader)?);
+        }
+        self.seed = seed;
+        Ok(())
+    }
+}
+
+/// Implement merging by summing counts.
+#[cfg(feature = "std")]
+impl MergeableFilter for CountingFilter {
+    fn merge(&mut self, other: Self) {
+        assert_eq!(self.seed, other.seed, "filters must use the same seed");
+        for (hash, count) in other.inner {
+            *self.inner.entry(hash).or_insert(0) += count;
+        }
+    }
+}
+
+/// Estimating filter implementation backed by a HyperLogLog sketch.
+///
+/// This filter is unable to detect unique values, and only exists to
+/// estimate the number of unique values via `len`. Only a single byte
+/// per register is required (16KB using the default precision of `14`),
+/// with a typical error of roughly `1.04 / sqrt(2^precision)`, so under
+/// 1% by default.
+///
+/// As values are not stored, `detect` only signals whether a value has
+/// changed the sketch, and `contains` whether a value could not change
+/// the sketch; neither should be used to filter values.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct EstimateFilter {
+    registers: Vec<u8>,
+    precision: u32,
+    seed: u64,
+}
+
+#[cfg(feature = "std")]
+impl EstimateFilter {
+    /// Creates a new `EstimateFilter` using a custom precision.
+    ///
+    /// Each step in precision doubles the memory required, in exchange
+    /// for a more accurate estimate.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the precision is not within the range `4..=18`.
+    pub fn with_precision(precision: u32) -> Self {
+        assert!(
+            (4..=18).contains(&precision),
+            "precision must be within the range 4..=18"
+        );
+        Self {
+            registers: vec![0; 1 << precision],
+            precision,
+            seed: 0,
+        }
+    }
+
+    /// Creates a new `EstimateFilter` using a custom hash seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Self::default()
+        }
+    }
+
+    /// Retrieves the register index and rank for an input.
+    fn locate(&self, input: &[u8]) -> (usize, u8) {
+        let hash = xxh3_64_with_seed(input, self.seed);
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rank = ((hash << self.precision) | (1 << (self.precision - 1))).leading_zeros() + 1;
+        (index, rank as u8)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for EstimateFilter {
+    fn default() -> Self {
+        Self::with_precision(14)
+    }
+}
+
+/// Implement all trait methods.
+#[cfg(feature = "std")]
+impl Filter for EstimateFilter {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        let (index, rank) = self.locate(input);
+        if rank <= self.registers[index] {
+            return false;
+        }
+        self.registers[index] = rank;
+        true
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        let (index, rank) = self.locate(input);
+        rank <= self.registers[index]
+    }
+
+    fn clear(&mut self) {
+        self.registers.iter_mut().for_each(|register| *register = 0)
+    }
+
+    fn len(&self) -> u64 {
+        let count = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / count);
+
+        let (sum, zeros) = self
+            .registers
+            .iter()
+            .fold((0.0, 0), |(sum, zeros), register| {
+                (
+                    sum + 2f64.powi(-(*register as i32)),
+                    zeros + (*register == 0) as u32,
+                )
+            });
+
+        let estimate = alpha * count * count / sum;
+
+        // use linear counting for small cardinalities, as it's more accurate
+        if estimate <= 2.5 * count && zeros > 0 {
+            return (count * (count / zeros as f64).ln()).round() as u64;
+        }
+
+        estimate.round() as u64
+    }
+
+    fn memory(&self) -> Option<u64> {
+        Some(self.registers.capacity() as u64)
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u64(writer, self.seed)?;
+        write_bytes(writer, &self.registers)
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let seed = read_u64(reader)?;
+        let registers = read_bytes(reader)?;
+        if !registers.len().is_power_of_two() || !(16..=1 << 18).contains(&registers.len()) {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+        self.precision = registers.len().trailing_zeros();
+        self.registers = registers;
+        self.seed = seed;
+        Ok(())
+    }
+}
+
+/// Implement merging via the maximum of each register.
+#[cfg(feature = "std")]
+impl MergeableFilter for EstimateFilter {
+    /// # Panics
+    ///
+    /// Sketches are also only comparable when using the same precision.
+    fn merge(&mut self, other: Self) {
+        assert_eq!(self.seed, other.seed, "filters must use the same seed");
+        assert_eq!(
+            self.precision, other.precision,
+            "filters must use the same precision"
+        );
+        for (register, other) in self.registers.iter_mut().zip(other.registers) {
+            *register = (*register).max(other);
+        }
+    }
+}
+
+/// Uniq filter implementation to only remove consecutive duplicates.
+///
+/// This is the fastest filter (although not by much), and the best in
+/// terms of memory efficiency as it only requires a single value stored
+/// in the filter memory at once. It operates in the same was as the Unix
+/// `uniq` utility, and thus requires your data be sorted prior to any
+/// execution.
+///
+/// Remember that repeatedly running Runiq on the same input would be
+/// a good candidate for sorting your data initially and then making
+/// use of this filter to optimize memory usage going forward.
+#[derive(Clone, Debug, Default)]
+pub struct SortedFilter {
+    inner: Vec<u8>,
+    len: u64,
+}
+
+/// Implement all trait methods.
+impl Filter for SortedFilter {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        // check for consec collision
+        if input == &self.inner[..] {
+            return false;
+        }
+
+        // overwrite the previous value
+        self.inner = input.to_vec();
+        self.len += 1;
+        true
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        input == &self.inner[..]
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear();
+        self.len = 0;
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn memory(&self) -> Option<u64> {
+        Some(self.inner.capacity() as u64)
+    }
+
+    #[cfg(feature = "std")]
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u64(writer, self.len)?;
+        write_bytes(writer, &self.inner)
+    }
+
+    #[cfg(feature = "std")]
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let len = read_u64(reader)?;
+        self.inner = read_bytes(reader)?;
+        self.len = len;
+        Ok(())
+    }
+}
+
+/// Bitset filter backed by a scalable Bloom Filter.
+///
+/// This filter operates with the least amount of memory, with a cost
+/// of speed (roughly 60-70% of the speed of the `QuickFilter`, using
+/// only 25% of the memory).
+///
+/// The backing bloom filter is sized for `1e6` insertions by default, with
+/// `1e-8` probability of collisions. This is roughly comparable to the
+/// collision rate of the digest filter, so this should be chosen when
+/// memory is critical. These parameters can be changed by creating the
+/// filter via `CompactFilter::builder`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompactFilter {
+    inner: GrowableBloom,
+    params: BloomParams,
+    seed: u64,
+}
+
+#[cfg(feature = "std")]
+impl CompactFilter {
+    /// Creates a new `CompactFilterBuilder` to configure a filter.
+    pub fn builder() -> CompactFilterBuilder {
+        CompactFilterBuilder::default()
+    }
+
+    /// Creates a new `CompactFilter` using a custom hash seed.
+    ///
+    /// As false positives are possible in this filter, seeding the hash
+    /// is a good idea when filtering untrusted inputs; the default seed
+    /// is `0`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::builder().seed(seed).build()
+    }
+
+    /// Creates a new `CompactFilter` sized for a number of insertions.
+    ///
+    /// This is a shorthand for `CompactFilter::builder` when only the
+    /// number of insertions needs to change.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the number of insertions is `0`.
+    pub fn with_estimated_insertions(insertions: usize) -> Self {
+        Self::builder().estimated_insertions(insertions).build()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for CompactFilter {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Builder used to configure the parameters of a `CompactFilter`.
+///
+/// Memory usage of the filter is driven by the number of insertions and
+/// the error ratio; each entry requires roughly `-ln(p) / ln(2)^2` bits,
+/// so the default of `1e-8` uses roughly 5 bytes per entry. The memory
+/// for the estimated insertions is allocated up front, with additional
+/// memory allocated as the filter grows past the estimate.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct CompactFilterBuilder {
+    insertions: usize,
+    params: BloomParams,
+    seed: u64,
+}
+
+#[cfg(feature = "std")]
+impl CompactFilterBuilder {
+    /// Sets the estimated number of insertions, defaulting to `1e6`.
+    ///
+    /// The filter is sized for this many entries up front, so estimating
+    /// too low will require the filter to grow, while estimating too high
+    /// will allocate memory which is never used.
+    pub fn estimated_insertions(mut self, insertions: usize) -> Self {
+        self.insertions = insertions;
+        self
+    }
+
+    /// Sets the desired rate of false positives, defaulting to `1e-8`.
+    ///
+    /// Lower rates require more memory per entry, so this should be as
+    /// high as the use case can tolerate.
+    pub fn error_ratio(mut self, ratio: f64) -> Self {
+        self.params.error_ratio = ratio;
+        self
+    }
+
+    /// Sets the factor by which each new internal filter grows.
+    ///
+    /// Larger factors grow the filter in fewer (but larger) steps as
+    /// more entries are found; the default factor is `2`.
+    pub fn growth_factor(mut self, factor: usize) -> Self {
+        self.params.growth_factor = factor;
+        self
+    }
+
+    /// Sets the ratio by which the error rate tightens on each growth.
+    ///
+    /// Each internal filter uses a tighter error rate than the last, to
+    /// keep the overall error rate close to the desired rate; the default
+    /// ratio is `0.5`. Lower ratios require more memory for each growth.
+    pub fn tightening_ratio(mut self, ratio: f64) -> Self {
+        self.params.tightening_ratio = ratio;
+        self
+    }
+
+    /// Sets the seed used when hashing entries, defaulting to `0`.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Creates a new `CompactFilter` from the configured parameters.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the parameters are invalid; see `try_build`
+    /// for the validation applied to each parameter.
+    pub fn build(self) -> CompactFilter {
+        self.try_build().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Creates a new `CompactFilter`, validating the configured parameters.
+    ///
+    /// This will return an error if the error or tightening ratios are not
+    /// within the range `(0, 1)`, if the growth factor is less than `2`, or
+    /// if the number of estimated insertions is `0`.
+    pub fn try_build(self) -> io::Result<CompactFilter> {
+        let invalid = |msg: &str| Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+
+        if !(self.params.error_ratio > 0.0 && self.params.error_ratio < 1.0) {
+            return invalid("error ratio must be within the range (0, 1)");
+        }
+        if !(self.params.tightening_ratio > 0.0 && self.params.tightening_ratio < 1.0) {
+            return invalid("tightening ratio must be within the range (0, 1)");
+        }
+        if self.params.growth_factor < 2 {
+            return invalid("growth factor must be at least 2");
+        }
+        if self.insertions == 0 {
+            return invalid("estimated insertions must be at least 1");
+        }
+
+        Ok(CompactFilter {
+            inner: self.params.bloom(self.insertions),
+            params: self.params,
+            seed: self.seed,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for CompactFilterBuilder {
+    fn default() -> Self {
+        Self {
+            insertions: 1_000_000,
+            params: BloomParams::default(),
+            seed: 0,
+        }
+    }
+}
+
+/// Parameters used to create the bloom filter of a `CompactFilter`.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct BloomParams {
+    error_ratio: f64,
+    growth_factor: usize,
+    tightening_ratio: f64,
+}
+
+#[cfg(feature = "std")]
+impl BloomParams {
+    /// Creates an empty bloom filter sized for a number of insertions.
+    fn bloom(&self, insertions: usize) -> GrowableBloom {
+        GrowableBloomBuilder::new()
+            .estimated_insertions(insertions)
+            .desired_error_ratio(self.error_ratio)
+            .growth_factor(self.growth_factor)
+            .tightening_ratio(self.tightening_ratio)
+            .build()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for BloomParams {
+    fn default() -> Self {
+        Self {
+            error_ratio: 1e-8,
+            growth_factor: 2,
+            tightening_ratio: 0.5,
+        }
+    }
+}
+
+/// Implement all trait methods.
+#[cfg(feature = "std")]
+impl Filter for CompactFilter {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        self.inner.insert(xxh3_64_with_seed(input, self.seed))
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        self.inner.contains(xxh3_64_with_seed(input, self.seed))
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    fn len(&self) -> u64 {
+        self.inner.len() as u64
+    }
+
+    fn memory(&self) -> Option<u64> {
+        bincode::serialized_size(&self.inner).ok()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        // bloom filters can't be resized, so only an empty filter is rebuilt
+        if self.inner.is_empty() && additional > 0 {
+            self.inner = self.params.bloom(additional);
+        }
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u64(writer, self.seed)?;
+        bincode::serialize_into(writer, &self.inner).map_err(invalid)
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let seed = read_u64(reader)?;
+        self.inner = bincode::deserialize_from(reader).map_err(invalid)?;
+        self.seed = seed;
+        Ok(())
+    }
+}
+
+/// Fingerprint filter backed by a scalable Cuckoo Filter.
+///
+/// Like the `CompactFilter`, only a small fingerprint of each value is
+/// stored, so memory usage is far lower than the `QuickFilter` (roughly
+/// 4 bytes per entry). Unlike a bloom filter, the rate of false positives
+/// does not climb as the filter fills up; fingerprints are 32 bits in
+/// buckets of 4, for a rate of roughly `2e-9` per table. Once a table is
+/// full, a new table of double the size is added.
+///
+/// The filter is sized for `1e6` entries by default, which can be changed
+/// by creating the filter via `CuckooFilter::with_capacity`.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct CuckooFilter {
+    tables: Vec<CuckooTable>,
+    len: u64,
+    seed: u64,
+}
+
+#[cfg(feature = "std")]
+impl CuckooFilter {
+    /// Creates a new `CuckooFilter` sized for a number of entries.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_seed(capacity, 0)
+    }
+
+    /// Creates a new `CuckooFilter` using a custom hash seed.
+    ///
+    /// As false positives are possible in this filter, seeding the hash
+    /// is a good idea when filtering untrusted inputs; the default seed
+    /// is `0`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_capacity_and_seed(1_000_000, seed)
+    }
+
+    /// Creates a new `CuckooFilter` sized for a number of entries, using
+    /// a custom hash seed.
+    pub fn with_capacity_and_seed(capacity: usize, seed: u64) -> Self {
+        Self {
+            tables: vec![CuckooTable::new(capacity)],
+            len: 0,
+            seed,
+        }
+    }
+
+    /// Hashes an input, returning the hash alongside the fingerprint.
+    fn fingerprint(&self, input: &[u8]) -> (u64, u32) {
+        let hash = xxh3_64_with_seed(input, self.seed);
+        (hash, ((hash >> 32) as u32).max(1))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for CuckooFilter {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+/// Implement all trait methods.
+#[cfg(feature = "std")]
+impl Filter for CuckooFilter {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        let (hash, fingerprint) = self.fingerprint(input);
+
+        if self
+            .tables
+            .iter()
+            .any(|table| table.contains(hash, fingerprint))
+        {
+            return false;
+        }
+
+        let table = self.tables.last_mut().expect("table must exist");
+
+        if !table.insert(hash, fingerprint) {
+            let mut table = CuckooTable::with_buckets(table.buckets.len() * 2);
+            table.insert(hash, fingerprint);
+            self.tables.push(table);
+        }
+
+        self.len += 1;
+        true
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        let (hash, fingerprint) = self.fingerprint(input);
+        self.tables
+            .iter()
+            .any(|table| table.contains(hash, fingerprint))
+    }
+
+    fn clear(&mut self) {
+        self.tables.truncate(1);
+        self.tables[0].clear();
+        self.len = 0;
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn memory(&self) -> Option<u64> {
+        let buckets = self.tables.iter().map(|table| table.buckets.capacity());
+        Some((buckets.sum::<usize>() * mem::size_of::<[u32; 4]>()) as u64)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        // tables can't be resized, so only an empty filter is rebuilt
+        if self.len == 0 && additional > 0 {
+            self.tables = vec![CuckooTable::new(additional)];
+        }
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u64(writer, self.seed)?;
+        write_u64(writer, self.len)?;
+        write_u64(writer, self.tables.len() as u64)?;
+        for table in &self.tables {
+            let (fingerprint, index) = table.victim.unwrap_or((0, 0));
+            write_u64(writer, fingerprint as u64)?;
+            write_u64(writer, index as u64)?;
+            write_bytes(writer, &table.to_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let seed = read_u64(reader)?;
+        let len = read_u64(reader)?;
+        let count = read_u64(reader)?;
+        let mut tables = Vec::new();
+        for _ in 0..count {
+            let fingerprint = read_u64(reader)? as u32;
+            let index = read_u64(reader)? as usize;
+            let mut table = CuckooTable::from_bytes(&read_bytes(reader)?)?;
+            if fingerprint != 0 {
+                table.victim = Some((fingerprint, index));
+            }
+            tables.push(table);
+        }
+        if tables.is_empty() {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+        self.tables = tables;
+        self.len = len;
+        self.seed = seed;
+        Ok(())
+    }
+}
+
+/// Single table of fingerprints used within a `CuckooFilter`.
+///
+/// Empty slots are represented by a fingerprint of `0`, and the number
+/// of buckets is always a power of two so indexes can be masked.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+struct CuckooTable {
+    buckets: Vec<[u32; 4]>,
+    victim: Option<(u32, usize)>,
+    random: u64,
+}
+
+#[cfg(feature = "std")]
+impl CuckooTable {
+    /// Maximum number of evictions to attempt when inserting.
+    const MAX_KICKS: usize = 500;
+
+    /// Creates a new table sized for a number of entries.
+    fn new(capacity: usize) -> Self {
+        Self::with_buckets((capacity / 4).max(1).next_power_of_two())
+    }
+
+    /// Creates a new table with a number of buckets.
+    fn with_buckets(buckets: usize) -> Self {
+        Self {
+            buckets: vec![[0; 4]; buckets],
+            victim: None,
+            random: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    /// Checks whether a fingerprint exists in the table.
+    fn contains(&self, hash: u64, fingerprint: u32) -> bool {
+        let first = self.index(hash);
+        let second = self.alternate(first, fingerprint);
+
+        self.buckets[first].contains(&fingerprint)
+            || self.buckets[second].contains(&fingerprint)
+            || self.victim == Some((fingerprint, first))
+            || self.victim == Some((fingerprint, second))
+    }
+
+    /// Inserts a fingerprint into the table.
+    ///
+    /// Once an insertion requires too many evictions, the last evicted
+    /// fingerprint is kept aside and this returns `false` for any later
+    /// insertions, to signal that the table is full.
+    fn insert(&mut self, hash: u64, fingerprint: u32) -> bool {
+        if self.victim.is_some() {
+            return false;
+        }
+
+        let first = self.index(hash);
+        let second = self.alternate(first, fingerprint);
+
+        if self.place(first, fingerprint) || self.place(second, fingerprint) {
+            return true;
+        }
+
+        let mut fingerprint = fingerprint;
+        let mut index = if self.random() & 1 == 0 {
+            first
+        } else {
+            second
+        };
+
+        for _ in 0..Self::MAX_KICKS {
+            let slot = (self.random() % 4) as usize;
+            std::mem::swap(&mut fingerprint, &mut self.buckets[index][slot]);
+            index = self.alternate(index, fingerprint);
+            if self.place(index, fingerprint) {
+                return true;
+            }
+        }
+
+        self.victim = Some((fingerprint, index));
+        true
+    }
+
+    /// Removes all fingerprints from the table.
+    fn clear(&mut self) {
+        self.buckets.iter_mut().for_each(|bucket| *bucket = [0; 4]);
+        self.victim = None;
+    }
+
+    /// Places a fingerprint into an empty slot of a bucket, if any.
+    fn place(&mut self, index: usize, fingerprint: u32) -> bool {
+        match self.buckets[index].iter_mut().find(|slot| **slot == 0) {
+            Some(slot) => {
+                *slot = fingerprint;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Retrieves the primary bucket index for a hash.
+    fn index(&self, hash: u64) -> usize {
+        hash as usize & (self.buckets.len() - 1)
+    }
+
+    /// Retrieves the alternate bucket index for a fingerprint.
+    fn alternate(&self, index: usize, fingerprint: u32) -> usize {
+        let offset = (fingerprint as u64).wrapping_mul(0x5bd1_e995) as usize;
+        (index ^ offset) & (self.buckets.len() - 1)
+    }
+
+    /// Generates a pseudo random value used to pick evictions.
+    fn random(&mut self) -> u64 {
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 7;
+        self.random ^= self.random << 17;
+        self.random
+    }
+
+    /// Encodes all buckets as little endian bytes.
+    fn to_bytes(&self) -> Vec<u8> {
+        self.buckets
+            .iter()
+            .flatten()
+            .flat_map(|fingerprint| fingerprint.to_le_bytes())
+            .collect()
+    }
+
+    /// Decodes a table from bytes created by `to_bytes`.
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let buckets = bytes.len() / 16;
+        if buckets * 16 != bytes.len() || !buckets.is_power_of_two() {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+
+        let mut table = Self::with_buckets(buckets);
+        for (index, chunk) in bytes.chunks_exact(4).enumerate() {
+            let fingerprint = u32::from_le_bytes(chunk.try_into().expect("chunk must fit"));
+            table.buckets[index / 4][index % 4] = fingerprint;
+        }
+        Ok(table)
+    }
+}
+
+/// Exact filter implementation backed by a hash table on disk.
+///
+/// This filter stores hashes in the same way as the `QuickFilter`, but
+/// the table of hashes lives in a file rather than in memory. This makes
+/// it possible to filter datasets far larger than the available memory,
+/// at the cost of speed (as every detection requires reading from the
+/// file, although this is typically served by the page cache).
+///
+/// The table is created within a configurable directory, and is removed
+/// when the filter is dropped. As detection cannot fail, any I/O error
+/// raised when accessing the table will cause a panic; `try_detect` can
+/// be used to handle these errors instead.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct DiskFilter {
+    dir: PathBuf,
+    seed: u64,
+    table: DiskTable,
+}
+
+#[cfg(feature = "std")]
+impl DiskFilter {
+    /// Creates a new `DiskFilter` storing its table in a directory.
+    pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        Self::with_seed(dir, 0)
+    }
+
+    /// Creates a new `DiskFilter` in a directory using a custom hash seed.
+    pub fn with_seed<P: AsRef<Path>>(dir: P, seed: u64) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let table = DiskTable::create(&dir, DISK_INITIAL_PAGES)?;
+        Ok(Self { dir, seed, table })
+    }
+
+    /// Grows the table to twice the number of pages.
+    fn grow(&mut self) -> io::Result<()> {
+        let mut table = DiskTable::create(&self.dir, self.table.pages * 2)?;
+        for hash in self.table.hashes()? {
+            table.insert(hash?)?;
+        }
+        self.table = table;
+        Ok(())
+    }
+
+    /// Detects a unique value, returning any I/O errors raised.
+    pub fn try_detect(&mut self, input: &[u8]) -> io::Result<bool> {
+        // grow the table once half full, to keep probe sequences short
+        if self.table.len * 2 >= self.table.pages * DISK_PAGE_SLOTS {
+            self.grow()?;
+        }
+
+        // zero is used to mark an empty slot, so it can't be stored
+        let hash = xxh3_64_with_seed(input, self.seed).max(1);
+
+        self.table.insert(hash)
+    }
+
+    /// Removes all values from the filter, returning any I/O errors raised.
+    pub fn try_clear(&mut self) -> io::Result<()> {
+        self.table.clear()
+    }
+
+    /// Merges all values from another filter, returning any I/O errors raised.
+    pub fn try_merge(&mut self, other: Self) -> io::Result<()> {
+        self.reserve(other.table.len as usize);
+        for hash in other.table.hashes()? {
+            let hash = hash?;
+            // grow the table once half full, in the same way as detection
+            if self.table.len * 2 >= self.table.pages * DISK_PAGE_SLOTS {
+                self.grow()?;
+            }
+            self.table.insert(hash)?;
+        }
+        Ok(())
+    }
+
+    /// Checks whether a value has been seen, returning any I/O errors raised.
+    pub fn try_contains(&self, input: &[u8]) -> io::Result<bool> {
+        self.table
+            .contains(xxh3_64_with_seed(input, self.seed).max(1))
+    }
+}
+
+/// Implement all trait methods.
+#[cfg(feature = "std")]
+impl Filter for DiskFilter {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        self.try_detect(input)
+            .expect("unable to access disk filter")
+    }
+
+    fn try_detect(&mut self, input: &[u8]) -> io::Result<bool> {
+        DiskFilter::try_detect(self, input)
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        self.try_contains(input)
+            .expect("unable to access disk filter")
+    }
+
+    fn clear(&mut self) {
+        self.try_clear().expect("unable to access disk filter")
+    }
+
+    fn len(&self) -> u64 {
+        self.table.len
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        let pages = pages_for(self.table.len + additional as u64);
+
+        // only an empty table is replaced, and failures keep the current table
+        if self.table.len == 0 && pages > self.table.pages {
+            if let Ok(table) = DiskTable::create(&self.dir, pages) {
+                self.table = table;
+            }
+        }
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u64(writer, self.seed)?;
+        write_u64(writer, self.table.len)?;
+        for hash in self.table.hashes()? {
+            write_u64(writer, hash?)?;
+        }
+        Ok(())
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let seed = read_u64(reader)?;
+        let len = read_u64(reader)?;
+
+        // size the table up front to avoid growing while loading
+        let mut table = DiskTable::create(&self.dir, pages_for(len))?;
+        for _ in 0..len {
+            table.insert(read_u64(reader)?)?;
+        }
+
+        self.table = table;
+        self.seed = seed;
+        Ok(())
+    }
+}
+
+/// Implement merging by inserting all hashes of the other table.
+#[cfg(feature = "std")]
+impl MergeableFilter for DiskFilter {
+    /// # Panics
+    ///
+    /// As with detection, this will panic if the tables cannot be accessed.
+    fn merge(&mut self, other: Self) {
+        assert_eq!(self.seed, other.seed, "filters must use the same seed");
+        self.try_merge(other).expect("unable to access disk filter")
+    }
+}
+
+/// Number of bytes in each page of a `DiskTable`.
+#[cfg(feature = "std")]
+const DISK_PAGE_SIZE: u64 = 4096;
+
+/// Number of hash slots in each page of a `DiskTable`.
+#[cfg(feature = "std")]
+const DISK_PAGE_SLOTS: u64 = DISK_PAGE_SIZE / 8;
+
+/// Number of pages allocated when creating a `DiskFilter`.
+#[cfg(feature = "std")]
+const DISK_INITIAL_PAGES: u64 = 16;
+
+/// Calculates the number of pages needed to hold a number of hashes.
+///
+/// Tables grow once half full, so this allows twice the number of slots.
+#[cfg(feature = "std")]
+fn pages_for(len: u64) -> u64 {
+    let mut pages = DISK_INITIAL_PAGES;
+    while len * 2 >= pages * DISK_PAGE_SLOTS {
+        pages *= 2;
+    }
+    pages
+}
+
+/// Open addressing table of hashes stored within a file.
+///
+/// Slots are probed linearly, and are read a page at a time so that
+/// most detections only require a single read from the file.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct DiskTable {
+    file: File,
+    path: PathBuf,
+    pages: u64,
+    len: u64,
+}
+
+#[cfg(feature = "std")]
+impl DiskTable {
+    /// Creates a new (empty) table file with a number of pages.
+    fn create(dir: &Path, pages: u64) -> io::Result<DiskTable> {
+        static TABLES: AtomicUsize = AtomicUsize::new(0);
+
+        let id = TABLES.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("runiq-{}-{}.filter", process::id(), id));
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        let table = DiskTable {
+            file,
+            path,
+            pages,
+            len: 0,
+        };
+
+        table.file.set_len(pages * DISK_PAGE_SIZE)?;
+
+        Ok(table)
+    }
+
+    /// Inserts a (non-zero) hash, returning whether it was added.
+    fn insert(&mut self, hash: u64) -> io::Result<bool> {
+        let position = match self.probe(hash)? {
+            Probe::Found => return Ok(false),
+            Probe::Empty(position) => position,
+        };
+
+        self.file.seek(SeekFrom::Start(position))?;
+        self.file.write_all(&hash.to_ne_bytes())?;
+        self.len += 1;
+
+        Ok(true)
+    }
+
+    /// Removes all hashes from the table, keeping the size of the table.
+    fn clear(&mut self) -> io::Result<()> {
+        // truncating and extending the file zeroes every slot
+        self.file.set_len(0)?;
+        self.file.set_len(self.pages * DISK_PAGE_SIZE)?;
+        self.len = 0;
+        Ok(())
+    }
+
+    /// Checks whether a (non-zero) hash is stored in the table.
+    fn contains(&self, hash: u64) -> io::Result<bool> {
+        Ok(matches!(self.probe(hash)?, Probe::Found))
+    }
+
+    /// Probes the table for a hash, or the empty slot it would be stored in.
+    fn probe(&self, hash: u64) -> io::Result<Probe> {
+        let slots = self.pages * DISK_PAGE_SLOTS;
+        let mut slot = hash % slots;
+        let mut buffer = [0; DISK_PAGE_SIZE as usize];
+        let mut file = &self.file;
+
+        loop {
+            let page = slot / DISK_PAGE_SLOTS;
+
+            file.seek(SeekFrom::Start(page * DISK_PAGE_SIZE))?;
+            file.read_exact(&mut buffer)?;
+
+            // probe the rest of the page before moving on to the next
+            for offset in (slot % DISK_PAGE_SLOTS)..DISK_PAGE_SLOTS {
+                let start = (offset * 8) as usize;
+                let found = u64::from_ne_bytes(buffer[start..start + 8].try_into().unwrap());
+
+                if found == hash {
+                    return Ok(Probe::Found);
+                }
+
+                if found == 0 {
+                    return Ok(Probe::Empty(page * DISK_PAGE_SIZE + offset * 8));
+                }
+            }
+
+            slot = (page + 1) % self.pages * DISK_PAGE_SLOTS;
+        }
+    }
+
+    /// Reads all hashes stored in the table.
+    fn hashes(&self) -> io::Result<impl Iterator<Item = io::Result<u64>> + '_> {
+        let mut slot = [0; 8];
+        let mut reader = io::BufReader::new(&self.file);
+
+        reader.seek(SeekFrom::Start(0))?;
+
+        Ok((0..self.pages * DISK_PAGE_SLOTS).filter_map(move |_| {
+            match reader.read_exact(&mut slot) {
+                Ok(()) => Some(u64::from_ne_bytes(slot))
+                    .filter(|hash| *hash != 0)
+                    .map(Ok),
+                Err(err) => Some(Err(err)),
+            }
+        }))
+    }
+}
+
+/// Result of probing a `DiskTable` for a hash.
+#[cfg(feature = "std")]
+enum Probe {
+    /// The hash is stored in the table.
+    Found,
+
+    /// The hash is missing, and would be stored at this position.
+    Empty(u64),
+}
+
+/// Implement `Drop` to remove the table file.
+#[cfg(feature = "std")]
+impl Drop for DiskTable {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Sliding window filter to detect uniques within recent inputs.
+///
+/// Only the hashes of the last `size` inputs are kept, so a value is only
+/// treated as a duplicate if it was seen within the window; once it falls
+/// out of the window it's treated as unique again. Memory is bounded by
+/// the size of the window, rather than the number of uniques.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct WindowFilter {
+    counts: HashMap<u64, usize, BuildIdentityHasher<u64>>,
+    recent: VecDeque<u64>,
+    seed: u64,
+    size: usize,
+}
+
+#[cfg(feature = "std")]
+impl WindowFilter {
+    /// Creates a new `WindowFilter` over the last `size` inputs.
+    pub fn new(size: usize) -> Self {
+        Self::with_seed(size, 0)
+    }
+
+    /// Creates a new `WindowFilter` using a custom hash seed.
+    pub fn with_seed(size: usize, seed: u64) -> Self {
+        Self {
+            counts: HashMap::default(),
+            recent: VecDeque::new(),
+            seed,
+            size,
+        }
+    }
+
+    /// Pushes a hash into the window, evicting the oldest if full.
+    fn push(&mut self, hash: u64) {
+        *self.counts.entry(hash).or_insert(0) += 1;
+        self.recent.push_back(hash);
+
+        if self.recent.len() <= self.size {
+            return;
+        }
+
+        if let Some(oldest) = self.recent.pop_front() {
+            if let Some(count) = self.counts.get_mut(&oldest) {
+                *count -= 1;
+                if *count == 0 {
+                    self.counts.remove(&oldest);
+                }
+            }
+        }
+    }
+}
+
+/// Implement all trait methods.
+#[cfg(feature = "std")]
+impl Filter for WindowFilter {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        let hash = xxh3_64_with_seed(input, self.seed);
+        let unique = !self.counts.contains_key(&hash);
+        self.push(hash);
+        unique
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        self.counts
+            .contains_key(&xxh3_64_with_seed(input, self.seed))
+    }
+
+    fn clear(&mut self) {
+        self.counts.clear();
+        self.recent.clear();
+    }
+
+    fn len(&self) -> u64 {
+        self.counts.len() as u64
+    }
+
+    fn memory(&self) -> Option<u64> {
+        let recent = self.recent.capacity() * mem::size_of::<u64>();
+        Some(table_memory::<(u64, usize)>(self.counts.capacity()) + recent as u64)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        let additional = additional.min(self.size);
+        self.counts.reserve(additional);
+        self.recent.reserve(additional);
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u64(writer, self.seed)?;
+        write_u64(writer, self.recent.len() as u64)?;
+        for hash in &self.recent {
+            write_u64(writer, *hash)?;
+        }
+        Ok(())
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let seed = read_u64(reader)?;
+        let len = read_u64(reader)?;
+        self.counts.clear();
+        self.recent.clear();
+        for _ in 0..len {
+            self.push(read_u64(reader)?);
+        }
+        self.seed = seed;
+        Ok(())
+    }
+}
+
+/// Bounded filter to detect uniques within recently seen inputs.
+///
+/// At most `size` hashes are kept, and once full the least recently seen
+/// hash is evicted to make room for a new one. Unlike the `WindowFilter`,
+/// seeing a duplicate refreshes the value, so frequently repeated values
+/// are never forgotten. Memory is bounded by the size of the filter.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct LruFilter {
+    indices: HashMap<u64, usize, BuildIdentityHasher<u64>>,
+    nodes: Vec<LruNode>,
+    newest: usize,
+    oldest: usize,
+    seed: u64,
+    size: usize,
+}
+
+/// Entry in the recency list of an `LruFilter`.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug)]
+struct LruNode {
+    hash: u64,
+    newer: usize,
+    older: usize,
+}
+
+#[cfg(feature = "std")]
+impl LruFilter {
+    /// Marker used to represent the end of the recency list.
+    const NONE: usize = usize::MAX;
+
+    /// Creates a new `LruFilter` remembering up to `size` inputs.
+    pub fn new(size: usize) -> Self {
+        Self::with_seed(size, 0)
+    }
+
+    /// Creates a new `LruFilter` using a custom hash seed.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the size is `0`.
+    pub fn with_seed(size: usize, seed: u64) -> Self {
+        assert!(size > 0, "size must be at least 1");
+        Self {
+            indices: HashMap::default(),
+            nodes: Vec::new(),
+            newest: Self::NONE,
+            oldest: Self::NONE,
+            seed,
+            size,
+        }
+    }
+
+    /// Pushes a hash as the most recently seen, evicting the oldest if full.
+    fn push(&mut self, hash: u64) {
+        if let Some(&index) = self.indices.get(&hash) {
+            self.unlink(index);
+            self.link(index);
+            return;
+        }
+
+        let index = if self.nodes.len() < self.size {
+            self.nodes.push(LruNode {
+                hash,
+                newer: Self::NONE,
+                older: Self::NONE,
+            });
+            self.nodes.len() - 1
+        } else {
+            // reuse the node of the oldest hash
+            let index = self.oldest;
+            self.unlink(index);
+            self.indices.remove(&self.nodes[index].hash);
+            self.nodes[index].hash = hash;
+            index
+        };
+
+        self.indices.insert(hash, index);
+        self.link(index);
+    }
+
+    /// Links a node as the newest in the recency list.
+    fn link(&mut self, index: usize) {
+        self.nodes[index].newer = Self::NONE;
+        self.nodes[index].older = self.newest;
+
+        match self.newest {
+            Self::NONE => self.oldest = index,
+            newest => self.nodes[newest].newer = index,
+        }
+
+        self.newest = index;
+    }
+
+    /// Unlinks a node from the recency list.
+    fn unlink(&mut self, index: usize) {
+        let LruNode { newer, older, .. } = self.nodes[index];
+
+        match newer {
+            Self::NONE => self.newest = older,
+            newer => self.nodes[newer].older = older,
+        }
+
+        match older {
+            Self::NONE => self.oldest = newer,
+            older => self.nodes[older].newer = newer,
+        }
+    }
+}
+
+/// Implement all trait methods.
+#[cfg(feature = "std")]
+impl Filter for LruFilter {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        let hash = xxh3_64_with_seed(input, self.seed);
+        let unique = !self.indices.contains_key(&hash);
+        self.push(hash);
+        unique
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        self.indices
+            .contains_key(&xxh3_64_with_seed(input, self.seed))
+    }
+
+    fn clear(&mut self) {
+        self.indices.clear();
+        self.nodes.clear();
+        self.newest = Self::NONE;
+        self.oldest = Self::NONE;
+    }
+
+    fn len(&self) -> u64 {
+        self.indices.len() as u64
+    }
+
+    fn memory(&self) -> Option<u64> {
+        let nodes = self.nodes.capacity() * mem::size_of::<LruNode>();
+        Some(table_memory::<(u64, usize)>(self.indices.capacity()) + nodes as u64)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        let additional = additional.min(self.size);
+        self.indices.reserve(additional);
+        self.nodes.reserve(additional);
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_u64(writer, self.seed)?;
+        write_u64(writer, self.nodes.len() as u64)?;
+
+        // write from oldest to newest, so loading restores the order
+        let mut index = self.oldest;
+        while index != Self::NONE {
+            write_u64(writer, self.nodes[index].hash)?;
+            index = self.nodes[index].newer;
+        }
+
+        Ok(())
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let seed = read_u64(reader)?;
+        let len = read_u64(reader)?;
+        self.clear();
+        for _ in 0..len {
+            self.push(read_u64(reader)?);
+        }
+        self.seed = seed;
+        Ok(())
+    }
+}
+
+/// Expiring filter to detect uniques within a period of time.
+///
+/// Each unique value is remembered for a fixed duration after it was
+/// first seen, after which it expires and is treated as unique again.
+/// Duplicates do not extend the lifetime of a value, so values repeating
+/// on a regular basis are detected again once per duration. Memory is
+/// bounded by the number of uniques seen within the duration.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct TtlFilter {
+    expiries: VecDeque<(Instant, u64)>,
+    inner: HashMap<u64, Instant, BuildIdentityHasher<u64>>,
+    seed: u64,
+    ttl: Duration,
+}
+
+#[cfg(feature = "std")]
+impl TtlFilter {
+    /// Creates a new `TtlFilter` expiring values after a duration.
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_seed(ttl, 0)
+    }
+
+    /// Creates a new `TtlFilter` using a custom hash seed.
+    pub fn with_seed(ttl: Duration, seed: u64) -> Self {
+        Self {
+            expiries: VecDeque::new(),
+            inner: HashMap::default(),
+            seed,
+            ttl,
+        }
+    }
+
+    /// Detects a unique value at a point in time.
+    fn detect_at(&mut self, input: &[u8], now: Instant) -> bool {
+        // values expire in the order they were inserted
+        while let Some(&(expiry, hash)) = self.expiries.front() {
+            if expiry > now {
+                break;
+            }
+            self.expiries.pop_front();
+            self.inner.remove(&hash);
+        }
+
+        let hash = xxh3_64_with_seed(input, self.seed);
+        if self.inner.contains_key(&hash) {
+            return false;
+        }
+
+        let expiry = now + self.ttl;
+
+        self.inner.insert(hash, expiry);
+        self.expiries.push_back((expiry, hash));
+
+        true
+    }
+}
+
+/// Implement all trait methods.
+#[cfg(feature = "std")]
+impl Filter for TtlFilter {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        self.detect_at(input, Instant::now())
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        // expired values may not have been removed yet
+        self.inner
+            .get(&xxh3_64_with_seed(input, self.seed))
+            .is_some_and(|expiry| *expiry > Instant::now())
+    }
+
+    fn clear(&mut self) {
+        self.expiries.clear();
+        self.inner.clear();
+    }
+
+    fn len(&self) -> u64 {
+        self.inner.len() as u64
+    }
+
+    fn memory(&self) -> Option<u64> {
+        let expiries = self.expiries.capacity() * mem::size_of::<(Instant, u64)>();
+        Some(table_memory::<(u64, Instant)>(self.inner.capacity()) + expiries as u64)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional);
+        self.expiries.reserve(additional);
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        let now = Instant::now();
+        write_u64(writer, self.seed)?;
+        write_u64(writer, self.expiries.len() as u64)?;
+        for (expiry, hash) in &self.expiries {
+            // instants can't be persisted, so the remaining lifetime is stored
+            let remaining = expiry.saturating_duration_since(now);
+            write_u64(writer, remaining.as_millis() as u64)?;
+            write_u64(writer, *hash)?;
+        }
+        Ok(())
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let now = Instant::now();
+        let seed = read_u64(reader)?;
+        let len = read_u64(reader)?;
+        self.expiries.clear();
+        self.inner.clear();
+        for _ in 0..len {
+            let remaining = Duration::from_millis(read_u64(reader)?);
+            let hash = read_u64(reader)?;
+            self.expiries.push_back((now + remaining, hash));
+            self.inner.insert(hash, now + remaining);
+        }
+        self.seed = seed;
+        Ok(())
+    }
+}
+
+/// Wrapper filter to detect uniques regardless of case.
+///
+/// Inputs are lowercased before being passed to the inner filter, so
+/// that `Input` and `INPUT` are treated as the same value. Valid UTF-8
+/// is lowercased fully, whereas any other input only has the ASCII
+/// range lowercased.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct CaseInsensitiveFilter<F> {
+    inner: F,
+    buffer: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<F: Filter> CaseInsensitiveFilter<F> {
+    /// Creates a new `CaseInsensitiveFilter` around an inner filter.
+    pub fn new(inner: F) -> Self {
+        Self {
+            inner,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Consumes the wrapper, returning the inner filter.
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+/// Implement all trait methods.
+#[cfg(feature = "std")]
+impl<F: Filter> Filter for CaseInsensitiveFilter<F> {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        self.buffer.clear();
+
+        match std::str::from_utf8(input) {
+            Ok(value) if !value.is_ascii() => self
+                .buffer
+                .extend_from_slice(value.to_lowercase().as_bytes()),
+            _ => self
+                .buffer
+                .extend(input.iter().map(|byte| byte.to_ascii_lowercase())),
+        }
+
+        self.inner.detect(&self.buffer)
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        match std::str::from_utf8(input) {
+            Ok(value) if !value.is_ascii() => self.inner.contains(value.to_lowercase().as_bytes()),
+            _ => self.inner.contains(&input.to_ascii_lowercase()),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    fn len(&self) -> u64 {
+        self.inner.len()
+    }
+
+    fn memory(&self) -> Option<u64> {
+        self.inner.memory()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.inner.save_state(writer)
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.inner.load_state(reader)
+    }
+}
+
+/// Implement merging by merging the inner filters.
+#[cfg(feature = "std")]
+impl<F: MergeableFilter> MergeableFilter for CaseInsensitiveFilter<F> {
+    fn merge(&mut self, other: Self) {
+        self.inner.merge(other.inner)
+    }
+}
+
+/// Wrapper filter to detect uniques regardless of Unicode form.
+///
+/// Inputs are normalized before being passed to the inner filter, so
+/// that canonically equivalent values (such as precomposed and decomposed
+/// accents) are treated as the same value. Input which is not valid UTF-8
+/// is passed through unchanged.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct NormalizedFilter<F> {
+    inner: F,
+    form: Normalization,
+    buffer: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<F: Filter> NormalizedFilter<F> {
+    /// Creates a new `NormalizedFilter` around an inner filter.
+    pub fn new(inner: F, form: Normalization) -> Self {
+        Self {
+            inner,
+            form,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Consumes the wrapper, returning the inner filter.
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+/// Implement all trait methods.
+#[cfg(feature = "std")]
+impl<F: Filter> Filter for NormalizedFilter<F> {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        self.buffer.clear();
+        self.form.normalize(input, &mut self.buffer);
+        self.inner.detect(&self.buffer)
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        let mut buffer = Vec::new();
+        self.form.normalize(input, &mut buffer);
+        self.inner.contains(&buffer)
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    fn len(&self) -> u64 {
+        self.inner.len()
+    }
+
+    fn memory(&self) -> Option<u64> {
+        self.inner.memory()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.inner.save_state(writer)
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.inner.load_state(reader)
+    }
+}
+
+/// Implement merging by merging the inner filters.
+#[cfg(feature = "std")]
+impl<F: MergeableFilter> MergeableFilter for NormalizedFilter<F> {
+    /// # Panics
+    ///
+    /// This will panic if the filters normalize to different forms.
+    fn merge(&mut self, other: Self) {
+        assert_eq!(self.form, other.form, "filters must use the same form");
+        self.inner.merge(other.inner)
+    }
+}
+
+/// Enum to store all supported forms of Unicode normalization.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, EnumString)]
+#[strum(ascii_case_insensitive)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Normalization {
+    /// Canonical composition (such as precomposed accents).
+    Nfc,
+
+    /// Compatibility composition (such as expanded ligatures).
+    Nfkc,
+}
+
+#[cfg(feature = "std")]
+impl Normalization {
+    /// Normalizes an input into the provided buffer.
+    ///
+    /// ASCII input is always normalized, so is written as is. Input
+    /// which is not valid UTF-8 is also written unchanged.
+    pub fn normalize(self, input: &[u8], output: &mut Vec<u8>) {
+        let value = match std::str::from_utf8(input) {
+            Ok(value) if !value.is_ascii() => value,
+            _ => return output.extend_from_slice(input),
+        };
+
+        let mut encoded = [0; 4];
+        let mut push = |ch: char| output.extend_from_slice(ch.encode_utf8(&mut encoded).as_bytes());
+
+        match self {
+            Normalization::Nfc => value.nfc().for_each(&mut push),
+            Normalization::Nfkc => value.nfkc().for_each(&mut push),
+        }
+    }
+}
+
+/// Implement all trait methods for boxed filters.
+impl<F: Filter + ?Sized> Filter for Box<F> {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        (**self).detect(input)
+    }
+
+    fn detect_batch(&mut self, inputs: &[&[u8]]) -> Vec<bool> {
+        (**self).detect_batch(inputs)
+    }
+
+    #[cfg(feature = "std")]
+    fn try_detect(&mut self, input: &[u8]) -> io::Result<bool> {
+        (**self).try_detect(input)
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        (**self).contains(input)
+    }
+
+    fn clear(&mut self) {
+        (**self).clear()
+    }
+
+    fn len(&self) -> u64 {
+        (**self).len()
+    }
+
+    fn memory(&self) -> Option<u64> {
+        (**self).memory()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        (**self).reserve(additional)
+    }
+
+    #[cfg(feature = "std")]
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        (**self).save_state(writer)
+    }
+
+    #[cfg(feature = "std")]
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        (**self).load_state(reader)
+    }
+}
+
+/// Enum to store all possible variants of filters.
+///
+/// This will implement the `Into` trait in order to create a new
+/// boxed filter from a filter kind to keep conversion contained.
+///
+/// Each variant has a fixed number which is written to state files,
+/// so existing numbers must never change. The number 6 belonged to a
+/// retired filter, so it must never be reused either.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, EnumString)]
+#[strum(ascii_case_insensitive)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Filters {
+    /// Hashed comparisons with more efficient throughput.
+    Quick = 0,
+
+    /// Naive comparisons of strings within a set.
+    Simple = 1,
+
+    /// Adjacent comparisons on sorted data (similar to uniq).
+    Sorted = 2,
+
+    /// Bloom filter comparisons with compact memory usage.
+    Compact = 3,
+
+    /// Hashed comparisons stored on disk rather than in memory.
+    Disk = 4,
+
+    /// Cuckoo filter comparisons with compact memory usage.
+    Cuckoo = 5,
+
+    /// Hashed comparisons using 128-bit hashes to avoid collisions.
+    Quick128 = 7,
+}
+
+#[cfg(feature = "std")]
+impl Filters {
+    /// Retrieves a filter kind from the name of the filter.
+    ///
+    /// Names are matched case insensitively, so both `quick` and
+    /// `Quick` will resolve to `Filters::Quick`.
+    pub fn from_name(name: &str) -> Option<Filters> {
+        name.parse().ok()
+    }
+
+    /// Creates a new boxed `Filter` based on the enum value.
+    ///
+    /// # Panics
+    ///
+    /// The disk filter is created in the temporary directory of the
+    /// system, and this will panic if the filter cannot be created.
+    /// Use `try_into_filter` to handle any errors instead.
+    pub fn into_filter(self) -> Box<dyn Filter> {
+        self.try_into_filter()
+            .expect("unable to create disk filter")
+    }
+
+    /// Creates a new boxed `Filter` based on the enum value.
+    ///
+    /// The disk filter is created in the temporary directory of the
+    /// system, and any error raised when creating it is returned.
+    pub fn try_into_filter(self) -> io::Result<Box<dyn Filter>> {
+        Ok(match self {
+            Filters::Quick => Box::<QuickFilter>::default(),
+            Filters::Simple => Box::<SimpleFilter>::default(),
+            Filters::Compact => Box::<CompactFilter>::default(),
+            Filters::Cuckoo => Box::<CuckooFilter>::default(),
+            Filters::Quick128 => Box::<Quick128Filter>::default(),
+            Filters::Sorted => Box::<SortedFilter>::default(),
+            Filters::Disk => Box::new(DiskFilter::new(env::temp_dir())?),
+        })
+    }
+}
+
+/// Implement `From` to convert to `Filter`.
+#[cfg(feature = "std")]
+impl From<Filters> for Box<dyn Filter> {
+    /// Creates a new `Filter` type based on the enum value.
+    fn from(kind: Filters) -> Self {
+        kind.into_filter()
+    }
+}
+
+/// Estimates the memory used by a hash table with a given capacity.
+///
+/// Tables store each value alongside a byte of control data, so this
+/// is close enough for reporting without relying on table internals.
+fn table_memory<T>(capacity: usize) -> u64 {
+    (capacity * (mem::size_of::<T>() + 1)) as u64
+}
+
+/// Writes a `u64` value in little endian form.
+#[cfg(feature = "std")]
+fn write_u64(writer: &mut dyn Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+/// Reads a `u64` value in little endian form.
+#[cfg(feature = "std")]
+fn read_u64(reader: &mut dyn Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Writes a length prefixed sequence of bytes.
+#[cfg(feature = "std")]
+fn write_bytes(writer: &mut dyn Write, value: &[u8]) -> io::Result<()> {
+    write_u64(writer, value.len() as u64)?;
+    writer.write_all(value)
+}
+
+/// Reads a length prefixed sequence of bytes.
+#[cfg(feature = "std")]
+fn read_bytes(reader: &mut dyn Read) -> io::Result<Vec<u8>> {
+    let len = read_u64(reader)?;
+    let mut value = Vec::new();
+    reader.take(len).read_to_end(&mut value)?;
+    if value.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(value)
+}
+
+/// Converts a serialization error into an I/O error.
+#[cfg(feature = "std")]
+fn invalid(err: bincode::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::RandomState;
+
+    #[test]
+    fn naive_filter_detection() {
+        let mut filter = SimpleFilter::default();
+
+        let ins1 = filter.detect(b"input1");
+        let ins2 = filter.detect(b"input1");
+
+        assert!(ins1);
+        assert!(!ins2);
+    }
+
+    #[test]
+    fn digest_filter_detection() {
+        let mut filter = QuickFilter::default();
+
+        let ins1 = filter.detect(b"input1");
+        let ins2 = filter.detect(b"input1");
+
+        assert!(ins1);
+        assert!(!ins2);
+    }
+
+    #[test]
+    fn capacity_creation() {
+        let mut simple = SimpleFilter::with_capacity(1024);
+        let mut quick = QuickFilter::with_capacity(1024);
+        let mut compact = CompactFilter::with_estimated_insertions(1024);
+
+        assert!(simple.inner.capacity() >= 1024);
+        assert!(quick.inner.capacity() >= 1024);
+
+        assert!(simple.detect(b"input1"));
+        assert!(quick.detect(b"input1"));
+        assert!(compact.detect(b"input1"));
+        assert!(!compact.detect(b"input1"));
+    }
+
+    #[test]
+    fn batch_detection() {
+        let inputs: &[&[u8]] = &[b"input1", b"input2", b"input1", b"input3"];
+        let expected = vec![true, true, false, true];
+
+        let filters: Vec<Box<dyn Filter>> = vec![
+            Box::new(SimpleFilter::default()),
+            Box::new(QuickFilter::default()),
+            Box::new(Quick128Filter::default()),
+        ];
+
+        for mut filter in filters {
+            assert_eq!(filter.detect_batch(inputs), expected);
+            assert_eq!(
+                filter.detect_batch(&[b"input2", b"input4"]),
+                vec![false, true]
+            );
+            assert_eq!(filter.len(), 4);
+        }
+    }
+
+    #[test]
+    fn digest_filter_seeded_detection() {
+        let mut filter = QuickFilter::with_seed(1);
+
+        let ins1 = filter.detect(b"input1");
+        let ins2 = filter.detect(b"input1");
+
+        assert!(ins1);
+        assert!(!ins2);
+        assert!(filter.inner.contains(&xxh3_64_with_seed(b"input1", 1)));
+        assert!(!filter.inner.contains(&xxh3_64_with_seed(b"input1", 0)));
+    }
+
+    #[test]
+    fn wide_digest_filter_detection() {
+        let mut filter = Quick128Filter::with_seed(1);
+
+        assert!(filter.detect(b"input1"));
+        assert!(!filter.detect(b"input1"));
+        assert!(filter.contains(b"input1"));
+        assert!(filter.inner.contains(&xxh3_128_with_seed(b"input1", 1)));
+
+        let mut other = Quick128Filter::with_seed(1);
+        other.detect(b"input2");
+        filter.merge(other);
+
+        assert_eq!(filter.len(), 2);
+        assert!(!filter.detect(b"input2"));
+    }
+
+    #[test]
+    fn digest_filter_hashed_detection() {
+        let mut filter = QuickFilter::with_hasher(RandomState::new());
+
+        assert!(filter.detect(b"input1"));
+        assert!(!filter.detect(b"input1"));
+        assert!(filter.contains(b"input1"));
+
+        let err = filter.save_state(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+        let mut filter = QuickFilter::with_hasher(Xxh3Builder::with_seed(1));
+
+        assert!(filter.detect(b"input1"));
+        assert!(filter.inner.contains(&xxh3_64_with_seed(b"input1", 1)));
+    }
+
+    #[test]
+    fn counting_filter_detection() {
+        let mut filter = CountingFilter::default();
+
+        assert!(filter.detect(b"input1"));
+        assert!(!filter.detect(b"input1"));
+        assert!(!filter.detect(b"input1"));
+        assert!(filter.detect(b"input2"));
+
+        assert_eq!(filter.count(b"input1"), 3);
+        assert_eq!(filter.count(b"input2"), 1);
+        assert_eq!(filter.count(b"input3"), 0);
+        assert_eq!(filter.len(), 2);
+
+        let mut other = CountingFilter::default();
+        other.detect(b"input2");
+        filter.merge(other);
+
+        assert_eq!(filter.count(b"input2"), 2);
+
+        let mut state = Vec::new();
+        filter.save_state(&mut state).unwrap();
+
+        let mut restored = CountingFilter::with_seed(1);
+        restored.load_state(&mut &state[..]).unwrap();
+
+        assert_eq!(restored.count(b"input1"), 3);
+    }
+
+    #[test]
+    fn estimate_filter_estimation() {
+        let mut filter = EstimateFilter::default();
+
+        assert!(filter.is_empty());
+
+        for value in 0..100_000 {
+            filter.detect(value.to_string().as_bytes());
+            filter.detect(value.to_string().as_bytes());
+        }
+
+        let estimate = filter.len() as f64;
+        assert!((estimate - 100_000.0).abs() < 3_000.0, "{}", estimate);
+
+        let mut other = EstimateFilter::default();
+        for value in 50_000..150_000 {
+            other.detect(value.to_string().as_bytes());
+        }
+        filter.merge(other);
+
+        let estimate = filter.len() as f64;
+        assert!((estimate - 150_000.0).abs() < 4_500.0, "{}", estimate);
+
+        let mut state = Vec::new();
+        filter.save_state(&mut state).unwrap();
+
+        let mut restored = EstimateFilter::with_precision(4);
+        restored.load_state(&mut &state[..]).unwrap();
+
+        assert_eq!(restored.len(), filter.len());
+
+        filter.clear();
+
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn sorted_filter_detection() {
+        let mut filter = SortedFilter::default();
+
+        let ins1 = filter.detect(b"input1");
+        let ins2 = filter.detect(b"input1");
+        let ins3 = filter.detect(b"input2");
+        let ins4 = filter.detect(b"input1");
+
+        assert!(ins1);
+        assert!(!ins2);
+        assert!(ins3);
+        assert!(ins4);
+    }
+
+    #[test]
+    fn bloom_filter_detection() {
+        let mut filter = CompactFilter::default();
+
+        let ins1 = filter.detect(b"input1");
+        let ins2 = filter.detect(b"input1");
+
+        assert!(ins1);
+        assert!(!ins2);
+    }
+
+    #[test]
+    fn bloom_filter_seeded_detection() {
+        let mut filter = CompactFilter::with_seed(1);
+
+        let ins1 = filter.detect(b"input1");
+        let ins2 = filter.detect(b"input1");
+
+        assert!(ins1);
+        assert!(!ins2);
+    }
+
+    #[test]
+    fn bloom_filter_built_detection() {
+        let mut filter = CompactFilter::builder()
+            .error_ratio(0.01)
+            .growth_factor(4)
+            .tightening_ratio(0.8)
+            .seed(1)
+            .build();
+
+        let ins1 = filter.detect(b"input1");
+        let ins2 = filter.detect(b"input1");
+
+        assert!(ins1);
+        assert!(!ins2);
+        assert_eq!(filter.seed, 1);
+        assert_eq!(filter.params.error_ratio, 0.01);
+    }
+
+    #[test]
+    fn bloom_filter_builder_validation() {
+        let builders = vec![
+            CompactFilter::builder().error_ratio(0.0),
+            CompactFilter::builder().error_ratio(1.0),
+            CompactFilter::builder().tightening_ratio(1.5),
+            CompactFilter::builder().growth_factor(1),
+            CompactFilter::builder().estimated_insertions(0),
+        ];
+
+        for builder in builders {
+            let err = builder.try_build().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+
+        let filter = CompactFilter::builder()
+            .estimated_insertions(100)
+            .try_build()
+            .unwrap();
+
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn cuckoo_filter_detection() {
+        let mut filter = CuckooFilter::with_capacity(16);
+
+        for value in 0..10_000 {
+            assert!(filter.detect(value.to_string().as_bytes()));
+        }
+
+        assert!(filter.tables.len() > 1);
+        assert_eq!(filter.len(), 10_000);
+
+        for value in 0..10_000 {
+            assert!(filter.contains(value.to_string().as_bytes()));
+            assert!(!filter.detect(value.to_string().as_bytes()));
+        }
+
+        filter.clear();
+
+        assert_eq!(filter.tables.len(), 1);
+        assert!(filter.detect(b"input1"));
+    }
+
+    #[test]
+    fn disk_filter_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut filter = DiskFilter::new(dir.path()).unwrap();
+
+        for value in 0..10_000 {
+            assert!(filter.detect(value.to_string().as_bytes()));
+        }
+
+        for value in 0..10_000 {
+            assert!(!filter.detect(value.to_string().as_bytes()));
+        }
+
+        assert!(filter.table.pages > DISK_INITIAL_PAGES);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let mut filter: Box<dyn Filter> = Box::new(filter);
+
+        assert!(!filter.try_detect(b"0").unwrap());
+        assert!(filter.try_detect(b"input").unwrap());
+
+        drop(filter);
+
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn reserved_filter_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut filter = DiskFilter::new(dir.path()).unwrap();
+
+        filter.reserve(100_000);
+
+        assert_eq!(filter.table.pages, pages_for(100_000));
+
+        let mut filters: Vec<Box<dyn Filter>> = vec![
+            Box::new(filter),
+            Box::<CompactFilter>::default(),
+            Box::<QuickFilter>::default(),
+            Box::<SimpleFilter>::default(),
+        ];
+
+        for filter in filters.iter_mut() {
+            filter.reserve(1_000);
+
+            assert!(filter.detect(b"input1"));
+            assert!(!filter.detect(b"input1"));
+        }
+    }
+
+    #[test]
+    fn window_filter_detection() {
+        let mut filter = WindowFilter::new(2);
+
+        assert!(filter.detect(b"input1"));
+        assert!(filter.detect(b"input2"));
+        assert!(!filter.detect(b"input1"));
+        assert!(filter.detect(b"input3"));
+        assert!(filter.detect(b"input2"));
+        assert!(!filter.detect(b"input3"));
+
+        assert_eq!(filter.recent.len(), 2);
+        assert_eq!(filter.counts.len(), 2);
+    }
+
+    #[test]
+    fn lru_filter_detection() {
+        let mut filter = LruFilter::new(2);
+
+        assert!(filter.detect(b"input1"));
+        assert!(filter.detect(b"input2"));
+        assert!(!filter.detect(b"input1"));
+
+        // input2 is the least recently seen, so is evicted
+        assert!(filter.detect(b"input3"));
+        assert!(!filter.detect(b"input1"));
+        assert!(filter.detect(b"input2"));
+
+        assert_eq!(filter.len(), 2);
+        assert!(!filter.contains(b"input3"));
+
+        let mut state = Vec::new();
+        filter.save_state(&mut state).unwrap();
+
+        let mut restored = LruFilter::new(2);
+        restored.load_state(&mut &state[..]).unwrap();
+
+        assert!(restored.detect(b"input3"));
+        assert!(!restored.detect(b"input2"));
+        assert!(restored.detect(b"input1"));
+    }
+
+    #[test]
+    fn ttl_filter_detection() {
+        let mut filter = TtlFilter::new(Duration::from_secs(10));
+        let start = Instant::now();
+
+        assert!(filter.detect_at(b"input1", start));
+        assert!(!filter.detect_at(b"input1", start + Duration::from_secs(5)));
+        assert!(filter.detect_at(b"input2", start + Duration::from_secs(5)));
+        assert!(filter.detect_at(b"input1", start + Duration::from_secs(10)));
+        assert!(!filter.detect_at(b"input2", start + Duration::from_secs(12)));
+        assert!(filter.detect_at(b"input2", start + Duration::from_secs(15)));
+
+        assert_eq!(filter.inner.len(), 2);
+        assert_eq!(filter.expiries.len(), 2);
+    }
+
+    #[test]
+    fn filter_membership() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut filters: Vec<Box<dyn Filter>> = vec![
+            Box::<CompactFilter>::default(),
+            Box::new(DiskFilter::new(dir.path()).unwrap()),
+            Box::<QuickFilter>::default(),
+            Box::<SimpleFilter>::default(),
+            Box::<SortedFilter>::default(),
+            Box::new(TtlFilter::new(Duration::from_secs(60))),
+            Box::new(WindowFilter::new(10)),
+            Box::new(CaseInsensitiveFilter::new(SimpleFilter::default())),
+            Box::new(NormalizedFilter::new(
+                SimpleFilter::default(),
+                Normalization::Nfc,
+            )),
+        ];
+
+        for filter in filters.iter_mut() {
+            assert!(!filter.contains(b"input1"));
+            assert!(!filter.contains(b"input1"));
+            assert!(filter.detect(b"input1"));
+            assert!(filter.contains(b"input1"));
+            assert!(!filter.contains(b"input2"));
+        }
+    }
+
+    #[test]
+    fn filter_clearing() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut filters: Vec<Box<dyn Filter>> = vec![
+            Box::<CompactFilter>::default(),
+            Box::new(DiskFilter::new(dir.path()).unwrap()),
+            Box::<QuickFilter>::default(),
+            Box::<SimpleFilter>::default(),
+            Box::<SortedFilter>::default(),
+            Box::new(TtlFilter::new(Duration::from_secs(60))),
+            Box::new(WindowFilter::new(10)),
+            Box::new(CaseInsensitiveFilter::new(SimpleFilter::default())),
+        ];
+
+        for filter in filters.iter_mut() {
+            assert!(filter.detect(b"input1"));
+            assert!(!filter.detect(b"input1"));
+
+            filter.clear();
+
+            assert!(!filter.contains(b"input1"));
+            assert!(filter.detect(b"input1"));
+        }
+
+        let mut filter = QuickFilter::default();
+
+        filter.reserve(1024);
+        filter.detect(b"input1");
+        filter.clear();
+
+        assert!(filter.inner.capacity() >= 1024);
+    }
+
+    #[test]
+    fn filter_cardinality() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut filters: Vec<Box<dyn Filter>> = vec![
+            Box::<CompactFilter>::default(),
+            Box::new(DiskFilter::new(dir.path()).unwrap()),
+            Box::<QuickFilter>::default(),
+            Box::<SimpleFilter>::default(),
+            Box::<SortedFilter>::default(),
+            Box::new(TtlFilter::new(Duration::from_secs(60))),
+            Box::new(WindowFilter::new(10)),
+            Box::new(CaseInsensitiveFilter::new(SimpleFilter::default())),
+        ];
+
+        for filter in filters.iter_mut() {
+            assert!(filter.is_empty());
+
+            filter.detect(b"input1");
+            filter.detect(b"input1");
+            filter.detect(b"input2");
+
+            assert_eq!(filter.len(), 2);
+
+            filter.clear();
+
+            assert_eq!(filter.len(), 0);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn filter_serialization() {
+        fn roundtrip<F>(mut filter: F)
+        where
+            F: Filter + serde::Serialize + serde::de::DeserializeOwned,
+        {
+            filter.detect(b"input1");
+
+            let bytes = bincode::serialize(&filter).unwrap();
+            let mut restored: F = bincode::deserialize(&bytes).unwrap();
+
+            assert_eq!(restored.len(), 1);
+            assert!(!restored.detect(b"input1"));
+            assert!(restored.detect(b"input2"));
+        }
+
+        roundtrip(CompactFilter::with_seed(1));
+        roundtrip(QuickFilter::with_seed(1));
+        roundtrip(SimpleFilter::default());
+    }
+
+    #[test]
+    fn filter_merging() {
+        fn merged<F: MergeableFilter>(mut lhs: F, mut rhs: F) {
+            lhs.detect(b"input1");
+            lhs.detect(b"input2");
+            rhs.detect(b"input2");
+            rhs.detect(b"input3");
+
+            lhs.merge(rhs);
+
+            assert_eq!(lhs.len(), 3);
+            assert!(lhs.contains(b"input1"));
+            assert!(lhs.contains(b"input3"));
+            assert!(!lhs.contains(b"input4"));
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+
+        merged(
+            DiskFilter::new(dir.path()).unwrap(),
+            DiskFilter::new(dir.path()).unwrap(),
+        );
+        merged(QuickFilter::with_seed(1), QuickFilter::with_seed(1));
+        merged(SimpleFilter::default(), SimpleFilter::default());
+        merged(
+            CaseInsensitiveFilter::new(SimpleFilter::default()),
+            CaseInsensitiveFilter::new(SimpleFilter::default()),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "filters must use the same seed")]
+    fn filter_merging_seeds() {
+        QuickFilter::with_seed(1).merge(QuickFilter::with_seed(2));
+    }
+
+    #[test]
+    fn concurrent_filter_detection() {
+        let filter = ConcurrentFilter::with_shards(6, 0);
+        let uniques = AtomicUsize::new(0);
+
+        assert_eq!(filter.shards.len(), 8);
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for value in 0..1000 {
+                        if filter.detect(value.to_string().as_bytes()) {
+                            uniques.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(uniques.load(Ordering::Relaxed), 1000);
+        assert_eq!(Filter::len(&filter), 1000);
+        assert!(filter.contains(b"999"));
+
+        let filter = ConcurrentFilter::with_shards(1, 0);
+
+        assert!(filter.detect(b"input1"));
+        assert!(!filter.detect(b"input1"));
+    }
+
+    #[test]
+    fn case_insensitive_filter_detection() {
+        let mut filter = CaseInsensitiveFilter::new(SimpleFilter::default());
+
+        let ins1 = filter.detect(b"Input1");
+        let ins2 = filter.detect(b"INPUT1");
+        let ins3 = filter.detect("ÄPFEL".as_bytes());
+        let ins4 = filter.detect("äpfel".as_bytes());
+        let ins5 = filter.detect(b"\xFFInput1");
+        let ins6 = filter.detect(b"\xFFinput1");
+
+        assert!(ins1);
+        assert!(!ins2);
+        assert!(ins3);
+        assert!(!ins4);
+        assert!(ins5);
+        assert!(!ins6);
+        assert!(filter.into_inner().inner.contains(&b"input1"[..]));
+    }
+
+    #[test]
+    fn normalized_filter_detection() {
+        let mut filter = NormalizedFilter::new(SimpleFilter::default(), Normalization::Nfc);
+
+        let ins1 = filter.detect("caf\u{e9}".as_bytes());
+        let ins2 = filter.detect("cafe\u{301}".as_bytes());
+        let ins3 = filter.detect("\u{fb01}".as_bytes());
+        let ins4 = filter.detect(b"\xFFcafe");
+
+        assert!(ins1);
+        assert!(!ins2);
+        assert!(ins3);
+        assert!(ins4);
+
+        let mut filter = NormalizedFilter::new(SimpleFilter::default(), Normalization::Nfkc);
+
+        let ins1 = filter.detect("\u{fb01}".as_bytes());
+        let ins2 = filter.detect(b"fi");
+
+        assert!(ins1);
+        assert!(!ins2);
+        assert!(filter.into_inner().inner.contains(&b"fi"[..]));
+    }
+
+    #[test]
+    fn filter_state_restoration() {
+        let kinds = [
+            Filters::Quick,
+            Filters::Simple,
+            Filters::Sorted,
+            Filters::Compact,
+            Filters::Cuckoo,
+            Filters::Quick128,
+            Filters::Disk,
+        ];
+
+        for kind in &kinds {
+            let mut filter = kind.into_filter();
+            let mut state = Vec::new();
+
+            assert!(filter.detect(b"input1"));
+            filter.save_state(&mut state).unwrap();
+
+            let mut restored = kind.into_filter();
+            restored.load_state(&mut &state[..]).unwrap();
+
+            let ins1 = restored.detect(b"input1");
+            let ins2 = restored.detect(b"input2");
+
+            assert!(!ins1, "{:?} restored without state", kind);
+            assert!(ins2, "{:?} restored with extra state", kind);
+            assert!(restored.load_state(&mut &state[..4]).is_err());
+        }
+    }
+
+    #[test]
+    fn filter_from_name() {
+        assert!(matches!(Filters::from_name("quick"), Some(Filters::Quick)));
+        assert!(matches!(
+            Filters::from_name("Simple"),
+            Some(Filters::Simple)
+        ));
+        assert!(matches!(
+            Filters::from_name("SORTED"),
+            Some(Filters::Sorted)
+        ));
+        assert!(matches!(
+            Filters::from_name("compact"),
+            Some(Filters::Compact)
+        ));
+        assert!(matches!(Filters::from_name("disk"), Some(Filters::Disk)));
+        assert!(Filters::from_name("unknown").is_none());
+    }
+
+    #[test]
+    fn filter_memory_estimates() {
+        let mut quick = QuickFilter::default();
+        let empty = quick.memory().unwrap();
+
+        for value in 0..1000u32 {
+            quick.detect(&value.to_le_bytes());
+        }
+
+        assert!(quick.memory().unwrap() >= empty + 1000 * 8);
+
+        let disk = DiskFilter::new(env::temp_dir()).unwrap();
+
+        assert_eq!(disk.memory(), None);
+    }
+
+    #[test]
+    fn filter_into_filter() {
+        let mut filter = Filters::from_name("quick").unwrap().into_filter();
+
+        let ins1 = filter.detect(b"input1");
+        let ins2 = filter.detect(b"input1");
+
+        assert!(ins1);
+        assert!(!ins2);
+
+        let mut filter: Box<dyn Filter> = Filters::Simple.into();
+
+        assert!(filter.detect(b"input1"));
+        assert!(!filter.detect(b"input1"));
+
+        let mut filter = Filters::Disk.try_into_filter().unwrap();
+
+        assert!(filter.try_detect(b"input1").unwrap());
+        assert!(!filter.try_detect(b"input1").unwrap());
     }
 }
@@ -0,0 +1,528 @@
+//! Input module used to read records from input sources.
+//!
+//! Records are typically lines of input, but the `Records` trait
+//! allows for other formats to be read from a source (such as any
+//! kind of length-delimited binary messages).
+use memchr::memmem;
+use memmap2::Mmap;
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+/// Interval between polls for new data when following a file.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Expands input names into the list of inputs to read.
+///
+/// Names containing glob characters are expanded to all matching
+/// paths (unless a path with the literal name exists), and directories
+/// are expanded to all files beneath them when recursive. Expansions
+/// are sorted to keep the ordering stable, and any other names (such
+/// as `-` or URLs) are kept as they are.
+pub fn expand(names: &[String], recursive: bool) -> io::Result<Vec<String>> {
+    let mut inputs = Vec::with_capacity(names.len());
+
+    for name in names {
+        if name == "-" || name.contains("://") {
+            inputs.push(name.clone());
+            continue;
+        }
+
+        let mut paths = Vec::new();
+
+        if name.contains(['*', '?', '[']) && !Path::new(name).exists() {
+            let matches = glob::glob(name).map_err(|err| {
+                let message = format!("invalid pattern '{}': {}", name, err);
+                io::Error::new(io::ErrorKind::InvalidInput, message)
+            })?;
+
+            for path in matches {
+                paths.push(path.map_err(io::Error::from)?);
+            }
+
+            paths.sort();
+        }
+
+        // unmatched patterns are kept, to fail when opened
+        if paths.is_empty() {
+            paths.push(name.into());
+        }
+
+        for path in paths {
+            if !path.is_dir() {
+                inputs.push(path.to_string_lossy().into_owned());
+                continue;
+            }
+
+            if !recursive {
+                let message = format!("cannot open '{}': is a directory", path.display());
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            }
+
+            walk(&path, &mut inputs)?;
+        }
+    }
+
+    Ok(inputs)
+}
+
+/// Walks a directory recursively, collecting all files in name order.
+///
+/// Symbolic links to directories are not followed, to avoid any cycles
+/// in the directory tree.
+fn walk(dir: &Path, inputs: &mut Vec<String>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            walk(&path, inputs)?;
+        } else if !path.is_dir() {
+            inputs.push(path.to_string_lossy().into_owned());
+        }
+    }
+
+    Ok(())
+}
+
+/// Calculates the total size of all inputs, if known.
+///
+/// The size is only known when every input is a regular file, as the
+/// size of a stream (such as stdin) cannot be known up front.
+pub fn size(names: &[String]) -> Option<u64> {
+    names
+        .iter()
+        .map(|name| match fs::metadata(name) {
+            Ok(metadata) if name != "-" && metadata.is_file() => Some(metadata.len()),
+            _ => None,
+        })
+        .sum()
+}
+
+/// Opens an input by name as a reader.
+///
+/// The name `-` is used to read from stdin, and (when enabled) any
+/// remote names are streamed over the network; anything else is read
+/// as a file.
+pub fn open(name: &str) -> io::Result<Box<dyn Read + Send>> {
+    match name {
+        "-" => Ok(Box::new(io::stdin())),
+        #[cfg(feature = "net")]
+        any if crate::net::is_remote(any) => crate::net::open(any),
+        any => Ok(Box::new(File::open(any)?)),
+    }
+}
+
+/// Opens a file as a reader which follows any data appended to it.
+///
+/// The reader never reaches the end of the file; it instead polls
+/// the file for new data, in the same way as `tail -f`. If the file
+/// is truncated (such as by log rotation), it's read from the start.
+pub fn follow(name: &str) -> io::Result<Box<dyn Read + Send>> {
+    Ok(Box::new(Follow {
+        file: File::open(name)?,
+        position: 0,
+    }))
+}
+
+/// Reader to follow a file as it grows.
+struct Follow {
+    file: File,
+    position: u64,
+}
+
+/// Implement `Read` to poll for data appended to the file.
+impl Read for Follow {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.file.read(buf)?;
+
+            if read > 0 || buf.is_empty() {
+                self.position += read as u64;
+                return Ok(read);
+            }
+
+            // restart from the beginning if the file was truncated
+            if self.file.metadata()?.len() < self.position {
+                self.file.seek(SeekFrom::Start(0))?;
+                self.position = 0;
+                continue;
+            }
+
+            thread::sleep(FOLLOW_INTERVAL);
+        }
+    }
+}
+
+/// Strips any byte order mark from the start of a record.
+pub fn unmark(record: &[u8]) -> &[u8] {
+    match record {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => rest,
+        _ => record,
+    }
+}
+
+/// Format enum to represent the layout of records in an input.
+#[derive(Clone, Debug)]
+pub enum Format {
+    /// Records separated by a delimiter (typically newlines).
+    Lines(Vec<u8>),
+
+    /// Rows of CSV, which may span lines within quoted values.
+    Csv,
+
+    /// FASTA records, spanning a header and lines of sequence.
+    Fasta,
+
+    /// FASTQ records, spanning four lines each.
+    Fastq,
+
+    /// Length-delimited protobuf messages.
+    #[cfg(feature = "protobuf")]
+    Messages,
+}
+
+impl Format {
+    /// Opens an input by name as a record reader of this format.
+    ///
+    /// Non-empty regular files are memory-mapped when reading delimited
+    /// records, so that records can be read without being copied. All
+    /// other inputs (such as pipes) fall back to buffered reads, using
+    /// a buffer of `capacity` bytes.
+    pub fn open(&self, name: &str, capacity: usize) -> io::Result<Box<dyn Records + Send>> {
+        if let (Format::Lines(delimiter), false) = (self, name == "-" || name.contains("://")) {
+            let file = File::open(name)?;
+            let metadata = file.metadata()?;
+
+            if metadata.is_file() && metadata.len() > 0 {
+                return Ok(Box::new(Mapped::new(&file, delimiter)?));
+            }
+
+            return Ok(self.records(file, capacity));
+        }
+        Ok(self.records(open(name)?, capacity))
+    }
+
+    /// Determines whether records of this format are textual.
+    ///
+    /// Byte order marks are only meaningful in textual formats.
+    pub fn is_textual(&self) -> bool {
+        match self {
+            #[cfg(feature = "protobuf")]
+            Format::Messages => false,
+            _ => true,
+        }
+    }
+
+    /// Creates a record reader of this format from a reader.
+    ///
+    /// The reader is buffered using a buffer of `capacity` bytes.
+    pub fn records<'a, R: Read + Send + 'a>(
+        &self,
+        reader: R,
+        capacity: usize,
+    ) -> Box<dyn Records + Send + 'a> {
+        let reader = BufReader::with_capacity(capacity, reader);
+        match self {
+            Format::Lines(delimiter) => Box::new(Lines::new(reader, delimiter)),
+            Format::Csv => Box::new(Rows::new(reader)),
+            Format::Fasta => Box::new(crate::sequences::Fasta::new(reader)),
+            Format::Fastq => Box::new(crate::sequences::Fastq::new(reader)),
+            #[cfg(feature = "protobuf")]
+            Format::Messages => Box::new(crate::protobuf::Messages::new(reader)),
+        }
+    }
+}
+
+/// Trait for any type which can read a sequence of records.
+pub trait Records {
+    /// Reads the next record from the source.
+    ///
+    /// Records are returned as slices of bytes, borrowed from the
+    /// internal buffer of the reader until the next call. A value
+    /// of `None` is used to signal the end of input.
+    fn next_record(&mut self) -> io::Result<Option<&[u8]>>;
+
+    /// Reads the next record, alongside whether it's already known to be unique.
+    ///
+    /// Records are only detected ahead of time when read on a worker
+    /// thread with a shared filter; all other sources return `None`.
+    fn next_detected(&mut self) -> io::Result<Option<(&[u8], Option<bool>)>> {
+        Ok(self.next_record()?.map(|record| (record, None)))
+    }
+
+    /// Retrieves the byte offset of the next record in the source.
+    fn offset(&self) -> u64;
+}
+
+/// Reader for delimited records (typically newlines).
+///
+/// Delimiters can be any non-empty sequence of bytes. Trailing
+/// delimiters are stripped from the returned records, but are
+/// included in the tracked offsets. When delimited by newlines,
+/// a trailing `\r` is also stripped to support `\r\n` endings.
+pub struct Lines<B> {
+    reader: B,
+    buffer: Vec<u8>,
+    delimiter: Vec<u8>,
+    offset: u64,
+}
+
+impl<B: BufRead> Lines<B> {
+    /// Creates a new `Lines` reader from a buffered reader.
+    pub fn new(reader: B, delimiter: &[u8]) -> Lines<B> {
+        assert!(!delimiter.is_empty(), "delimiter must not be empty");
+        Lines {
+            reader,
+            buffer: Vec::new(),
+            delimiter: delimiter.to_vec(),
+            offset: 0,
+        }
+    }
+}
+
+/// Implement `Records` for delimited records of input.
+impl<B: BufRead> Records for Lines<B> {
+    fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        self.buffer.clear();
+
+        let last = self.delimiter[self.delimiter.len() - 1];
+
+        // read until the final byte of the delimiter, until it's complete
+        loop {
+            let read = self.reader.read_until(last, &mut self.buffer)?;
+
+            if read == 0 || self.buffer.ends_with(&self.delimiter) {
+                break;
+            }
+        }
+
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+
+        self.offset += self.buffer.len() as u64;
+
+        let mut len = self.buffer.len();
+
+        if self.buffer.ends_with(&self.delimiter) {
+            len -= self.delimiter.len();
+            if self.delimiter == b"\n" && len > 0 && self.buffer[len - 1] == b'\r' {
+                len -= 1;
+            }
+        }
+
+        Ok(Some(&self.buffer[..len]))
+    }
+
+    #[inline]
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+/// Reader for rows of CSV (as per RFC 4180).
+///
+/// Rows are returned as they appear in the input (minus the trailing
+/// newline) so that they can be emitted as valid CSV. Newlines within
+/// quoted values do not end a row.
+pub struct Rows<B> {
+    reader: B,
+    buffer: Vec<u8>,
+    offset: u64,
+}
+
+impl<B: BufRead> Rows<B> {
+    /// Creates a new `Rows` reader from a buffered reader.
+    pub fn new(reader: B) -> Rows<B> {
+        Rows {
+            reader,
+            buffer: Vec::new(),
+            offset: 0,
+        }
+    }
+}
+
+/// Implement `Records` for rows of CSV input.
+impl<B: BufRead> Records for Rows<B> {
+    fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        self.buffer.clear();
+
+        let mut quoted = false;
+
+        // read lines until all quoted values have been closed
+        loop {
+            let start = self.buffer.len();
+            let read = self.reader.read_until(b'\n', &mut self.buffer)?;
+
+            if read == 0 {
+                break;
+            }
+
+            let quotes = self.buffer[start..].iter().filter(|byte| **byte == b'"');
+
+            quoted ^= quotes.count() % 2 == 1;
+
+            if !quoted {
+                break;
+            }
+        }
+
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+
+        self.offset += self.buffer.len() as u64;
+
+        let mut row = &self.buffer[..];
+
+        if let [rest @ .., b'\n'] = row {
+            row = rest;
+            if let [rest @ .., b'\r'] = row {
+                row = rest;
+            }
+        }
+
+        Ok(Some(row))
+    }
+
+    #[inline]
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+/// Reader for delimited records within a memory-mapped file.
+///
+/// Records are borrowed directly from the mapping rather than being
+/// copied into a buffer, and are otherwise identical to `Lines`.
+pub struct Mapped {
+    map: Mmap,
+    delimiter: Vec<u8>,
+    offset: usize,
+}
+
+impl Mapped {
+    /// Creates a new `Mapped` reader by mapping a file.
+    pub fn new(file: &File, delimiter: &[u8]) -> io::Result<Mapped> {
+        assert!(!delimiter.is_empty(), "delimiter must not be empty");
+
+        // SAFETY: the mapping is read-only, and inputs are not expected to
+        // be truncated whilst being read (just as with any other reader).
+        let map = unsafe { Mmap::map(file)? };
+
+        #[cfg(unix)]
+        let _ = map.advise(memmap2::Advice::Sequential);
+
+        Ok(Mapped {
+            map,
+            delimiter: delimiter.to_vec(),
+            offset: 0,
+        })
+    }
+}
+
+/// Implement `Records` for delimited records of a mapping.
+impl Records for Mapped {
+    fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        let remaining = &self.map[self.offset..];
+
+        if remaining.is_empty() {
+            return Ok(None);
+        }
+
+        let position = match self.delimiter[..] {
+            [byte] => memchr::memchr(byte, remaining),
+            ref delimiter => memmem::find(remaining, delimiter),
+        };
+
+        let mut record = match position {
+            Some(position) => {
+                self.offset += position + self.delimiter.len();
+                &remaining[..position]
+            }
+            None => {
+                self.offset = self.map.len();
+                remaining
+            }
+        };
+
+        if position.is_some() && self.delimiter == b"\n" {
+            if let [rest @ .., b'\r'] = record {
+                record = rest;
+            }
+        }
+
+        Ok(Some(record))
+    }
+
+    #[inline]
+    fn offset(&self) -> u64 {
+        self.offset as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lines, Mapped, Records, Rows};
+
+    use std::io::Write;
+
+    fn records(input: &[u8], delimiter: &[u8]) -> Vec<Vec<u8>> {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(input).unwrap();
+
+        let mut lines = Lines::new(input, delimiter);
+        let mut mapped = Mapped::new(&file, delimiter).unwrap();
+        let mut records = Vec::new();
+
+        while let Some(record) = lines.next_record().unwrap() {
+            assert_eq!(mapped.next_record().unwrap(), Some(record));
+            records.push(record.to_vec());
+            assert_eq!(mapped.offset(), lines.offset());
+        }
+
+        assert_eq!(mapped.next_record().unwrap(), None);
+        assert_eq!(lines.offset(), input.len() as u64);
+        records
+    }
+
+    #[test]
+    fn newline_delimited_records() {
+        assert_eq!(
+            records(b"a\nb\r\nc", b"\n"),
+            vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]
+        );
+    }
+
+    #[test]
+    fn multi_byte_delimited_records() {
+        assert_eq!(
+            records(b"a;b::c:;:d::", b"::"),
+            vec![b"a;b".to_vec(), b"c:;:d".to_vec()]
+        );
+        assert_eq!(
+            records(b"a\nb\r\nc", b"\r\n"),
+            vec![b"a\nb".to_vec(), b"c".to_vec()]
+        );
+    }
+
+    #[test]
+    fn csv_rows() {
+        let input = b"a,\"b\nc\"\r\n\"d\"\"\ne\",f\ng";
+        let mut rows = Rows::new(&input[..]);
+
+        assert_eq!(rows.next_record().unwrap(), Some(&b"a,\"b\nc\""[..]));
+        assert_eq!(rows.offset(), 9);
+        assert_eq!(rows.next_record().unwrap(), Some(&b"\"d\"\"\ne\",f"[..]));
+        assert_eq!(rows.next_record().unwrap(), Some(&b"g"[..]));
+        assert_eq!(rows.next_record().unwrap(), None);
+        assert_eq!(rows.offset(), input.len() as u64);
+    }
+}
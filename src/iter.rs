@@ -0,0 +1,96 @@
+//! Iterator module used to filter unique values from iterators.
+use crate::filters::Filter;
+
+/// Extension trait to filter unique values from an `Iterator`.
+///
+/// This is implemented for any iterator with values which can be viewed
+/// as bytes, such as `String`, `&str` or `Vec<u8>`.
+pub trait RuniqIteratorExt: Iterator + Sized
+where
+    Self::Item: AsRef<[u8]>,
+{
+    /// Creates an iterator which only yields the first occurrence of a value.
+    ///
+    /// Values are checked against the provided `Filter`, which can be
+    /// retrieved afterwards via `UniqueVia::into_filter`.
+    fn unique_via<F: Filter>(self, filter: F) -> UniqueVia<Self, F> {
+        UniqueVia {
+            inner: self,
+            filter,
+        }
+    }
+}
+
+/// Implement the extension for all iterators of byte-like values.
+impl<I> RuniqIteratorExt for I
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+}
+
+/// Iterator yielding values not previously seen by a `Filter`.
+///
+/// This is created via `RuniqIteratorExt::unique_via`.
+#[derive(Debug)]
+pub struct UniqueVia<I, F> {
+    inner: I,
+    filter: F,
+}
+
+impl<I, F> UniqueVia<I, F> {
+    /// Retrieves a reference to the internal filter.
+    pub fn as_filter(&self) -> &F {
+        &self.filter
+    }
+
+    /// Consumes the iterator, returning the internal filter.
+    pub fn into_filter(self) -> F {
+        self.filter
+    }
+}
+
+/// Implement `Iterator` to skip values already seen.
+impl<I, F> Iterator for UniqueVia<I, F>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+    F: Filter,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let filter = &mut self.filter;
+        self.inner.find(|value| filter.detect(value.as_ref()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::{Filter, QuickFilter};
+    #[cfg(not(feature = "std"))]
+    use alloc::{vec, vec::Vec};
+
+    #[test]
+    fn unique_iteration() {
+        let inputs = vec!["a", "b", "a", "c", "b"];
+        let mut uniques = inputs.into_iter().unique_via(QuickFilter::default());
+
+        assert_eq!(uniques.size_hint(), (0, Some(5)));
+        assert_eq!(uniques.by_ref().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+        assert_eq!(uniques.as_filter().len(), 3);
+
+        let filter = uniques.into_filter();
+        let outputs = vec![b"c".to_vec(), b"d".to_vec()]
+            .into_iter()
+            .unique_via(filter)
+            .collect::<Vec<_>>();
+
+        assert_eq!(outputs, vec![b"d".to_vec()]);
+    }
+}
@@ -0,0 +1,100 @@
+//! JSON module used to extract keys from JSON Lines input.
+//!
+//! Each record is parsed as a JSON document, and the key is taken from
+//! the value at a dotted path within the document. Values are only
+//! parsed along the path, so the rest of each document is never fully
+//! deserialized. The original record is emitted.
+use serde_json::value::RawValue;
+use serde_json::{Error, Value};
+
+use std::collections::HashMap;
+
+/// Extractor used to pull a key from a JSON document.
+#[derive(Clone, Debug)]
+pub struct Extractor {
+    path: Vec<String>,
+}
+
+impl Extractor {
+    /// Creates a new `Extractor` for a dotted path.
+    ///
+    /// Each segment of the path names a field of an object, or an index
+    /// of an array (such as `events.0.id`).
+    pub fn new(path: &str) -> Extractor {
+        Extractor {
+            path: path.split('.').map(str::to_string).collect(),
+        }
+    }
+
+    /// Extracts the key value from a JSON document.
+    ///
+    /// Values are written as canonical compact JSON (with strings quoted
+    /// and object fields sorted), so equivalent values always produce the
+    /// same key and values of different types never collide. Missing
+    /// values produce an empty key, which no JSON value can serialize to.
+    pub fn extract(&self, input: &[u8], key: &mut Vec<u8>) -> Result<(), Error> {
+        let mut value: &RawValue = serde_json::from_slice(input)?;
+
+        for segment in &self.path {
+            let json = value.get();
+            let next = match json.as_bytes().first() {
+                Some(b'{') => {
+                    serde_json::from_str::<HashMap<String, &RawValue>>(json)?.remove(segment)
+                }
+                Some(b'[') => segment
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| {
+                        serde_json::from_str::<Vec<&RawValue>>(json)
+                            .map(|mut values| {
+                                (index < values.len()).then(|| values.swap_remove(index))
+                            })
+                            .transpose()
+                    })
+                    .transpose()?,
+                _ => None,
+            };
+
+            value = match next {
+                Some(next) => next,
+                None => return Ok(()),
+            };
+        }
+
+        let mut value: Value = serde_json::from_str(value.get())?;
+        value.sort_all_objects();
+        serde_json::to_writer(key, &value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Extractor;
+
+    fn extract(path: &str, input: &str) -> Vec<u8> {
+        let mut key = Vec::new();
+        Extractor::new(path)
+            .extract(input.as_bytes(), &mut key)
+            .unwrap();
+        key
+    }
+
+    #[test]
+    fn json_key_extraction() {
+        let input = r#"{"id":"a\"b","meta":{"tags":[1,{"y":2, "x":1}]}}"#;
+
+        assert_eq!(extract("id", input), br#""a\"b""#);
+        assert_eq!(extract("meta.tags.0", input), b"1");
+        assert_eq!(extract("meta.tags.1", input), br#"{"x":1,"y":2}"#);
+        assert_eq!(extract("meta.tags.2", input), b"");
+        assert_eq!(extract("meta.missing", input), b"");
+        assert_eq!(extract("id.nested", input), b"");
+
+        assert_ne!(extract("id", r#"{"id":1}"#), extract("id", r#"{"id":"1"}"#));
+        assert_ne!(extract("id", r#"{"id":""}"#), extract("id", r#"{}"#));
+
+        assert!(Extractor::new("id")
+            .extract(b"{\"id\":", &mut Vec::new())
+            .is_err());
+    }
+}
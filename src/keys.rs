@@ -0,0 +1,409 @@
+//! Keys module used to select the parts of an entry to filter on.
+//!
+//! By default the entire entry is used to detect uniques, but it's
+//! possible to select a subset of an entry (such as a set of fields)
+//! so that only that subset determines uniqueness. The full entry is
+//! still emitted as output.
+use regex::bytes::Regex;
+
+use std::borrow::Cow;
+use std::io;
+
+/// Selector for a key made from one or more delimited fields.
+///
+/// Fields are indexed from 1, and split on a delimiter (or runs of
+/// whitespace if no delimiter is provided). Missing fields are treated
+/// as being empty.
+#[derive(Clone, Debug)]
+pub struct Fields {
+    delimiter: Option<Vec<u8>>,
+    indices: Vec<usize>,
+}
+
+impl Fields {
+    /// Creates a new `Fields` selector from a set of field indices.
+    pub fn new(indices: Vec<usize>, delimiter: Option<&str>) -> Fields {
+        Fields {
+            delimiter: delimiter.map(|delimiter| delimiter.as_bytes().to_vec()),
+            indices,
+        }
+    }
+
+    /// Extracts the selected fields into the provided key buffer.
+    ///
+    /// A single field is written as is, but multiple fields are encoded
+    /// so that the key is unambiguous; every zero byte is escaped as
+    /// `0x00 0x01` and every field is terminated by `0x00 0x00`. This means
+    /// that fields `("a", "bc")` and `("ab", "c")` can never collide,
+    /// and the ordering of keys is the same as the ordering of fields.
+    pub fn extract(&self, input: &[u8], key: &mut Vec<u8>) {
+        for &index in &self.indices {
+            let field = self.field(input, index).unwrap_or_default();
+            push_field(key, field, self.indices.len() > 1);
+        }
+    }
+
+    /// Locates a field (indexed from 1) within the input.
+    fn field<'a>(&self, input: &'a [u8], index: usize) -> Option<&'a [u8]> {
+        match self.delimiter {
+            None => input
+                .split(|byte| byte.is_ascii_whitespace())
+                .filter(|field| !field.is_empty())
+                .nth(index - 1),
+            Some(ref delimiter) => {
+                let mut remaining = input;
+
+                for _ in 1..index {
+                    let position = find(remaining, delimiter)?;
+                    remaining = &remaining[position + delimiter.len()..];
+                }
+
+                match find(remaining, delimiter) {
+                    Some(position) => Some(&remaining[..position]),
+                    None => Some(remaining),
+                }
+            }
+        }
+    }
+}
+
+/// Selector for a key made from one or more columns of a CSV row.
+///
+/// Columns can be selected by index (starting from 1) or by name, in
+/// which case they're resolved against the header row of the input.
+/// Missing columns are treated as being empty.
+#[derive(Clone, Debug)]
+pub struct Columns {
+    indices: Vec<usize>,
+    selectors: Vec<String>,
+}
+
+impl Columns {
+    /// Creates a new `Columns` selector from a set of column selectors.
+    pub fn new(selectors: Vec<String>) -> Columns {
+        Columns {
+            indices: Vec::new(),
+            selectors,
+        }
+    }
+
+    /// Resolves the selected columns against a CSV header row.
+    pub fn resolve(&mut self, header: &[u8]) -> io::Result<()> {
+        let names = parse_row(header);
+
+        self.indices = self
+            .selectors
+            .iter()
+            .map(|selector| match selector.parse::<usize>() {
+                Ok(index) => Ok(index),
+                Err(_) => names
+                    .iter()
+                    .position(|name| &name[..] == selector.as_bytes())
+                    .map(|position| position + 1)
+                    .ok_or_else(|| {
+                        let message = format!("no column named '{}' in CSV header", selector);
+                        io::Error::new(io::ErrorKind::InvalidInput, message)
+                    }),
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(())
+    }
+
+    /// Extracts the selected columns into the provided key buffer.
+    ///
+    /// Values are unquoted before being written, and multiple columns
+    /// are encoded in the same way as `Fields::extract`.
+    pub fn extract(&self, row: &[u8], key: &mut Vec<u8>) {
+        let values = parse_row(row);
+
+        for &index in &self.indices {
+            let value = values.get(index - 1).map(|value| &value[..]);
+            push_field(key, value.unwrap_or_default(), self.indices.len() > 1);
+        }
+    }
+}
+
+/// Parses a CSV row into the (unquoted) values of each column.
+///
+/// Quoted values may contain commas, newlines, and escaped quotes (as
+/// `""`). Parsing is lenient, so any content between the closing quote
+/// of a value and the next comma is ignored rather than rejected.
+pub fn parse_row(row: &[u8]) -> Vec<Cow<'_, [u8]>> {
+    let mut values = Vec::new();
+    let mut remaining = row;
+
+    loop {
+        let end = match remaining {
+            [b'"', quoted @ ..] => {
+                let mut value = Vec::new();
+                let mut position = 0;
+
+                while position < quoted.len() {
+                    match (quoted[position], quoted.get(position + 1)) {
+                        (b'"', Some(b'"')) => position += 1,
+                        (b'"', _) => break,
+                        _ => (),
+                    }
+                    value.push(quoted[position]);
+                    position += 1;
+                }
+
+                values.push(Cow::Owned(value));
+                remaining = &quoted[position.min(quoted.len())..];
+                remaining.iter().position(|byte| *byte == b',')
+            }
+            _ => {
+                let end = remaining.iter().position(|byte| *byte == b',');
+                values.push(Cow::Borrowed(&remaining[..end.unwrap_or(remaining.len())]));
+                end
+            }
+        };
+
+        match end {
+            Some(end) => remaining = &remaining[end + 1..],
+            None => return values,
+        }
+    }
+}
+
+/// Pushes a field into a key, escaping it when combining fields.
+///
+/// When escaped, every zero byte is written as `0x00 0x01` and the
+/// field is terminated by `0x00 0x00`.
+fn push_field(key: &mut Vec<u8>, field: &[u8], escaped: bool) {
+    if !escaped {
+        key.extend_from_slice(field);
+        return;
+    }
+
+    for &byte in field {
+        key.push(byte);
+        if byte == 0 {
+            key.push(1);
+        }
+    }
+
+    key.extend_from_slice(&[0, 0]);
+}
+
+/// Strips ANSI escape sequences from an entry into the provided buffer.
+///
+/// This covers control sequences (such as colors and cursor movement),
+/// operating system commands (such as window titles and hyperlinks),
+/// and any other escape sequences (such as character set selection).
+pub fn strip_ansi(input: &[u8], output: &mut Vec<u8>) {
+    let mut position = 0;
+
+    while position < input.len() {
+        if input[position] != 0x1B {
+            output.push(input[position]);
+            position += 1;
+            continue;
+        }
+
+        let rest = &input[position + 1..];
+        let length = match rest.first() {
+            // control sequences end with a byte in 0x40..=0x7E
+            Some(b'[') => rest[1..]
+                .iter()
+                .position(|byte| (0x40..=0x7E).contains(byte))
+                .map_or(rest.len(), |end| end + 2),
+            // commands end with a BEL, or an ESC followed by a backslash
+            Some(b']') => rest
+                .windows(2)
+                .position(|window| window[0] == 0x07 || window == b"\x1B\\")
+                .map_or(rest.len(), |end| end + 1 + (rest[end] == 0x1B) as usize),
+            // anything else is any intermediate bytes, then a final byte
+            Some(_) => rest
+                .iter()
+                .position(|byte| !(0x20..=0x2F).contains(byte))
+                .map_or(rest.len(), |end| end + 1),
+            None => 0,
+        };
+
+        position += length + 1;
+    }
+}
+
+/// Removes all matches of a pattern from an entry into the provided buffer.
+pub fn mask(input: &[u8], pattern: &Regex, output: &mut Vec<u8>) {
+    let mut position = 0;
+
+    for matched in pattern.find_iter(input) {
+        output.extend_from_slice(&input[position..matched.start()]);
+        position = matched.end();
+    }
+
+    output.extend_from_slice(&input[position..]);
+}
+
+/// Trims leading and/or trailing ASCII whitespace from a key.
+pub fn trim(mut key: &[u8], start: bool, end: bool) -> &[u8] {
+    if start {
+        while let [first, rest @ ..] = key {
+            if !first.is_ascii_whitespace() {
+                break;
+            }
+            key = rest;
+        }
+    }
+    if end {
+        while let [rest @ .., last] = key {
+            if !last.is_ascii_whitespace() {
+                break;
+            }
+            key = rest;
+        }
+    }
+    key
+}
+
+/// Folds the case of an entry into the provided key buffer.
+///
+/// Valid UTF-8 is lowercased fully, whereas any other input only has
+/// the ASCII range lowercased (as there's no meaningful case to fold).
+pub fn fold_case(input: &[u8], key: &mut Vec<u8>) {
+    match std::str::from_utf8(input) {
+        Ok(value) if !value.is_ascii() => key.extend_from_slice(value.to_lowercase().as_bytes()),
+        _ => key.extend(input.iter().map(|byte| byte.to_ascii_lowercase())),
+    }
+}
+
+/// Finds the first position of a needle within a haystack.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.len() == 1 {
+        return haystack.iter().position(|byte| *byte == needle[0]);
+    }
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fold_case, mask, parse_row, strip_ansi, trim, Columns, Fields};
+    use regex::bytes::Regex;
+
+    fn extract(fields: &Fields, input: &[u8]) -> Vec<u8> {
+        let mut key = Vec::new();
+        fields.extract(input, &mut key);
+        key
+    }
+
+    #[test]
+    fn single_field_extraction() {
+        let fields = Fields::new(vec![2], Some(","));
+
+        assert_eq!(extract(&fields, b"a,b,c"), b"b");
+        assert_eq!(extract(&fields, b"a,,c"), b"");
+        assert_eq!(extract(&fields, b"a"), b"");
+    }
+
+    #[test]
+    fn whitespace_field_extraction() {
+        let fields = Fields::new(vec![2], None);
+
+        assert_eq!(extract(&fields, b"  a \t b  c"), b"b");
+    }
+
+    #[test]
+    fn multi_byte_delimiter_extraction() {
+        let fields = Fields::new(vec![3], Some("::"));
+
+        assert_eq!(extract(&fields, b"a::b:c::d"), b"d");
+    }
+
+    #[test]
+    fn composite_field_extraction() {
+        let fields = Fields::new(vec![1, 3], Some(","));
+
+        assert_eq!(extract(&fields, b"a,x,b"), extract(&fields, b"a,y,b"));
+        assert_ne!(extract(&fields, b"a,x,bc"), extract(&fields, b"ab,x,c"));
+        assert_ne!(extract(&fields, b"a\0,x,b"), extract(&fields, b"a,x,\0b"));
+        assert_ne!(
+            extract(&fields, b"a\0,x,\xFF"),
+            extract(&fields, b"a,x,\xFF\0")
+        );
+        assert!(extract(&fields, b"a,x,b") < extract(&fields, b"a\0,x,b"));
+    }
+
+    #[test]
+    fn csv_row_parsing() {
+        let values = parse_row(b"a,\"b,\"\"c\"\"\",,\"d\ne\"");
+
+        assert_eq!(values.len(), 4);
+        assert_eq!(&values[0][..], b"a");
+        assert_eq!(&values[1][..], b"b,\"c\"");
+        assert_eq!(&values[2][..], b"");
+        assert_eq!(&values[3][..], b"d\ne");
+    }
+
+    #[test]
+    fn column_extraction() {
+        let mut columns = Columns::new(vec!["name".to_string(), "1".to_string()]);
+
+        assert!(columns.resolve(b"id,name").is_ok());
+
+        let mut key1 = Vec::new();
+        let mut key2 = Vec::new();
+
+        columns.extract(b"1,\"a,b\"", &mut key1);
+        columns.extract(b"\"1\",a\\,b", &mut key2);
+
+        assert_eq!(key1, b"a,b\x00\x001\x00\x00");
+        assert_ne!(key1, key2);
+
+        let mut columns = Columns::new(vec!["missing".to_string()]);
+
+        assert!(columns.resolve(b"id,name").is_err());
+    }
+
+    #[test]
+    fn case_folded_keys() {
+        let mut key = Vec::new();
+
+        fold_case("ÄPFEL Input".as_bytes(), &mut key);
+        assert_eq!(key, "äpfel input".as_bytes());
+
+        key.clear();
+        fold_case(b"\xFFInput", &mut key);
+        assert_eq!(key, b"\xFFinput");
+    }
+
+    #[test]
+    fn ansi_stripped_keys() {
+        let strip = |input: &[u8]| {
+            let mut key = Vec::new();
+            strip_ansi(input, &mut key);
+            key
+        };
+
+        assert_eq!(strip(b"\x1B[1;31mred\x1B[0m text"), b"red text");
+        assert_eq!(strip(b"\x1B]0;title\x07a\x1B]8;;url\x1B\\b"), b"ab");
+        assert_eq!(strip(b"\x1B(Bplain\x1B"), b"plain");
+        assert_eq!(strip(b"\x1B[31"), b"");
+    }
+
+    #[test]
+    fn masked_keys() {
+        let pattern = Regex::new(r"\d{2}:\d{2}|pid=\d+").unwrap();
+        let masked = |input: &[u8]| {
+            let mut key = Vec::new();
+            mask(input, &pattern, &mut key);
+            key
+        };
+
+        assert_eq!(masked(b"12:30 pid=42 started"), b"  started");
+        assert_eq!(masked(b"no matches"), b"no matches");
+        assert_eq!(masked(b"pid=1pid=2"), b"");
+    }
+
+    #[test]
+    fn trimmed_keys() {
+        assert_eq!(trim(b" \ta b \t", true, true), b"a b");
+        assert_eq!(trim(b" \ta b \t", true, false), b"a b \t");
+        assert_eq!(trim(b" \ta b \t", false, true), b" \ta b");
+        assert_eq!(trim(b" \t ", true, true), b"");
+    }
+}
@@ -13,6 +13,54 @@
 //! used as a library as the `Filter` trait is exposed publicly. If
 //! you are using Runiq as a library, do **not** rely on any modules
 //! hidden from the public documentation.
+//!
+//! Without the `std` feature, the library is `no_std` (requiring only
+//! `alloc`), and only provides the `SimpleFilter`, `QuickFilter` and
+//! `SortedFilter` types alongside the `Filter` traits.
 #![doc(html_root_url = "https://docs.rs/runiq/2.0.0")]
+#![cfg_attr(not(feature = "std"), no_std)]
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
 mod filters;
-pub use filters::{CompactFilter, Filter, Filters, QuickFilter, SimpleFilter, SortedFilter};
+mod iter;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "store")]
+mod store;
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "std")]
+mod write;
+#[cfg(feature = "std")]
+mod xor;
+#[cfg(feature = "std")]
+pub use filters::{
+    CaseInsensitiveFilter, CompactFilter, CompactFilterBuilder, ConcurrentFilter, CountingFilter,
+    CuckooFilter, DiskFilter, EstimateFilter, Filters, LruFilter, Normalization, NormalizedFilter,
+    Quick128Filter, TtlFilter, WindowFilter,
+};
+pub use filters::{
+    Filter, MergeableFilter, QuickFilter, SimpleFilter, SortedFilter, Xxh3Builder, Xxh3Hasher,
+};
+pub use iter::{RuniqIteratorExt, UniqueVia};
+#[cfg(feature = "python")]
+pub use python::PyFilter;
+#[cfg(feature = "std")]
+pub use stats::Stats;
+#[cfg(feature = "store")]
+pub use store::StoreFilter;
+#[cfg(feature = "async")]
+pub use stream::UniqueLines;
+#[cfg(feature = "wasm")]
+pub use wasm::{unique_lines, WasmFilter};
+#[cfg(feature = "std")]
+pub use write::UniqueWriter;
+#[cfg(feature = "std")]
+pub use xor::XorFilter;
@@ -13,107 +13,1029 @@
 //! used as a library as the `Filter` trait is exposed publicly. If
 //! you are using Runiq as a library, do **not** rely on any modules
 //! hidden from the public documentation.
-use bytelines::ByteLinesReader;
+use xxhash_rust::xxh3::xxh3_64_with_seed;
 
+mod bench;
+mod config;
+mod counter;
+mod deny;
+mod digest;
+mod input;
+mod json;
+mod keys;
+#[cfg(feature = "net")]
+mod net;
 mod options;
+mod output;
+#[cfg(feature = "plugin")]
+mod plugin;
+mod progress;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+mod sample;
+mod sequences;
+mod sources;
+mod spill;
+mod state;
 mod statistics;
 
-use crate::options::Options;
-use crate::statistics::Stats;
-use runiq::Filter;
+use crate::counter::Counter;
+use crate::input::Format;
+use crate::keys::{fold_case, mask, strip_ansi, trim, Columns, Fields};
+use crate::options::{OffsetScope, OnError, Options, StatisticsFormat, Unsampled};
+use crate::output::Output;
+use crate::progress::Progress;
+use crate::sample::Reservoir;
+use crate::sequences::SequenceFormat;
+use crate::sources::Sources;
+use crate::spill::Spill;
+use crate::statistics::Breakdown;
+use runiq::{
+    CompactFilter, ConcurrentFilter, CuckooFilter, DiskFilter, EstimateFilter, Filter, Filters,
+    LruFilter, Quick128Filter, QuickFilter, Stats, TtlFilter, WindowFilter,
+};
 
+#[cfg(feature = "store")]
+use runiq::StoreFilter;
+
+use std::cmp::{Ordering, Reverse};
 use std::env;
 use std::fs::File;
-use std::io::{self, BufReader, Read, Write};
-
-const EOL: &[u8; 1] = &[b'\n'];
+use std::io::{self, BufWriter, Write};
+use std::process;
+use std::str;
+use std::sync::Arc;
+use std::time::Instant;
 
-fn main() -> io::Result<()> {
-    let result = run();
+fn main() {
+    // parse in our options from the command line args
+    let options = Options::from(env::args_os());
+    let exit_code = options.exit_code;
 
-    if let Err(ref err) = result {
-        if err.kind() != io::ErrorKind::BrokenPipe {
-            return result;
+    match run(options) {
+        // exit codes reflect whether duplicates were found, as with grep
+        Ok(duplicates) if exit_code => process::exit(if duplicates { 0 } else { 1 }),
+        Ok(_) => (),
+        Err(err) => {
+            if err.kind() != io::ErrorKind::BrokenPipe {
+                eprintln!("runiq: {}", err);
+                process::exit(if exit_code { 2 } else { 1 });
+            }
         }
     }
+}
 
+/// Emits a warning to stderr, or an error when running in strict mode.
+fn warn(options: &Options, message: &str) -> io::Result<()> {
+    if options.strict {
+        return Err(io::Error::other(message));
+    }
+    eprintln!("runiq: warning: {}", message);
     Ok(())
 }
 
-fn run() -> io::Result<()> {
-    // parse in our options from the command line args
-    let options = Options::from(env::args_os());
+/// Compares two entries, optionally parsing them as numbers.
+///
+/// Numeric comparisons will return `None` if either entry cannot
+/// be parsed as a number, otherwise entries are compared as bytes.
+fn compare(input: &[u8], previous: &[u8], numeric: bool) -> Option<Ordering> {
+    if !numeric {
+        return Some(input.cmp(previous));
+    }
+    number(input)?.partial_cmp(&number(previous)?)
+}
 
-    // borrow IO for checker
-    let stdin = io::stdin();
-    let stdout = io::stdout();
+/// Orders two entries when sorting output, numerically if requested.
+///
+/// Unlike `compare` this is a total order, so numeric sorting places any
+/// non-numeric entries after all numeric entries.
+fn order(lhs: &[u8], rhs: &[u8], numeric: bool) -> Ordering {
+    if !numeric {
+        return lhs.cmp(rhs);
+    }
+    match (number(lhs), number(rhs)) {
+        (Some(left), Some(right)) => left.total_cmp(&right).then_with(|| lhs.cmp(rhs)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => lhs.cmp(rhs),
+    }
+}
 
-    // ensure all sources exist as readers
-    let readers: Vec<Box<dyn Read>> = options
-        .inputs
-        .iter()
-        .map(|input| -> Box<dyn Read> {
-            match input.as_ref() {
-                "-" => Box::new(stdin.lock()),
-                any => Box::new(File::open(any).unwrap()),
+/// Parses an entry as a number, ignoring surrounding whitespace.
+fn number(value: &[u8]) -> Option<f64> {
+    str::from_utf8(value).ok()?.trim().parse().ok()
+}
+
+/// Determines whether a hash falls within a sample of the provided rate.
+fn sampled(hash: u64, rate: f64) -> bool {
+    rate >= 1.0 || (hash as f64) < rate * (u64::MAX as f64)
+}
+
+/// Creates a boxed filter of the provided kind, using the seed from options.
+fn create_filter(kind: Filters, options: &Options) -> io::Result<Box<dyn Filter>> {
+    Ok(match kind {
+        Filters::Quick => Box::new(QuickFilter::with_seed(options.seed)),
+        Filters::Quick128 => Box::new(Quick128Filter::with_seed(options.seed)),
+        Filters::Compact => {
+            let mut builder = CompactFilter::builder().seed(options.seed);
+            if let Some(rate) = options.error_rate {
+                builder = builder.error_ratio(rate);
             }
-        })
-        .collect();
+            if let Some(factor) = options.growth_factor {
+                builder = builder.growth_factor(factor);
+            }
+            if let Some(ratio) = options.tightening_ratio {
+                builder = builder.tightening_ratio(ratio);
+            }
+            Box::new(builder.try_build()?)
+        }
+        Filters::Cuckoo => Box::new(CuckooFilter::with_seed(options.seed)),
+        Filters::Disk => {
+            let dir = options
+                .disk_dir
+                .clone()
+                .map_or_else(env::temp_dir, Into::into);
+            Box::new(DiskFilter::with_seed(dir, options.seed)?)
+        }
+        kind => kind.try_into_filter()?,
+    })
+}
+
+/// Determines whether uniques can be detected on worker threads.
+///
+/// Workers detect entire entries against a shared filter, so this is only
+/// possible when keys are the entries themselves, when using the default
+/// filter without any other state, and when occurrences aren't counted.
+/// Workers race to detect entries repeated across inputs, so this is also
+/// only possible when entries are emitted unordered.
+fn detectable(options: &Options) -> bool {
+    #[cfg(feature = "plugin")]
+    let plugged = options.plugin.is_some();
+    #[cfg(not(feature = "plugin"))]
+    let plugged = false;
+
+    #[cfg(feature = "protobuf")]
+    let decoded = options.protobuf.is_some();
+    #[cfg(not(feature = "protobuf"))]
+    let decoded = false;
+
+    #[cfg(feature = "store")]
+    let stored = options.store.is_some();
+    #[cfg(not(feature = "store"))]
+    let stored = false;
+
+    let keyed = decoded
+        || options.csv
+        || options.jsonl.is_some()
+        || options.key.is_some()
+        || options.key_bytes.is_some()
+        || options.format.is_some()
+        || options.ignore_pattern.is_some()
+        || options.skip_chars > 0
+        || options.check_chars.is_some()
+        || options.trim_start
+        || options.trim_end
+        || options.normalize.is_some()
+        || options.ignore_case
+        || options.strip_ansi;
 
-    // create boxed filter from provided option filter
-    let mut filter: Box<dyn Filter> = options.filter.into();
+    let counted = options.count
+        || options.max_count.is_some()
+        || options.min_count > 1
+        || options.all_repeated
+        || options.group;
 
-    // create statistics container for filters
+    let stateful = plugged
+        || stored
+        || options.estimate
+        || options.window.is_some()
+        || options.ttl.is_some()
+        || options.lru.is_some()
+        || options.max_memory.is_some()
+        || options.state.is_some();
+
+    options.threads > 1
+        && options.unordered
+        && !options.follow
+        && matches!(options.filter, Filters::Quick)
+        && !keyed
+        && !counted
+        && !stateful
+}
+
+/// Filters all inputs, returning whether any duplicates were found.
+fn run(options: Options) -> io::Result<bool> {
+    // compare all filters against the inputs instead when benchmarking
+    if options.bench {
+        bench::run(&options)?;
+        return Ok(false);
+    }
+
+    // build a deny filter from the inputs instead when requested
+    if options.build_filter {
+        deny::build(&options)?;
+        return Ok(false);
+    }
+
+    // load any filter of entries to drop
+    let deny = options.deny.as_deref().map(deny::load).transpose()?;
+
+    // create boxed filter from provided option filter and seed, or a bounded filter
+    let mut filter: Box<dyn Filter> = match (options.window, options.ttl, options.lru) {
+        _ if options.estimate => Box::new(EstimateFilter::with_seed(options.seed)),
+        #[cfg(feature = "plugin")]
+        _ if options.plugin.is_some() => Box::new(plugin::load(
+            options.plugin.as_ref().unwrap(),
+            options.seed,
+        )?),
+        #[cfg(feature = "store")]
+        _ if options.store.is_some() => {
+            let dir = options.store.as_ref().unwrap();
+            Box::new(
+                StoreFilter::open_with_seed(dir, options.seed).map_err(|err| {
+                    io::Error::new(err.kind(), format!("cannot open store '{}': {}", dir, err))
+                })?,
+            )
+        }
+        (Some(size), _, _) => Box::new(WindowFilter::with_seed(size, options.seed)),
+        (_, Some(ttl), _) => Box::new(TtlFilter::with_seed(ttl, options.seed)),
+        (_, _, Some(size)) => Box::new(LruFilter::with_seed(size, options.seed)),
+        (None, None, None) => create_filter(options.filter, &options)?,
+    };
+
+    // restore the state of the filter from any previous runs
+    if let Some(ref path) = options.state {
+        state::load(path, options.filter, &mut *filter)?;
+    }
+
+    // pre-allocate the filter for the expected number of uniques
+    if let Some(capacity) = options.capacity {
+        filter.reserve(capacity);
+    }
+
+    // bound the memory of the filter by spilling to disk when requested
+    let mut spill = options
+        .max_memory
+        .map(|budget| Spill::new(budget, options.seed));
+
+    // counted entries are deferred until the end of input when needed,
+    // with every occurrence collected when they're all emitted
+    let collected = options.all_repeated || options.group;
+    let deferred = options.count || options.max_count.is_some() || collected;
+
+    // create a counter when counts or count thresholds are required
+    let mut counter = if collected {
+        Some(Counter::retaining_all(options.seed))
+    } else if deferred || options.min_count > 1 {
+        Some(Counter::new(options.seed, deferred))
+    } else {
+        None
+    };
+
+    // track the running occurrences of every entry when annotating
+    let mut annotations = if options.annotate {
+        Some(Counter::new(options.seed, false))
+    } else {
+        None
+    };
+
+    // counters track every distinct entry, so they're pre-allocated too
+    if let (Some(counter), Some(capacity)) = (counter.as_mut(), options.capacity) {
+        counter.reserve(capacity);
+    }
+
+    // create statistics container for filters, and time the filtering
     let mut statistics = Stats::new();
+    let timer = Instant::now();
+
+    // number of unique entries found, when only counting
+    let mut distinct: u64 = 0;
+
+    // expand any globs or directories into the inputs to read
+    let inputs = input::expand(&options.inputs, options.recursive)?;
+
+    // statistics replace entries, unless they're written to stderr
+    let replaced = options.statistics && !options.stats_stderr;
+
+    // break statistics down by input when there are several inputs
+    let tabled = options.statistics_format == StatisticsFormat::Table;
+    let mut breakdown = if options.statistics && tabled && inputs.len() > 1 {
+        Some(Breakdown::new(&inputs))
+    } else {
+        None
+    };
+
+    // in-place filtering can only ever replace a single file
+    if options.in_place && inputs.len() != 1 {
+        let message = "--in-place requires exactly one input file";
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    }
+
+    // following input only ever reads from a single file
+    if options.follow && inputs.len() != 1 {
+        let message = "--follow requires exactly one input file";
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    }
+
+    // re-join records using the same delimiter they're split on
+    let mut terminator = match () {
+        #[cfg(feature = "protobuf")]
+        _ if options.protobuf.is_some() => Vec::new(),
+        _ => options.record_delimiter.clone(),
+    };
+
+    // trailing bytes of each record which are stripped when reading
+    let trailing = terminator.len();
+
+    // write Windows line endings when requested
+    if options.crlf {
+        terminator = b"\r\n".to_vec();
+    }
+
+    // route to a file (or in place) or shards if provided, otherwise lock stdout to speed up writes
+    let create = {
+        let options = options.clone();
+        let target = inputs.first().cloned().unwrap_or_default();
+
+        move || -> io::Result<Output<'static>> {
+            let capacity = options.buffer_size;
+            let output = match (&options.output, &options.shard_output) {
+                _ if options.in_place => {
+                    Output::in_place(&target, options.backup.as_deref(), capacity)?
+                }
+                (Some(path), _) => Output::file(path, options.append, capacity)?,
+                (_, Some(pattern)) => Output::shards(pattern, options.shards, capacity)?,
+                (None, None) => Output::stdout(io::stdout().lock(), capacity),
+            };
+            let output = output
+                .terminator(&terminator)
+                .flushing(options.line_buffered);
+
+            // write digests in place of entries when requested
+            Ok(match options.emit_hash {
+                Some(algorithm) => output.digest(algorithm, options.with_entries),
+                None => output,
+            })
+        }
+    };
+
+    // discard entries when quiet, or write from a separate thread when using multiple threads
+    // (unless line buffered, as entries would otherwise be held back in batches); this is
+    // the last stage of the pipeline after reading and detection on the input workers
+    let mut output = match options.threads {
+        _ if options.quiet => Output::sink(),
+        _ if options.line_buffered => create()?,
+        1 => create()?,
+        _ => Output::piped(create)?,
+    };
+
+    // create a protobuf extractor for message keys when enabled
+    #[cfg(feature = "protobuf")]
+    let extractor = match options.protobuf {
+        Some(ref proto) => Some(protobuf::Extractor::new(
+            &proto.descriptor,
+            &proto.message,
+            &proto.field,
+        )?),
+        None => None,
+    };
+
+    // create a JSON extractor for document keys when enabled
+    let json = options.jsonl.as_deref().map(json::Extractor::new);
+
+    // create a field selector for keys when enabled
+    let fields = options
+        .key
+        .as_ref()
+        .map(|key| Fields::new(key.clone(), options.delimiter.as_deref()));
+
+    // create a column selector for CSV keys when enabled
+    let mut columns = options.column.clone().map(Columns::new);
+
+    // whether the CSV header has been handled yet
+    let mut header = false;
+
+    // scratch buffers to store keys extracted from records
+    let mut scratch = Vec::new();
+    let mut folded = Vec::new();
+    let mut masked = Vec::new();
+    let mut normalized = Vec::new();
+
+    // scratch buffer to store entries prefixed by their occurrences
+    let mut annotated = Vec::new();
+    let mut stripped = Vec::new();
+
+    // create a writer for entry offsets when enabled
+    let mut offsets = match options.emit_offsets {
+        Some(ref path) => Some(BufWriter::new(File::create(path)?)),
+        None => None,
+    };
+
+    // base offset of the current input across all inputs
+    let mut base = 0;
+
+    // buffer of entries to emit in sorted order when enabled
+    let mut sorted: Option<Vec<Vec<u8>>> = if options.sort { Some(Vec::new()) } else { None };
+
+    // create a reservoir to sample uniques when enabled
+    let mut reservoir = options
+        .sample_unique
+        .map(|size| Reservoir::new(size, options.seed));
+
+    // last emitted entry, used to separate groups of entries
+    let mut group: Option<Vec<u8>> = None;
+
+    // whether any duplicate entries have been found
+    let mut duplicates = false;
+
+    // previous entry to validate ordering of the input
+    let mut previous: Option<Vec<u8>> = None;
+    let mut unsorted = false;
+    let mut nonnumeric = false;
+
+    // format of the records within each input
+    let format = match () {
+        #[cfg(feature = "protobuf")]
+        _ if extractor.is_some() => Format::Messages,
+        _ if options.csv => Format::Csv,
+        _ => match options.format {
+            Some(SequenceFormat::Fasta) => Format::Fasta,
+            Some(SequenceFormat::Fastq) => Format::Fastq,
+            None => Format::Lines(options.record_delimiter.clone()),
+        },
+    };
+
+    // byte order marks are only meaningful in textual formats
+    let textual = format.is_textual();
+
+    // detect uniques on the worker threads against a shared filter when possible
+    let shared = if detectable(&options) {
+        let mut shared = ConcurrentFilter::with_seed(options.seed);
+        if let Some(capacity) = options.capacity {
+            shared.reserve(capacity);
+        }
+        Some(Arc::new(shared))
+    } else {
+        None
+    };
+
+    // read inputs sequentially, or across a pool of worker threads
+    let sources = match options.threads {
+        _ if options.follow => Sources::following(inputs.clone(), format, options.buffer_size),
+        1 => Sources::sequential(inputs.clone(), format, options.buffer_size),
+        threads => Sources::parallel(
+            inputs.clone(),
+            format,
+            options.buffer_size,
+            threads,
+            !options.unordered,
+            shared.clone(),
+        ),
+    };
+
+    // line numbers reached in each input, for warnings
+    let mut lines = vec![0; inputs.len()];
+
+    // track progress against the total size of inputs, or periodically, when enabled
+    let mut progress = match options.stats_interval {
+        Some(interval) => Some(Progress::periodic(interval)),
+        None if options.progress => Some(Progress::new(input::size(&inputs))),
+        None => None,
+    };
+
+    // number of uniques emitted, to stop once enough are found
+    let mut emitted: u64 = 0;
+
+    // iterate the records of each input, skipping if allowed
+    'inputs: for (index, opened) in sources {
+        let name = &inputs[index];
+        let mut records = match opened {
+            Ok(records) => records,
+            Err(err) => {
+                let message = format!("cannot open '{}': {}", name, err);
+                if !options.skip_missing {
+                    return Err(io::Error::new(err.kind(), message));
+                }
+                warn(&options, &message)?;
+                continue;
+            }
+        };
+        let mut line = lines[index];
+
+        // iterate all records as &[u8] slices
+        loop {
+            // track the offset of the record in the input
+            let offset = records.offset();
+
+            // read the next record, or move on to the next input
+            let (input, detected) = match records.next_detected() {
+                Ok(Some(next)) => next,
+                Ok(None) => break,
+                Err(err) => {
+                    let message = format!("cannot read '{}': {}", name, err);
+                    if !options.skip_missing {
+                        return Err(io::Error::new(err.kind(), message));
+                    }
+                    warn(&options, &message)?;
+                    continue 'inputs;
+                }
+            };
+
+            // track line numbers for warnings
+            line += 1;
+
+            // track the progress through all inputs
+            if let Some(ref mut progress) = progress {
+                progress.add_entry(input.len() + trailing);
+            }
+
+            // strip any byte order mark from the start of each input
+            let unmarked = match textual && offset == 0 {
+                true => input::unmark(input),
+                false => input,
+            };
+
+            // keep the byte order mark of the first input when requested
+            let input = if options.keep_bom && index == 0 {
+                input
+            } else {
+                unmarked
+            };
+
+            // the first row of each CSV input is the header, emitted once
+            if options.csv && offset == 0 {
+                if !header {
+                    if let Some(ref mut columns) = columns {
+                        columns.resolve(unmarked)?;
+                    }
+                    if !replaced && !options.count_distinct {
+                        output.write_entry(input)?;
+                    }
+                    header = true;
+                }
+                continue;
+            }
+
+            // strip ANSI escape sequences from entries when enabled
+            let plain = if options.strip_ansi {
+                stripped.clear();
+                strip_ansi(unmarked, &mut stripped);
+                &stripped[..]
+            } else {
+                unmarked
+            };
+
+            // emit the stripped entry if requested
+            let input = if options.strip_ansi_output {
+                plain
+            } else {
+                input
+            };
+
+            // drop any entries found in the deny filter
+            if let Some(ref deny) = deny {
+                if deny.contains(plain) {
+                    continue;
+                }
+            }
+
+            // extract the key used to detect uniques
+            let key = match () {
+                #[cfg(feature = "protobuf")]
+                _ if extractor.is_some() => {
+                    scratch.clear();
+                    let extracted = extractor.as_ref().unwrap().extract(plain, &mut scratch);
+                    if let Err(err) = extracted {
+                        let message =
+                            format!("cannot decode '{}' at message {}: {}", name, line, err);
+                        if options.on_error == OnError::Fail {
+                            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                        }
+                        warn(&options, &message)?;
+                        continue;
+                    }
+                    &scratch[..]
+                }
+                _ if json.is_some() => {
+                    scratch.clear();
+                    let extracted = json.as_ref().unwrap().extract(plain, &mut scratch);
+                    if let Err(err) = extracted {
+                        let message = format!("cannot decode '{}' at line {}: {}", name, line, err);
+                        if options.on_error == OnError::Fail {
+                            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                        }
+                        warn(&options, &message)?;
+                        continue;
+                    }
+                    &scratch[..]
+                }
+                _ if fields.is_some() => {
+                    scratch.clear();
+                    fields.as_ref().unwrap().extract(plain, &mut scratch);
+                    &scratch[..]
+                }
+                _ if columns.is_some() => {
+                    scratch.clear();
+                    columns.as_ref().unwrap().extract(plain, &mut scratch);
+                    &scratch[..]
+                }
+                _ if options.format.is_some() => {
+                    scratch.clear();
+                    options.format.unwrap().extract(plain, &mut scratch);
+                    &scratch[..]
+                }
+                _ if options.key_bytes.is_some() => {
+                    let range = options.key_bytes.as_ref().unwrap();
+                    let end = range.end.min(plain.len());
+                    &plain[range.start.min(end)..end]
+                }
+                _ => plain,
+            };
+
+            // remove any ignored patterns from the key when enabled
+            let key = match options.ignore_pattern {
+                Some(ref pattern) => {
+                    masked.clear();
+                    mask(key, pattern, &mut masked);
+                    &masked[..]
+                }
+                None => key,
+            };
 
-    // lock stdout to speed up the writes
-    let mut stdout = stdout.lock();
+            // skip leading bytes of the key when enabled
+            let key = &key[options.skip_chars.min(key.len())..];
 
-    // sequential readers for now
-    for reader in readers {
-        // construct our line reader to iterate lines of bytes
-        let mut lines = BufReader::new(reader).byte_lines();
+            // only check a prefix of the key when enabled
+            let key = match options.check_chars {
+                Some(chars) => &key[..chars.min(key.len())],
+                None => key,
+            };
+
+            // trim whitespace from the key when enabled
+            let key = trim(key, options.trim_start, options.trim_end);
+
+            // normalize the key to a Unicode form when enabled
+            let key = match options.normalize {
+                Some(form) => {
+                    normalized.clear();
+                    form.normalize(key, &mut normalized);
+                    &normalized[..]
+                }
+                None => key,
+            };
+
+            // fold the case of the key when ignoring case
+            let key = if options.ignore_case {
+                folded.clear();
+                fold_case(key, &mut folded);
+                &folded[..]
+            } else {
+                key
+            };
+
+            // validate ordering for the sorted filter or when asserted
+            if options.assert_increasing || matches!(options.filter, Filters::Sorted) {
+                if let Some(ref mut previous) = previous {
+                    let ordering = compare(key, previous, options.numeric);
+
+                    // error when asserting, otherwise warn (once)
+                    if options.assert_increasing {
+                        match ordering {
+                            Some(Ordering::Less) => {
+                                let message =
+                                    format!("input '{}' is not increasing at line {}", name, line);
+                                return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                            }
+                            None => {
+                                let message =
+                                    format!("input '{}' is not numeric at line {}", name, line);
+                                return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                            }
+                            _ => (),
+                        }
+                    } else {
+                        match ordering {
+                            Some(Ordering::Less) if !unsorted => {
+                                let message =
+                                    format!("input '{}' is not sorted at line {}", name, line);
+                                warn(&options, &message)?;
+                                unsorted = true;
+                            }
+                            None if !nonnumeric => {
+                                let message =
+                                    format!("input '{}' is not numeric at line {}", name, line);
+                                warn(&options, &message)?;
+                                nonnumeric = true;
+                            }
+                            _ => (),
+                        }
+                    }
+
+                    previous.clear();
+                    previous.extend_from_slice(key);
+                } else {
+                    previous = Some(key.to_vec());
+                }
+            }
+
+            // pass through or drop entries outside of any sample
+            if let Some(rate) = options.sample {
+                if !sampled(xxh3_64_with_seed(key, options.seed), rate) {
+                    let counting = replaced || options.count_distinct;
+                    if !counting && options.unsampled == Unsampled::Pass {
+                        output.write_entry(input)?;
+                    }
+                    continue;
+                }
+            }
+
+            // count the occurrence of the entry when annotating
+            let occurrence = match annotations {
+                Some(ref mut annotations) => annotations.increment(key, input),
+                None => 0,
+            };
 
-        // iterate all lines as &[u8] slices
-        while let Some(input) = lines.next().transpose()? {
             // track input sizing
             if options.statistics {
-                statistics.add_size(input.len() + 1)
+                statistics.add_size(input.len() + 1);
+                if let Some(ref mut breakdown) = breakdown {
+                    breakdown.add_size(index, input.len() + 1);
+                }
             }
 
-            // detect duplicate value
-            if filter.detect(input) {
-                // handle stats or print
-                if options.statistics {
-                    // add a unique count
-                    statistics.add_unique();
-                } else if !options.inverted {
-                    // echo if not inverted
-                    stdout.write_all(input)?;
-                    stdout.write_all(EOL)?;
+            // detect duplicate value, or threshold when counting
+            let unique = match counter {
+                Some(ref mut counter) => {
+                    let count = counter.increment(key, input);
+
+                    // any repeat occurrence is a duplicate
+                    duplicates |= count > 1;
+
+                    // deferred entries are only emitted at the end of input
+                    if deferred {
+                        continue;
+                    }
+
+                    count == options.min_count
                 }
-            } else {
-                // handle stats or print
-                if options.statistics {
-                    // add a duplicate count
+                None => match (detected, spill.as_mut()) {
+                    (Some(unique), _) => unique,
+                    (None, Some(spill)) => spill.detect(key)?,
+                    (None, None) => filter.try_detect(key)?,
+                },
+            };
+
+            // track whether any duplicates were found
+            duplicates |= !unique && counter.is_none();
+
+            // track uniques found in the progress
+            if let (true, Some(progress)) = (unique, progress.as_mut()) {
+                progress.add_unique();
+            }
+
+            // handle stats, rather than printing unless written to stderr
+            if options.statistics {
+                let hash = xxh3_64_with_seed(key, options.seed);
+                if unique {
+                    statistics.add_unique();
+                } else {
                     statistics.add_duplicate();
-                } else if options.inverted {
-                    // echo if we're inverted
-                    stdout.write_all(input)?;
-                    stdout.write_all(EOL)?;
+                }
+                if options.histogram {
+                    statistics.add_occurrence(hash);
+                }
+                if let Some(ref mut breakdown) = breakdown {
+                    if unique {
+                        breakdown.add_unique(index, hash);
+                    } else {
+                        breakdown.add_duplicate(index, hash);
+                    }
+                }
+                if replaced {
+                    continue;
+                }
+            }
+
+            // count uniques rather than printing
+            if options.count_distinct {
+                distinct += unique as u64;
+                continue;
+            }
+
+            // offer uniques to the reservoir when sampling
+            if let Some(ref mut reservoir) = reservoir {
+                if unique {
+                    reservoir.offer(input);
+                }
+                continue;
+            }
+
+            // buffer entries to emit once sorted
+            if let Some(ref mut sorted) = sorted {
+                if unique != options.inverted {
+                    sorted.push(input.to_vec());
+                }
+                continue;
+            }
+
+            // echo uniques, or duplicates if we're inverted
+            if unique != options.inverted {
+                // separate groups of entries if needed
+                if let Some(ref separator) = options.group_separator {
+                    match group {
+                        Some(ref mut group) if group != key => {
+                            output.write_entry(separator.as_bytes())?;
+                            group.clear();
+                            group.extend_from_slice(key);
+                        }
+                        Some(_) => (),
+                        None => group = Some(key.to_vec()),
+                    }
+                }
+
+                // prefix the entry with its occurrences if needed
+                if annotations.is_some() {
+                    annotated.clear();
+                    write!(annotated, "{:>7} ", occurrence)?;
+                    annotated.extend_from_slice(input);
+                    output.write_entry(&annotated)?;
+                } else {
+                    output.write_entry(input)?;
+                }
+
+                // write the offset of the entry if needed
+                if let Some(ref mut offsets) = offsets {
+                    match options.offset_scope {
+                        OffsetScope::Global => writeln!(offsets, "{}", base + offset)?,
+                        OffsetScope::File => writeln!(offsets, "{}\t{}", index, offset)?,
+                    }
+                }
+
+                // stop reading once enough uniques have been emitted
+                emitted += 1;
+                if options.max_uniques == Some(emitted) {
+                    break 'inputs;
                 }
             }
         }
+
+        // shift the base offset past this input
+        base += records.offset();
+        lines[index] = line;
+    }
+
+    // complete any progress output
+    if let Some(progress) = progress {
+        progress.finish();
     }
 
     // handle stats logging
     if options.statistics {
-        statistics.print();
+        statistics.set_memory(match shared {
+            Some(ref shared) => shared.memory(),
+            None => filter.memory(),
+        });
+        statistics.set_elapsed(timer.elapsed());
+        let delimiter = match options.statistics_format {
+            StatisticsFormat::Csv => Some(','),
+            StatisticsFormat::Tsv => Some('\t'),
+            StatisticsFormat::Table => None,
+        };
+        match (delimiter, options.stats_stderr) {
+            (Some(delimiter), false) => {
+                statistics::write_delimited(&statistics, io::stdout().lock(), delimiter)?
+            }
+            (Some(delimiter), true) => {
+                statistics::write_delimited(&statistics, io::stderr().lock(), delimiter)?
+            }
+            (None, stderr) => {
+                statistics::print(&statistics, stderr);
+                if let Some(breakdown) = breakdown {
+                    breakdown.print(stderr);
+                }
+            }
+        }
+    }
+
+    // emit the number of uniques found, or the estimate of the sketch
+    if options.count_distinct {
+        let distinct = if options.estimate {
+            filter.len()
+        } else {
+            distinct
+        };
+        output.write_entry(distinct.to_string().as_bytes())?;
+    }
+
+    // emit every occurrence of repeated entries, or uniques if we're inverted
+    if let Some(counter) = counter.take_if(|_| options.all_repeated) {
+        let maximum = options.max_count.unwrap_or(u64::MAX);
+        let minimum = options.min_count.max(2);
+
+        for (count, entry) in counter.into_occurrences() {
+            let repeated = count >= minimum && count <= maximum;
+            if repeated != options.inverted {
+                output.write_entry(&entry)?;
+            }
+        }
+    }
+
+    // emit every occurrence of each entry in groups, separated by empty lines
+    if let Some(counter) = counter.take_if(|_| options.group) {
+        let maximum = options.max_count.unwrap_or(u64::MAX);
+        let mut separate = false;
+
+        for group in counter.into_groups() {
+            let count = group.len() as u64;
+            if count < options.min_count || count > maximum {
+                continue;
+            }
+
+            if separate {
+                output.write_entry(b"")?;
+            }
+            separate = true;
+
+            for entry in group {
+                output.write_entry(&entry)?;
+            }
+        }
+    }
+
+    // emit deferred entries, optionally prefixed by their counts
+    if let (true, Some(counter)) = (deferred, counter) {
+        let maximum = options.max_count.unwrap_or(u64::MAX);
+        let mut buffer = Vec::new();
+
+        // skip entries outside the thresholds, or uniques if we're inverted
+        let entries = counter.into_entries().filter(|(count, _)| {
+            *count >= options.min_count && *count <= maximum && !(options.inverted && *count == 1)
+        });
+
+        // order by frequency, keeping only the most frequent entries when requested
+        let entries: Box<dyn Iterator<Item = (u64, Vec<u8>)>> =
+            match (options.frequency, options.top) {
+                (false, None) => Box::new(entries),
+                (_, top) => {
+                    let mut entries = entries.collect::<Vec<_>>();
+                    entries.sort_by_key(|(count, _)| Reverse(*count));
+                    entries.truncate(top.unwrap_or(usize::MAX));
+                    Box::new(entries.into_iter())
+                }
+            };
+
+        for (count, entry) in entries {
+            // write entries directly when not counting
+            if !options.count {
+                output.write_entry(&entry)?;
+                continue;
+            }
+
+            buffer.clear();
+            if options.frequency {
+                write!(buffer, "{}\t", count)?;
+            } else {
+                write!(buffer, "{:>7} ", count)?;
+            }
+            buffer.extend_from_slice(&entry);
+
+            output.write_entry(&buffer)?;
+        }
+    }
+
+    // emit the sampled uniques
+    if let Some(reservoir) = reservoir {
+        for entry in reservoir.into_sample() {
+            output.write_entry(&entry)?;
+        }
+    }
+
+    // emit the buffered entries in sorted order
+    if let Some(mut sorted) = sorted {
+        sorted.sort_unstable_by(|lhs, rhs| order(lhs, rhs, options.numeric));
+
+        if options.sort_reverse {
+            sorted.reverse();
+        }
+
+        for entry in sorted {
+            output.write_entry(&entry)?;
+        }
     }
 
     // flush buffers
-    stdout.flush()?;
+    output.finish()?;
+
+    // persist the state of the filter for future runs
+    if let Some(ref path) = options.state {
+        state::save(path, options.filter, &*filter)?;
+    }
+
+    // flush offsets if needed
+    if let Some(mut offsets) = offsets {
+        offsets.flush()?;
+    }
 
     // done
-    Ok(())
+    Ok(duplicates)
 }
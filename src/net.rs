@@ -0,0 +1,84 @@
+//! Network module used to stream inputs from remote sources.
+//!
+//! Inputs provided as HTTP(S) URLs (or S3 URIs, when enabled) are
+//! requested and streamed directly into the filter as the response
+//! body arrives, rather than downloading the entire body first.
+use std::io::{self, Read};
+
+/// Determines whether an input name refers to a remote source.
+pub fn is_remote(name: &str) -> bool {
+    if cfg!(feature = "s3") && name.starts_with("s3://") {
+        return true;
+    }
+    name.starts_with("http://") || name.starts_with("https://")
+}
+
+/// Opens a streaming reader over the body of a remote source.
+pub fn open(name: &str) -> io::Result<Box<dyn Read + Send>> {
+    #[cfg(feature = "s3")]
+    if let Some(uri) = name.strip_prefix("s3://") {
+        return s3::open(uri);
+    }
+    get(name)
+}
+
+/// Opens a streaming reader over the body of a HTTP(S) URL.
+///
+/// Any response with a non-successful status code is treated as an
+/// error, so that error pages are never filtered as input.
+fn get(url: &str) -> io::Result<Box<dyn Read + Send>> {
+    match ureq::get(url).call() {
+        Ok(response) => Ok(Box::new(response.into_reader())),
+        Err(ureq::Error::Status(code, response)) => Err(io::Error::other(format!(
+            "HTTP {} {}",
+            code,
+            response.status_text()
+        ))),
+        Err(err) => Err(io::Error::other(err)),
+    }
+}
+
+#[cfg(feature = "s3")]
+mod s3 {
+    use rusty_s3::{Bucket, Credentials, S3Action, UrlStyle};
+
+    use std::env;
+    use std::io::{self, Read};
+    use std::time::Duration;
+
+    /// Opens a streaming reader over the body of an S3 object.
+    ///
+    /// Requests are signed with credentials from the standard AWS
+    /// environment variables (if any), and sent to the endpoint in
+    /// `AWS_ENDPOINT_URL` to support S3 compatible stores.
+    pub fn open(uri: &str) -> io::Result<Box<dyn Read + Send>> {
+        let (bucket, key) = uri
+            .split_once('/')
+            .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid S3 URI"))?;
+
+        let region = env::var("AWS_REGION")
+            .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|_| "us-east-1".to_string());
+
+        // custom endpoints are typically S3 compatible, so use path style
+        let (endpoint, style) = match env::var("AWS_ENDPOINT_URL") {
+            Ok(endpoint) => (endpoint, UrlStyle::Path),
+            Err(_) => (
+                format!("https://s3.{}.amazonaws.com", region),
+                UrlStyle::VirtualHost,
+            ),
+        };
+
+        let endpoint = endpoint.parse().map_err(io::Error::other)?;
+        let bucket =
+            Bucket::new(endpoint, style, bucket.to_string(), region).map_err(io::Error::other)?;
+
+        let credentials = Credentials::from_env();
+        let url = bucket
+            .get_object(credentials.as_ref(), key)
+            .sign(Duration::from_secs(60 * 60));
+
+        super::get(url.as_str())
+    }
+}
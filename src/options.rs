@@ -3,9 +3,22 @@
 //! Nothing particularly important to see here, just typical
 //! parsing of things like command line arguments into something
 //! more easily used internally (from the main application flow).
+use crate::config;
+use crate::digest::Algorithm;
+use crate::output::BUFFER_SIZE;
+use crate::sequences::SequenceFormat;
+use bytesize::ByteSize;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
 use clap::{value_parser, Arg, ArgAction, Command};
-use runiq::Filters;
+use regex::bytes::Regex;
+use runiq::{Filters, Normalization};
+use std::collections::hash_map::RandomState;
 use std::ffi::OsString;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::Range;
+use std::str;
+use std::time::Duration;
 
 /// Options struct to store configuration state.
 ///
@@ -15,10 +28,146 @@ use std::ffi::OsString;
 /// (in essence) as application configuration.
 #[derive(Clone, Debug)]
 pub struct Options {
+    pub all_repeated: bool,
+    pub annotate: bool,
+    pub append: bool,
+    pub assert_increasing: bool,
+    pub backup: Option<String>,
+    pub bench: bool,
+    pub buffer_size: usize,
+    pub build_filter: bool,
+    pub capacity: Option<usize>,
+    pub check_chars: Option<usize>,
+    pub column: Option<Vec<String>>,
+    pub count: bool,
+    pub count_distinct: bool,
+    pub crlf: bool,
+    pub csv: bool,
+    pub delimiter: Option<String>,
+    pub deny: Option<String>,
+    pub disk_dir: Option<String>,
+    pub emit_hash: Option<Algorithm>,
+    pub emit_offsets: Option<String>,
+    pub error_rate: Option<f64>,
+    pub estimate: bool,
+    pub exit_code: bool,
     pub filter: Filters,
+    pub follow: bool,
+    pub format: Option<SequenceFormat>,
+    pub frequency: bool,
+    pub group: bool,
+    pub group_separator: Option<String>,
+    pub growth_factor: Option<usize>,
+    pub histogram: bool,
+    pub ignore_case: bool,
+    pub ignore_pattern: Option<Regex>,
+    pub in_place: bool,
     pub inputs: Vec<String>,
     pub inverted: bool,
+    pub jsonl: Option<String>,
+    pub keep_bom: bool,
+    pub key: Option<Vec<usize>>,
+    pub key_bytes: Option<Range<usize>>,
+    pub line_buffered: bool,
+    pub lru: Option<usize>,
+    pub max_count: Option<u64>,
+    pub max_memory: Option<u64>,
+    pub max_uniques: Option<u64>,
+    pub min_count: u64,
+    pub normalize: Option<Normalization>,
+    pub numeric: bool,
+    pub offset_scope: OffsetScope,
+    pub on_error: OnError,
+    pub output: Option<String>,
+    #[cfg(feature = "plugin")]
+    pub plugin: Option<String>,
+    pub progress: bool,
+    pub quiet: bool,
+    #[cfg(feature = "protobuf")]
+    pub protobuf: Option<Protobuf>,
+    pub record_delimiter: Vec<u8>,
+    pub recursive: bool,
+    pub sample: Option<f64>,
+    pub sample_unique: Option<usize>,
+    pub seed: u64,
+    pub shard_output: Option<String>,
+    pub shards: usize,
+    pub skip_chars: usize,
+    pub skip_missing: bool,
+    pub sort: bool,
+    pub sort_reverse: bool,
+    pub state: Option<String>,
     pub statistics: bool,
+    pub statistics_format: StatisticsFormat,
+    pub stats_interval: Option<Duration>,
+    pub stats_stderr: bool,
+    #[cfg(feature = "store")]
+    pub store: Option<String>,
+    pub strict: bool,
+    pub strip_ansi: bool,
+    pub strip_ansi_output: bool,
+    pub threads: usize,
+    pub tightening_ratio: Option<f64>,
+    pub trim_end: bool,
+    pub trim_start: bool,
+    pub top: Option<usize>,
+    pub ttl: Option<Duration>,
+    pub unordered: bool,
+    pub unsampled: Unsampled,
+    pub window: Option<usize>,
+    pub with_entries: bool,
+}
+
+/// Scope used when writing the offsets of emitted entries.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum OffsetScope {
+    /// Offsets are counted across all inputs, as if concatenated.
+    Global,
+
+    /// Offsets are reset per input, prefixed with the input index.
+    File,
+}
+
+/// Behaviour to use for entries excluded from a sample.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Unsampled {
+    /// Entries are passed through without filtering.
+    Pass,
+
+    /// Entries are dropped from the output.
+    Drop,
+}
+
+/// Format used when printing statistics.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum StatisticsFormat {
+    /// Aligned table, intended to be read by humans.
+    Table,
+
+    /// Comma separated header and row of values.
+    Csv,
+
+    /// Tab separated header and row of values.
+    Tsv,
+}
+
+/// Behaviour to use when a record cannot be decoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum OnError {
+    /// Exits with an error.
+    Fail,
+
+    /// Skips the record with a warning.
+    Skip,
+}
+
+/// Protobuf configuration used to extract keys from messages.
+#[cfg(feature = "protobuf")]
+#[derive(Clone, Debug)]
+pub struct Protobuf {
+    pub descriptor: String,
+    pub message: String,
+    pub field: String,
 }
 
 impl Options {
@@ -31,32 +180,471 @@ impl Options {
         I: IntoIterator<Item = T>,
         T: Into<OsString> + Clone,
     {
-        // create a new parser for our args
-        let parser = Options::create_parser();
+        // collect the arguments, as they may need to be parsed again
+        let args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+        let mut skipped = Vec::new();
+
+        // parse out the arguments, dropping any configured defaults
+        // which conflict with the options provided by the user
+        let (mut parser, options) = loop {
+            // create a new parser for our args
+            let mut parser = Options::create_parser();
+
+            // apply any defaults from the environment or configuration
+            let configured = config::apply(&mut parser, &skipped)
+                .unwrap_or_else(|message| parser.error(ErrorKind::InvalidValue, message).exit());
+
+            // parse out the arguments into matching opts
+            let options = parser
+                .try_get_matches_from_mut(&args)
+                .unwrap_or_else(|err| err.exit());
+
+            let conflicting = config::conflicting(&parser, &options, &configured);
+
+            if conflicting.is_empty() {
+                break (parser, options);
+            }
+
+            skipped.extend(conflicting);
+        };
+
+        // subcommands take their inputs from the subcommand
+        let (bench, build_filter, sources) = match options.subcommand() {
+            Some(("bench", bench)) => (true, false, bench),
+            Some(("build-filter", build)) => (false, true, build),
+            _ => (false, false, &options),
+        };
+
+        // grab the shard count and output pattern
+        let shards = *options.get_one::<u64>("shards").unwrap() as usize;
+        let shard_output = options.get_one::<String>("shard-output").cloned();
+
+        // multiple shards must be written to different paths
+        if let Some(ref pattern) = shard_output {
+            if shards > 1 && !pattern.contains("{}") {
+                parser
+                    .error(
+                        ErrorKind::InvalidValue,
+                        "--shard-output must contain {} when using multiple shards",
+                    )
+                    .exit();
+            }
+        }
+
+        // in-place filtering needs exactly one file to replace
+        let in_place = options.get_flag("in-place");
+        let inputs = sources.get_many::<String>("inputs").unwrap();
+        let single = inputs.len() == 1 && sources.get_one::<String>("inputs").unwrap() != "-";
+
+        if in_place && !single {
+            parser
+                .error(
+                    ErrorKind::InvalidValue,
+                    "--in-place requires exactly one input file",
+                )
+                .exit();
+        }
 
-        // parse out the arguments into matching opts
-        let options = parser.get_matches_from(args);
+        // following input also only ever applies to a single file
+        let follow = options.get_flag("follow");
+
+        if follow && !single {
+            parser
+                .error(
+                    ErrorKind::InvalidValue,
+                    "--follow requires exactly one input file",
+                )
+                .exit();
+        }
+
+        // statistics must be enabled to choose their format
+        let tracked = options.get_flag("statistics") || options.get_flag("stats-stderr");
+        let formatted = options.value_source("statistics-format") == Some(ValueSource::CommandLine);
+
+        if formatted && !tracked {
+            parser
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "--statistics-format requires --statistics or --stats-stderr",
+                )
+                .exit();
+        }
+
+        // statistics must be enabled to include their histogram
+        if options.get_flag("histogram") && !tracked {
+            parser
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "--histogram requires --statistics or --stats-stderr",
+                )
+                .exit();
+        }
+
+        // grab the occurrence thresholds
+        let min_count = *options.get_one::<u64>("min-count").unwrap();
+        let max_count = options.get_one::<u64>("max-count").copied();
+
+        // printing only uniques is a maximum count of a single occurrence
+        let max_count = match options.get_flag("unique-only") {
+            true => Some(1),
+            false => max_count,
+        };
+
+        // thresholds must be provided as a valid range
+        if max_count.is_some_and(|max| max < min_count) {
+            parser
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--max-count must not be less than --min-count",
+                )
+                .exit();
+        }
+
+        // grab the key, which is a path for structured inputs
+        let key = options.get_one::<String>("key");
+
+        #[cfg(feature = "protobuf")]
+        let structured = options.get_flag("jsonl") || options.get_flag("protobuf");
+        #[cfg(not(feature = "protobuf"))]
+        let structured = options.get_flag("jsonl");
+
+        // otherwise the key must be a list of field indices
+        let fields = match key {
+            Some(key) if !structured => match parse_fields(key) {
+                Ok(fields) => Some(fields),
+                Err(err) => {
+                    let message = format!("invalid value '{}' for '--key <FIELDS>': {}", key, err);
+                    parser.error(ErrorKind::InvalidValue, message).exit()
+                }
+            },
+            _ => None,
+        };
 
         // attempt to parse the provided filter
         let filter = options.get_one::<Filters>("filter");
+        let group_separator = options.get_one::<String>("group-separator").cloned();
+
+        // group separators only make sense for the sorted filter
+        if group_separator.is_some() && !matches!(filter, Some(Filters::Sorted)) {
+            parser
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--group-separator can only be used with the sorted filter",
+                )
+                .exit();
+        }
+
+        // the disk directory only makes sense for the disk filter
+        let disk_dir = options.get_one::<String>("disk-dir").cloned();
+
+        if disk_dir.is_some() && !matches!(filter, Some(Filters::Disk)) {
+            parser
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--disk-dir can only be used with the disk filter",
+                )
+                .exit();
+        }
+
+        // bloom filter parameters only make sense for the compact filter
+        let bloom = ["error-rate", "growth-factor", "tightening-ratio"]
+            .iter()
+            .find(|id| options.contains_id(id));
+
+        if let (Some(id), false) = (bloom, matches!(filter, Some(Filters::Compact))) {
+            parser
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!("--{} can only be used with the compact filter", id),
+                )
+                .exit();
+        }
+
+        // spilling to disk relies on the hashing of the quick filter
+        let max_memory = options.get_one::<ByteSize>("max-memory").map(|size| size.0);
+
+        if max_memory.is_some() && !matches!(filter, Some(Filters::Quick)) {
+            parser
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--max-memory can only be used with the quick filter",
+                )
+                .exit();
+        }
+
+        // buffers must hold at least a byte to make any progress
+        let buffer_size = options
+            .get_one::<ByteSize>("buffer-size")
+            .map_or(BUFFER_SIZE, |size| size.0 as usize);
+
+        if buffer_size == 0 {
+            parser
+                .error(
+                    ErrorKind::InvalidValue,
+                    "--buffer-size must be greater than zero",
+                )
+                .exit();
+        }
+
+        // combine all ignored patterns into a single pattern
+        let ignore_pattern = options
+            .get_many::<String>("ignore-pattern")
+            .map(|patterns| {
+                let combined = patterns
+                    .map(|pattern| format!("(?:{})", pattern))
+                    .collect::<Vec<_>>()
+                    .join("|");
+
+                Regex::new(&combined).unwrap_or_else(|err| {
+                    let message = format!("invalid value for '--ignore-pattern <REGEX>': {}", err);
+                    parser.error(ErrorKind::InvalidValue, message).exit()
+                })
+            });
 
         // create opts
         Options {
+            // store whether we're benchmarking filters
+            bench,
+
+            // store whether we're building a deny filter
+            build_filter,
+
+            // grab the path of a deny filter to drop entries with, if any
+            deny: options.get_one::<String>("deny").cloned(),
+
             // grab and store statistics flags
-            statistics: options.get_flag("statistics"),
+            statistics: options.get_flag("statistics") || options.get_flag("stats-stderr"),
+            stats_stderr: options.get_flag("stats-stderr"),
+            histogram: options.get_flag("histogram"),
+            statistics_format: *options
+                .get_one::<StatisticsFormat>("statistics-format")
+                .unwrap(),
+
+            // grab and store progress flags
+            progress: options.get_flag("progress"),
+            stats_interval: options.get_one::<Duration>("stats-interval").copied(),
+
+            // grab and store quiet flags
+            quiet: options.get_flag("quiet"),
+
+            // grab and store exit code flags
+            exit_code: options.get_flag("exit-code"),
+
+            // grab and store strict flags
+            strict: options.get_flag("strict"),
+
+            // grab the sorting flags, where reversing implies sorting
+            sort: options.get_flag("sort") || options.get_flag("sort-reverse"),
+            sort_reverse: options.get_flag("sort-reverse"),
+
+            // store the patterns to remove from keys
+            ignore_pattern,
 
             // grab and store inversion flags
             inverted: options.get_flag("invert"),
 
+            // grab the file to write output to, and how
+            output: sources
+                .try_get_one::<String>("output")
+                .ok()
+                .flatten()
+                .cloned(),
+            append: options.get_flag("append"),
+
+            // store follow flags to poll input for new data
+            follow,
+
+            // grab line buffering flags, which following always requires
+            line_buffered: follow || options.get_flag("line-buffered"),
+
+            // store in-place flags, and the suffix for backups
+            in_place,
+            backup: options.get_one::<String>("backup").cloned(),
+
+            // store the size of the buffers used to read and write
+            buffer_size,
+
+            // grab the algorithm used to write digests, and whether to keep entries
+            emit_hash: options.get_one::<Algorithm>("emit-hash").copied(),
+            with_entries: options.get_flag("with-entries"),
+
+            // grab the path to write entry offsets to
+            emit_offsets: options.get_one::<String>("emit-offsets").cloned(),
+
+            // grab the scope to use for entry offsets
+            offset_scope: *options.get_one::<OffsetScope>("offset-scope").unwrap(),
+
+            // grab and store counting flags
+            count: options.get_flag("count")
+                || options.get_flag("frequency")
+                || options.get_one::<u64>("top").is_some(),
+            frequency: options.get_flag("frequency"),
+            count_distinct: options.get_flag("count-distinct") || options.get_flag("estimate"),
+            estimate: options.get_flag("estimate"),
+            all_repeated: options.get_flag("all-repeated"),
+            annotate: options.get_flag("annotate"),
+            group: options.get_flag("group"),
+
+            // grab the format of any sequence records
+            format: options.get_one::<SequenceFormat>("format").copied(),
+
+            // grab the CSV flags, and the columns used to build keys
+            csv: options.get_flag("csv"),
+            column: options.get_one::<Vec<String>>("column").cloned(),
+
+            // grab and store ANSI stripping flags
+            strip_ansi: options.get_flag("strip-ansi") || options.get_flag("strip-ansi-output"),
+            strip_ansi_output: options.get_flag("strip-ansi-output"),
+
+            // grab and store case folding flags
+            ignore_case: options.get_flag("ignore-case"),
+
+            // grab the form to normalize keys to
+            normalize: options.get_one::<Normalization>("normalize").copied(),
+
+            // grab the range of bytes to use as keys
+            key_bytes: options.get_one::<Range<usize>>("key-bytes").cloned(),
+
+            // grab the number of bytes to skip and check in keys
+            skip_chars: *options.get_one::<u64>("skip-chars").unwrap() as usize,
+            check_chars: options
+                .get_one::<u64>("check-chars")
+                .map(|chars| *chars as usize),
+
+            // grab whether to keep any byte order mark
+            keep_bom: options.get_flag("keep-bom"),
+
+            // grab the line endings to write
+            crlf: options.get_flag("crlf"),
+
+            // grab the delimiter used to split records
+            record_delimiter: if options.get_flag("zero-terminated") {
+                vec![0]
+            } else {
+                options
+                    .get_one::<Vec<u8>>("record-delimiter")
+                    .cloned()
+                    .unwrap_or_else(|| b"\n".to_vec())
+            },
+
+            // grab and store trimming flags
+            trim_end: options.get_flag("trim") || options.get_flag("trim-end"),
+            trim_start: options.get_flag("trim") || options.get_flag("trim-start"),
+
+            // grab and store ordering flags
+            assert_increasing: options.get_flag("assert-increasing"),
+            numeric: options.get_flag("numeric"),
+
+            // store the fields and delimiter used to build keys
+            key: fields,
+            delimiter: options.get_one::<String>("delimiter").cloned(),
+
+            // store the occurrences required to emit
+            max_count,
+            min_count,
+
+            // grab the number of uniques to stop after
+            max_uniques: options.get_one::<u64>("max-uniques").copied(),
+
             // store the filter to use for unique detection
             filter: filter.unwrap().to_owned(),
 
+            // grab the number of most frequent entries to emit, if any
+            top: options.get_one::<u64>("top").map(|top| *top as usize),
+
+            // grab the duration entries are remembered for, if any
+            ttl: options.get_one::<Duration>("ttl").copied(),
+
+            // grab the window size to filter within, if any
+            window: options
+                .get_one::<u64>("window")
+                .map(|window| *window as usize),
+
+            // grab the number of recent entries to remember, if any
+            lru: options.get_one::<u64>("lru").map(|lru| *lru as usize),
+
+            // grab the expected number of uniques to allocate for
+            capacity: options
+                .get_one::<u64>("capacity")
+                .map(|capacity| *capacity as usize),
+
+            // store the directory used by the disk filter
+            disk_dir,
+
+            // grab the path to persist filter state to
+            state: options.get_one::<String>("state").cloned(),
+
+            // grab the path of the filter plugin to load, if any
+            #[cfg(feature = "plugin")]
+            plugin: options.get_one::<String>("plugin").cloned(),
+
+            // grab the directory of the store to filter through, if any
+            #[cfg(feature = "store")]
+            store: options.get_one::<String>("store").cloned(),
+
+            // store the memory budget of the filter
+            max_memory,
+
+            // grab the parameters of the bloom filter
+            error_rate: options.get_one::<f64>("error-rate").copied(),
+            growth_factor: options
+                .get_one::<u64>("growth-factor")
+                .map(|factor| *factor as usize),
+            tightening_ratio: options.get_one::<f64>("tightening-ratio").copied(),
+
+            // store the separator to place between groups
+            group_separator,
+
+            // grab the behaviour to use on decoding errors
+            on_error: *options.get_one::<OnError>("on-error").unwrap(),
+
+            // store the JSON key path when enabled
+            jsonl: if options.get_flag("jsonl") {
+                key.cloned()
+            } else {
+                None
+            },
+
+            // store the protobuf configuration when enabled
+            #[cfg(feature = "protobuf")]
+            protobuf: if options.get_flag("protobuf") {
+                Some(Protobuf {
+                    descriptor: options.get_one::<String>("descriptor").cloned().unwrap(),
+                    message: options.get_one::<String>("message").cloned().unwrap(),
+                    field: key.cloned().unwrap(),
+                })
+            } else {
+                None
+            },
+
+            // grab the sampling rate and behaviour
+            sample: options.get_one::<f64>("sample").copied(),
+            unsampled: *options.get_one::<Unsampled>("unsampled").unwrap(),
+
+            // grab the size of any sample of uniques
+            sample_unique: options
+                .get_one::<u64>("sample-unique")
+                .map(|size| *size as usize),
+
+            // grab the seed to use when hashing
+            seed: *options.get_one::<u64>("seed").unwrap(),
+
+            // store the sharding configuration
+            shard_output,
+            shards,
+
+            // grab and store skipping flags
+            skip_missing: options.get_flag("skip-missing"),
+
+            // grab the threads used to read inputs, and the ordering
+            threads: *options.get_one::<u64>("threads").unwrap() as usize,
+            unordered: options.get_flag("unordered"),
+
+            // grab and store directory recursion flags
+            recursive: options.get_flag("recursive"),
+
             // own all inputs
-            inputs: options
-                .get_many::<String>("inputs")
-                .unwrap()
-                .map(|s| s.to_owned())
-                .collect(),
+            inputs: inputs.map(|s| s.to_owned()).collect(),
         }
     }
 
@@ -68,13 +656,341 @@ impl Options {
     /// In terms of visibility, this method is defined on the struct due to
     /// the parser being specifically designed around the `Options` struct.
     fn create_parser() -> Command {
-        Command::new("")
+        let parser = Command::new("")
             // package metadata from cargo
             .name(env!("CARGO_PKG_NAME"))
             .about(env!("CARGO_PKG_DESCRIPTION"))
             .version(env!("CARGO_PKG_VERSION"))
             // arguments and flag details
             .args(&[
+                // all-repeated: -D, --all-repeated
+                Arg::new("all-repeated")
+                    .help("Prints every occurrence of any repeated entry")
+                    .long_help(
+                        "Prints every occurrence of any repeated entry.\n\n\
+                         This works in the same way as uniq -D, but across the whole \
+                         input rather than adjacent entries. Every occurrence is stored \
+                         in memory and emitted in input order once all input has been \
+                         read. When inverted, every entry which is never repeated is \
+                         printed instead.",
+                    )
+                    .short('D')
+                    .long("all-repeated")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "count",
+                        "emit-offsets",
+                        "frequency",
+                        "group-separator",
+                        "sample-unique",
+                        "sort",
+                        "sort-reverse",
+                        "statistics",
+                        "top",
+                        "unique-only",
+                    ]),
+                // annotate: --annotate
+                Arg::new("annotate")
+                    .help("Prefixes entries with their running number of occurrences")
+                    .long_help(
+                        "Prefixes entries with their running number of occurrences.\n\n\
+                         Each entry is prefixed by the number of times it has been seen \
+                         so far (1 for the first time, 2 for the second, and so on), which \
+                         shows why an entry was emitted or suppressed; this is most useful \
+                         alongside --invert. The occurrences of every distinct entry are \
+                         stored in memory.",
+                    )
+                    .long("annotate")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "count-distinct",
+                        "emit-hash",
+                        "frequency",
+                        "group",
+                        "max-count",
+                        "sample",
+                        "sample-unique",
+                        "sort",
+                        "sort-reverse",
+                        "statistics",
+                        "top",
+                        "unique-only",
+                    ]),
+                // append: --append
+                Arg::new("append")
+                    .help("Appends to the file provided to --output")
+                    .long("append")
+                    .action(ArgAction::SetTrue)
+                    .requires("output"),
+                // assert-increasing: --assert-increasing
+                Arg::new("assert-increasing")
+                    .help("Exits with an error if entries ever decrease")
+                    .long("assert-increasing")
+                    .action(ArgAction::SetTrue),
+                // backup: --backup <SUFFIX>
+                Arg::new("backup")
+                    .help("Backs up the input replaced by --in-place")
+                    .long_help(
+                        "Backs up the input replaced by --in-place.\n\n\
+                         The original input is kept alongside the filtered input, \
+                         using the name of the input with the provided suffix.",
+                    )
+                    .long("backup")
+                    .num_args(1)
+                    .value_name("SUFFIX")
+                    .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                    .requires("in-place"),
+                // buffer-size: --buffer-size <SIZE>
+                Arg::new("buffer-size")
+                    .help("Size of the buffers used to read and write entries")
+                    .long_help(
+                        "Size of the buffers used to read and write entries.\n\n\
+                         Larger buffers mean fewer system calls, which can improve \
+                         throughput on very long lines or fast disks. Sizes may use \
+                         units, such as 256KiB or 1MB; the default is 64KiB.",
+                    )
+                    .long("buffer-size")
+                    .num_args(1)
+                    .value_name("SIZE")
+                    .value_parser(value_parser!(ByteSize)),
+                // capacity: --capacity <N>
+                Arg::new("capacity")
+                    .help("Expected number of uniques to allocate for up front")
+                    .long_help(
+                        "Expected number of uniques to allocate for up front.\n\n\
+                         Filters grow as uniques are found, which can dominate the time \
+                         taken on inputs with many uniques. Providing an estimate allows \
+                         the filter to allocate once; this is only a hint, so filtering \
+                         remains correct if the estimate is wrong.",
+                    )
+                    .long("capacity")
+                    .num_args(1)
+                    .value_name("N")
+                    .value_parser(value_parser!(u64)),
+                // check-chars: --check-chars <N>
+                Arg::new("check-chars")
+                    .help("Compares at most the first N bytes of keys")
+                    .long_help(
+                        "Compares at most the first N bytes of keys.\n\n\
+                         This matches the behaviour of `uniq -w`, and is applied after \
+                         any bytes are skipped via --skip-chars.",
+                    )
+                    .long("check-chars")
+                    .num_args(1)
+                    .value_name("N")
+                    .value_parser(value_parser!(u64)),
+                // column: --column <COLUMNS>
+                Arg::new("column")
+                    .help("Columns to use as the key for uniqueness with --csv")
+                    .long_help(
+                        "Columns to use as the key for uniqueness with --csv.\n\n\
+                         Columns are provided as a comma separated list of either \
+                         indices (starting from 1) or names from the header row. The \
+                         entire row will still be emitted.",
+                    )
+                    .long("column")
+                    .num_args(1)
+                    .value_name("COLUMNS")
+                    .value_parser(parse_columns)
+                    .requires("csv"),
+                // count: -c, --count
+                Arg::new("count")
+                    .help("Prefixes entries with their number of occurrences")
+                    .long_help(
+                        "Prefixes entries with their number of occurrences.\n\n\
+                         As counts are only known once all input has been read, every \
+                         distinct entry is stored in memory and entries are emitted at \
+                         the end of input (in the order they were first seen). When \
+                         inverted, only entries occurring more than once are emitted.",
+                    )
+                    .short('c')
+                    .long("count")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "emit-offsets",
+                        "group-separator",
+                        "sample-unique",
+                        "statistics",
+                    ]),
+                // count-distinct: --count-distinct
+                Arg::new("count-distinct")
+                    .help("Prints the number of unique entries instead of entries")
+                    .long_help(
+                        "Prints the number of unique entries instead of entries.\n\n\
+                         This is equivalent to piping entries to `wc -l`, but avoids \
+                         writing any entries. The count is only as accurate as the \
+                         chosen filter.",
+                    )
+                    .long("count-distinct")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "emit-offsets",
+                        "group",
+                        "group-separator",
+                        "invert",
+                        "max-count",
+                        "min-count",
+                        "quiet",
+                        "sample-unique",
+                        "shard-output",
+                        "statistics",
+                        "unique-only",
+                    ]),
+                // crlf: --crlf
+                Arg::new("crlf")
+                    .help("Writes entries with Windows (CRLF) line endings")
+                    .long_help(
+                        "Writes entries with Windows (CRLF) line endings.\n\n\
+                         Entries are always compared without any trailing carriage \
+                         return, so input with mixed line endings is filtered as \
+                         expected. By default entries are written with Unix (LF) line \
+                         endings, regardless of their original line endings.",
+                    )
+                    .long("crlf")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all(["record-delimiter", "zero-terminated"]),
+                // csv: --csv
+                Arg::new("csv")
+                    .help("Parses input as CSV, preserving the header row")
+                    .long_help(
+                        "Parses input as CSV, preserving the header row.\n\n\
+                         Rows are parsed as per RFC 4180, so quoted values may contain \
+                         commas and newlines. The header row of the first input is \
+                         always emitted, and the header rows of any other inputs are \
+                         skipped. Rows are emitted exactly as they appear in the input.",
+                    )
+                    .long("csv")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all(["count", "key", "record-delimiter", "zero-terminated"]),
+                // delimiter: -d, --delimiter <DELIM>
+                Arg::new("delimiter")
+                    .help("Delimiter used to split fields for --key")
+                    .short('d')
+                    .long("delimiter")
+                    .num_args(1)
+                    .value_name("DELIM")
+                    .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                    .requires("key"),
+                // deny: --deny <PATH>
+                Arg::new("deny")
+                    .help("Drops entries found in a filter built via build-filter")
+                    .long_help(
+                        "Drops entries found in a filter built via build-filter.\n\n\
+                         Entries are checked against the filter before any key is \
+                         extracted, and any entries found are dropped. All remaining \
+                         entries are filtered for duplicates as usual.",
+                    )
+                    .long("deny")
+                    .num_args(1)
+                    .value_name("PATH"),
+                // disk-dir: --disk-dir <DIR>
+                Arg::new("disk-dir")
+                    .help("Directory to store the table of the disk filter")
+                    .long_help(
+                        "Directory to store the table of the disk filter.\n\n\
+                         The table is removed once filtering is complete. When not \
+                         provided, the temporary directory of the system is used.",
+                    )
+                    .long("disk-dir")
+                    .num_args(1)
+                    .value_name("DIR"),
+                // emit-hash: --emit-hash[=ALGORITHM]
+                Arg::new("emit-hash")
+                    .help("Prints the digest of each entry rather than the entry")
+                    .long_help(
+                        "Prints the digest of each entry rather than the entry.\n\n\
+                         Digests are written as lowercase hex using xxh3, sha256 or md5 \
+                         (defaulting to xxh3), and are never seeded so the digest of an \
+                         entry is stable across runs. Algorithms must be attached to the \
+                         flag (such as --emit-hash=md5), so any following argument is an \
+                         input. Entries can be kept alongside their digests via \
+                         --with-entries.",
+                    )
+                    .long("emit-hash")
+                    .num_args(0..=1)
+                    .require_equals(true)
+                    .value_name("ALGORITHM")
+                    .value_parser(value_parser!(Algorithm))
+                    .default_missing_value("xxh3")
+                    .ignore_case(true)
+                    .conflicts_with_all([
+                        "count",
+                        "count-distinct",
+                        "frequency",
+                        "group",
+                        "group-separator",
+                        "statistics",
+                        "top",
+                    ]),
+                // emit-offsets: --emit-offsets <PATH>
+                Arg::new("emit-offsets")
+                    .help("Writes the byte offsets of emitted entries to a file")
+                    .long("emit-offsets")
+                    .num_args(1)
+                    .value_name("PATH")
+                    .conflicts_with("statistics"),
+                // error-rate: --error-rate <RATE>
+                Arg::new("error-rate")
+                    .help("Desired false positive rate of the compact filter")
+                    .long_help(
+                        "Desired false positive rate of the compact filter.\n\n\
+                         The rate must be between 0.0 and 1.0 (exclusive), and defaults \
+                         to 1e-8. Higher rates use less memory, at the cost of more \
+                         unique entries being treated as duplicates.",
+                    )
+                    .long("error-rate")
+                    .num_args(1)
+                    .value_name("RATE")
+                    .value_parser(parse_ratio),
+                // estimate: --estimate
+                Arg::new("estimate")
+                    .help("Prints an estimate of the number of unique entries")
+                    .long_help(
+                        "Prints an estimate of the number of unique entries.\n\n\
+                         This works in the same way as --count-distinct, but uses a \
+                         HyperLogLog sketch rather than a filter. The estimate is \
+                         typically within 1% of the real count, and only requires \
+                         a few KB of memory regardless of the number of entries.",
+                    )
+                    .long("estimate")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "count-distinct",
+                        "emit-offsets",
+                        "filter",
+                        "group",
+                        "group-separator",
+                        "invert",
+                        "max-count",
+                        "max-memory",
+                        "min-count",
+                        "quiet",
+                        "sample-unique",
+                        "shard-output",
+                        "state",
+                        "statistics",
+                        "ttl",
+                        "unique-only",
+                        "window",
+                    ]),
+                // exit-code: --exit-code
+                Arg::new("exit-code")
+                    .help("Exits with a status reflecting whether duplicates were found")
+                    .long_help(
+                        "Exits with a status reflecting whether duplicates were found.\n\n\
+                         In the same way as grep, the exit status is 0 if duplicates \
+                         were found, 1 if no duplicates were found, and 2 if an error \
+                         occurred. This allows runiq to be used as a predicate in shell \
+                         scripts.",
+                    )
+                    .long("exit-code")
+                    .action(ArgAction::SetTrue),
                 // filter: -f, --filter [naive]
                 Arg::new("filter")
                     .help("Filter to use to determine uniqueness")
@@ -85,9 +1001,182 @@ impl Options {
                     .hide_default_value(true)
                     .default_value("quick")
                     .ignore_case(true),
+                // follow: -F, --follow
+                Arg::new("follow")
+                    .help("Follows the input file, filtering entries as they arrive")
+                    .long_help(
+                        "Follows the input file, filtering entries as they arrive.\n\n\
+                         This works in the same way as tail -f; the input is polled \
+                         for new data rather than being read until the end, and uniques \
+                         are written as soon as they are found. Filtering continues \
+                         until runiq is interrupted.",
+                    )
+                    .short('F')
+                    .long("follow")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "frequency",
+                        "group",
+                        "in-place",
+                        "max-count",
+                        "output",
+                        "sample-unique",
+                        "shard-output",
+                        "state",
+                        "statistics",
+                        "threads",
+                        "unique-only",
+                    ]),
+                // format: --format <FORMAT>
+                Arg::new("format")
+                    .help("Parses input as sequence records, filtering on sequences")
+                    .long_help(
+                        "Parses input as sequence records, filtering on sequences.\n\n\
+                         FASTA records span a header line starting with > and any lines \
+                         of sequence that follow it, while FASTQ records span the header, \
+                         sequence, separator and quality lines. Records are compared on \
+                         their sequence alone, and the entire record will still be \
+                         emitted.",
+                    )
+                    .long("format")
+                    .num_args(1)
+                    .value_name("FORMAT")
+                    .value_parser(value_parser!(SequenceFormat))
+                    .ignore_case(true)
+                    .conflicts_with_all([
+                        "column",
+                        "csv",
+                        "key",
+                        "key-bytes",
+                        "record-delimiter",
+                        "zero-terminated",
+                    ]),
+                // frequency: --frequency
+                Arg::new("frequency")
+                    .help("Prints entries with their counts, from most to least frequent")
+                    .long_help(
+                        "Prints entries with their counts, from most to least frequent.\n\n\
+                         This replaces the classic sort | uniq -c | sort -rn in a single \
+                         pass. Entries are emitted once all input has been read, as rows \
+                         of the count and the entry separated by a tab. Entries with the \
+                         same count are emitted in the order they were first seen.",
+                    )
+                    .long("frequency")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "count-distinct",
+                        "emit-offsets",
+                        "follow",
+                        "group-separator",
+                        "lru",
+                        "max-uniques",
+                        "sample-unique",
+                        "sort",
+                        "statistics",
+                        "ttl",
+                        "window",
+                    ]),
+                // growth-factor: --growth-factor <N>
+                Arg::new("growth-factor")
+                    .help("Factor by which the compact filter grows when full")
+                    .long("growth-factor")
+                    .num_args(1)
+                    .value_name("N")
+                    .value_parser(value_parser!(u64).range(2..)),
+                // group: --group
+                Arg::new("group")
+                    .help("Prints every occurrence of each entry together in groups")
+                    .long_help(
+                        "Prints every occurrence of each entry together in groups.\n\n\
+                         This works in the same way as uniq --group, but across the whole \
+                         input rather than adjacent entries. Groups are separated by an \
+                         empty line, and are emitted in the order each entry was first \
+                         seen once all input has been read. Every occurrence is stored \
+                         in memory, and groups can be limited via --min-count and \
+                         --max-count.",
+                    )
+                    .long("group")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "emit-offsets",
+                        "frequency",
+                        "group-separator",
+                        "invert",
+                        "sample-unique",
+                        "sort",
+                        "sort-reverse",
+                        "statistics",
+                        "top",
+                        "unique-only",
+                    ]),
+                // group-separator: --group-separator [STR]
+                Arg::new("group-separator")
+                    .help("Prints a separator between groups of the sorted filter")
+                    .long("group-separator")
+                    .num_args(0..=1)
+                    .value_name("STR")
+                    .default_missing_value("")
+                    .conflicts_with_all(["shard-output", "statistics"]),
+                // histogram: --histogram
+                Arg::new("histogram")
+                    .help("Includes a histogram of occurrences in statistics")
+                    .long_help(
+                        "Includes a histogram of occurrences in statistics.\n\n\
+                         The histogram shows how many entries were seen once, 2-10 \
+                         times, 11-100 times, and so on. This requires a count of every \
+                         distinct entry in memory, in addition to the filter itself.",
+                    )
+                    .long("histogram")
+                    .action(ArgAction::SetTrue),
+                // ignore-case: --ignore-case
+                Arg::new("ignore-case")
+                    .help("Ignores differences in case when detecting uniques")
+                    .long_help(
+                        "Ignores differences in case when detecting uniques.\n\n\
+                         Keys are lowercased before being filtered, so the original \
+                         casing of the first occurrence of an entry is emitted.",
+                    )
+                    .long("ignore-case")
+                    .action(ArgAction::SetTrue),
+                // ignore-pattern: --ignore-pattern <REGEX>
+                Arg::new("ignore-pattern")
+                    .help("Removes matches of a pattern before comparing entries")
+                    .long_help(
+                        "Removes matches of a pattern before comparing entries.\n\n\
+                         All matches of the regular expression are removed from each \
+                         entry (such as timestamps, process IDs or UUIDs) before it is \
+                         checked for uniqueness; entries are still printed in full. \
+                         This can be provided multiple times.",
+                    )
+                    .long("ignore-pattern")
+                    .num_args(1)
+                    .value_name("REGEX")
+                    .action(ArgAction::Append)
+                    .value_parser(parse_pattern),
+                // in-place: --in-place
+                Arg::new("in-place")
+                    .help("Replaces the input file with the filtered entries")
+                    .long_help(
+                        "Replaces the input file with the filtered entries.\n\n\
+                         Entries are written to a temporary file alongside the input, \
+                         which replaces the input once all entries are written.",
+                    )
+                    .long("in-place")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all(["output", "shard-output", "statistics"]),
                 // inputs: +required +multiple
                 Arg::new("inputs")
                     .help("Input sources to filter")
+                    .long_help(
+                        "Input sources to filter.\n\n\
+                         Inputs can be files, glob patterns (such as logs/*.log), or \
+                         directories when used with --recursive. Matches are read in \
+                         sorted order, and - can be used to read from stdin.",
+                    )
                     .action(ArgAction::Append)
                     .hide_default_value(true)
                     .default_value("-"),
@@ -97,12 +1186,717 @@ impl Options {
                     .short('i')
                     .long("invert")
                     .action(ArgAction::SetTrue),
+                // jsonl: --jsonl
+                Arg::new("jsonl")
+                    .help("Parses input as JSON Lines, filtering on a field")
+                    .long_help(
+                        "Parses input as JSON Lines, filtering on a field.\n\n\
+                         Each entry is parsed as a JSON document, and the value at the \
+                         dotted path provided by --key is used to detect uniques (such \
+                         as event.id). Path segments can also index into arrays. Entries \
+                         which are not valid JSON are handled as per --on-error.",
+                    )
+                    .long("jsonl")
+                    .action(ArgAction::SetTrue)
+                    .requires("key")
+                    .conflicts_with("csv"),
+                // keep-bom: --keep-bom
+                Arg::new("keep-bom")
+                    .help("Keeps the byte order mark of the first input")
+                    .long_help(
+                        "Keeps the byte order mark of the first input.\n\n\
+                         UTF-8 byte order marks are stripped from the start of every \
+                         input, so that the first entry of each input can match the \
+                         same entry elsewhere. When set, the byte order mark of the \
+                         first input is kept on the first entry when emitted.",
+                    )
+                    .long("keep-bom")
+                    .action(ArgAction::SetTrue),
+                // key: -k, --key, --field <FIELDS>
+                Arg::new("key")
+                    .help("Fields to use as the key for uniqueness")
+                    .long_help(
+                        "Fields to use as the key for uniqueness.\n\n\
+                         Fields are provided as a comma separated list of indices, \
+                         starting from 1. Fields are split on runs of whitespace by \
+                         default, or on the value of --delimiter if provided. The \
+                         entire entry will still be emitted.\n\n\
+                         For structured inputs (such as --jsonl or --protobuf), this is \
+                         instead a dotted path to the field to filter on.",
+                    )
+                    .short('k')
+                    .long("key")
+                    .visible_alias("field")
+                    .num_args(1)
+                    .value_name("FIELDS"),
+                // key-bytes: --key-bytes <START:END>
+                Arg::new("key-bytes")
+                    .help("Range of bytes to use as the key for uniqueness")
+                    .long_help(
+                        "Range of bytes to use as the key for uniqueness.\n\n\
+                         Bytes are numbered from 1, and both ends of the range are \
+                         inclusive (such as 17:36), which suits fixed-width formats. \
+                         Either end can be omitted to use the start or end of each \
+                         entry. The entire entry will still be emitted.",
+                    )
+                    .long("key-bytes")
+                    .num_args(1)
+                    .value_name("START:END")
+                    .value_parser(parse_byte_range)
+                    .conflicts_with_all(["column", "key"]),
+                // line-buffered: --line-buffered
+                Arg::new("line-buffered")
+                    .help("Flushes output after every entry written")
+                    .long_help(
+                        "Flushes output after every entry written.\n\n\
+                         Output is otherwise buffered until the buffer is full, which \
+                         can hold back entries for a long time when input is slow. This \
+                         allows runiq to sit in the middle of an interactive pipeline, \
+                         at the cost of throughput. This is implied by --follow.",
+                    )
+                    .long("line-buffered")
+                    .action(ArgAction::SetTrue),
+                // lru: --lru <N>
+                Arg::new("lru")
+                    .help("Only remembers the N most recently seen entries")
+                    .long_help(
+                        "Only remembers the N most recently seen entries.\n\n\
+                         Once N entries are remembered, the least recently seen entry \
+                         is forgotten to make room for each new entry. Unlike --window, \
+                         seeing a duplicate refreshes the entry, so frequently repeated \
+                         entries are never printed again. Memory is bounded by N rather \
+                         than the number of uniques.",
+                    )
+                    .long("lru")
+                    .num_args(1)
+                    .value_name("N")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "estimate",
+                        "filter",
+                        "group",
+                        "max-count",
+                        "max-memory",
+                        "min-count",
+                        "state",
+                        "ttl",
+                        "unique-only",
+                        "window",
+                    ]),
+                // max-count: --max-count <N>
+                Arg::new("max-count")
+                    .help("Only prints entries seen at most this many times")
+                    .long_help(
+                        "Only prints entries seen at most this many times.\n\n\
+                         As counts are only known once all input has been read, every \
+                         distinct entry is stored in memory and entries are emitted at \
+                         the end of input (in the order they were first seen).",
+                    )
+                    .long("max-count")
+                    .num_args(1)
+                    .value_name("N")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .conflicts_with_all([
+                        "emit-offsets",
+                        "group-separator",
+                        "invert",
+                        "sample-unique",
+                        "statistics",
+                    ]),
+                // max-memory: --max-memory <SIZE>
+                Arg::new("max-memory")
+                    .help("Limits the memory used to filter, spilling to disk")
+                    .long_help(
+                        "Limits the memory used to filter, spilling to disk.\n\n\
+                         Once the budget is reached, the hashes of all entries seen are \
+                         merged into a sorted run stored in a temporary file, which is \
+                         searched when an entry has not been seen since the last spill. \
+                         Filtering remains exact, at the cost of speed. Sizes may use \
+                         units, such as 512MB or 2GiB.",
+                    )
+                    .long("max-memory")
+                    .num_args(1)
+                    .value_name("SIZE")
+                    .value_parser(value_parser!(ByteSize))
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "group",
+                        "max-count",
+                        "min-count",
+                        "unique-only",
+                    ]),
+                // max-uniques: --max-uniques <N>
+                Arg::new("max-uniques")
+                    .help("Stops reading once this many uniques are printed")
+                    .long_help(
+                        "Stops reading once this many uniques are printed.\n\n\
+                         Any remaining input is left unread, which makes it cheap to \
+                         take a handful of distinct entries from a large input.",
+                    )
+                    .long("max-uniques")
+                    .num_args(1)
+                    .value_name("N")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "count-distinct",
+                        "group",
+                        "invert",
+                        "max-count",
+                        "min-count",
+                        "sample-unique",
+                        "statistics",
+                        "unique-only",
+                    ]),
+                // min-count: --min-count [1]
+                Arg::new("min-count")
+                    .help("Only prints entries seen at least this many times")
+                    .long_help(
+                        "Only prints entries seen at least this many times.\n\n\
+                         Each entry is printed once, at the point it reaches the \
+                         provided count (or at the end of input when used alongside \
+                         --count). This requires tracking a counter for every \
+                         distinct entry (regardless of the filter in use), so memory \
+                         grows with the number of distinct entries in the input. A \
+                         value of 1 is identical to the default behaviour.",
+                    )
+                    .long("min-count")
+                    .num_args(1)
+                    .value_parser(value_parser!(u64).range(1..))
+                    .hide_default_value(true)
+                    .default_value("1")
+                    .conflicts_with_all(["invert", "statistics"]),
+                // normalize: --normalize <FORM>
+                Arg::new("normalize")
+                    .help("Normalizes Unicode before detecting uniques")
+                    .long_help(
+                        "Normalizes Unicode before detecting uniques.\n\n\
+                         Keys are normalized to the provided form, so that equivalent \
+                         strings (such as precomposed and decomposed accents) are \
+                         treated as the same entry. The original form of the first \
+                         occurrence of an entry is emitted.",
+                    )
+                    .long("normalize")
+                    .num_args(1)
+                    .value_name("FORM")
+                    .value_parser(value_parser!(Normalization))
+                    .ignore_case(true),
+                // numeric: --numeric
+                Arg::new("numeric")
+                    .help("Compares entries numerically when checking order or sorting")
+                    .long("numeric")
+                    .action(ArgAction::SetTrue),
+                // offset-scope: --offset-scope [global]
+                Arg::new("offset-scope")
+                    .help("Scope of the offsets written by --emit-offsets")
+                    .long("offset-scope")
+                    .num_args(1)
+                    .value_parser(value_parser!(OffsetScope))
+                    .hide_default_value(true)
+                    .default_value("global")
+                    .ignore_case(true)
+                    .requires("emit-offsets"),
+                // on-error: --on-error [fail]
+                Arg::new("on-error")
+                    .help("Behaviour when a record cannot be decoded")
+                    .long("on-error")
+                    .num_args(1)
+                    .value_parser(value_parser!(OnError))
+                    .hide_default_value(true)
+                    .default_value("fail")
+                    .ignore_case(true),
+                // output: -o, --output <PATH>
+                Arg::new("output")
+                    .help("Writes entries to a file rather than stdout")
+                    .long_help(
+                        "Writes entries to a file rather than stdout.\n\n\
+                         Entries are written to a temporary file alongside the output, \
+                         which replaces the output once all entries are written. Use \
+                         --append to write to the end of the output directly instead.",
+                    )
+                    .short('o')
+                    .long("output")
+                    .num_args(1)
+                    .value_name("PATH")
+                    .conflicts_with("shard-output"),
+                // progress: --progress
+                Arg::new("progress")
+                    .help("Reports the progress of filtering to stderr")
+                    .long_help(
+                        "Reports the progress of filtering to stderr.\n\n\
+                         Progress includes the amount of input read, the rate of entries \
+                         being read, and the number of uniques found so far. When all \
+                         inputs are files, a progress bar is also shown.",
+                    )
+                    .long("progress")
+                    .action(ArgAction::SetTrue),
+                // quiet: -q, --quiet
+                Arg::new("quiet")
+                    .help("Suppresses the output of all entries")
+                    .long_help(
+                        "Suppresses the output of all entries.\n\n\
+                         Entries are still filtered, but nothing is written, which is \
+                         cheaper than writing to /dev/null. This is most useful when \
+                         combined with --exit-code to validate inputs.",
+                    )
+                    .short('q')
+                    .long("quiet")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all(["in-place", "output", "shard-output"]),
+                // record-delimiter: --record-delimiter <DELIM>
+                Arg::new("record-delimiter")
+                    .help("Separates records by a delimiter rather than newlines")
+                    .long_help(
+                        "Separates records by a delimiter rather than newlines.\n\n\
+                         Delimiters can be any sequence of bytes, and support the \
+                         escapes \\n, \\r, \\t, \\0, \\\\ and \\xHH. Records are \
+                         written back out using the same delimiter.",
+                    )
+                    .long("record-delimiter")
+                    .num_args(1)
+                    .value_name("DELIM")
+                    .value_parser(parse_delimiter)
+                    .conflicts_with("zero-terminated"),
+                // recursive: -r, --recursive
+                Arg::new("recursive")
+                    .help("Reads all files within any input directories")
+                    .short('r')
+                    .long("recursive")
+                    .action(ArgAction::SetTrue),
+                // sample: --sample <RATE>
+                Arg::new("sample")
+                    .help("Only filters a deterministic sample of entries")
+                    .long_help(
+                        "Only filters a deterministic sample of entries.\n\n\
+                         The rate must be between 0.0 and 1.0, and sampling is based on \
+                         the hash of each entry, so identical entries will always make \
+                         the same decision. Entries outside of the sample are handled \
+                         based on the value of --unsampled.",
+                    )
+                    .long("sample")
+                    .num_args(1)
+                    .value_name("RATE")
+                    .value_parser(parse_rate),
+                // sample-unique: --sample-unique <K>
+                Arg::new("sample-unique")
+                    .help("Emits a random sample of K uniques at the end of input")
+                    .long_help(
+                        "Emits a random sample of K uniques at the end of input.\n\n\
+                         Uniques are sampled uniformly using a reservoir of size K, \
+                         which is emitted in input order once all input has been read. \
+                         The sample is driven by the value of --seed, so the same input \
+                         and seed will always produce the same sample.",
+                    )
+                    .long("sample-unique")
+                    .num_args(1)
+                    .value_name("K")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .conflicts_with_all([
+                        "emit-offsets",
+                        "group-separator",
+                        "invert",
+                        "statistics",
+                    ]),
+                // seed: --seed [0]
+                Arg::new("seed")
+                    .help("Seed to use when hashing entries")
+                    .long_help(
+                        "Seed to use when hashing entries.\n\n\
+                         Hashes are the same across machines for the same seed, so a \
+                         fixed seed gives reproducible output when sharding or saving \
+                         state. Use \"random\" to pick a random seed, which makes it far \
+                         harder to craft colliding entries when filtering untrusted input.",
+                    )
+                    .long("seed")
+                    .num_args(1)
+                    .value_name("SEED")
+                    .value_parser(parse_seed)
+                    .hide_default_value(true)
+                    .default_value("0"),
+                // shard-output: --shard-output <PATTERN>
+                Arg::new("shard-output")
+                    .help("Writes entries to shard files rather than stdout")
+                    .long_help(
+                        "Writes entries to shard files rather than stdout.\n\n\
+                         Any {} in the pattern is replaced with the shard index, and \
+                         entries are routed to a shard using their hash, so shards are \
+                         always disjoint. Patterns ending in .gz are written as gzip.",
+                    )
+                    .long("shard-output")
+                    .num_args(1)
+                    .value_name("PATTERN"),
+                // shards: --shards [1]
+                Arg::new("shards")
+                    .help("Number of shards to split entries across")
+                    .long("shards")
+                    .num_args(1)
+                    .value_parser(value_parser!(u64).range(1..))
+                    .hide_default_value(true)
+                    .default_value("1")
+                    .requires("shard-output"),
+                // skip-chars: --skip-chars [0]
+                Arg::new("skip-chars")
+                    .help("Ignores the first N bytes of keys when detecting uniques")
+                    .long_help(
+                        "Ignores the first N bytes of keys when detecting uniques.\n\n\
+                         This matches the behaviour of `uniq -s`, and is applied before \
+                         any other transformation of the key (such as --trim).",
+                    )
+                    .long("skip-chars")
+                    .num_args(1)
+                    .value_name("N")
+                    .value_parser(value_parser!(u64))
+                    .hide_default_value(true)
+                    .default_value("0"),
+                // skip-missing: --skip-missing
+                Arg::new("skip-missing")
+                    .help("Skips inputs which cannot be opened or read")
+                    .long_help(
+                        "Skips inputs which cannot be opened or read.\n\n\
+                         A warning naming the input is written to stderr, and filtering \
+                         continues with the remaining inputs. Any entries read from an \
+                         input before it failed are kept.",
+                    )
+                    .long("skip-missing")
+                    .visible_alias("continue-on-error")
+                    .action(ArgAction::SetTrue),
+                // sort: --sort
+                Arg::new("sort")
+                    .help("Prints entries in sorted order once all input is read")
+                    .long_help(
+                        "Prints entries in sorted order once all input is read.\n\n\
+                         Entries are buffered in memory until the end of input, rather \
+                         than being printed as they are found. Entries are sorted by \
+                         their bytes, or numerically when used with --numeric.",
+                    )
+                    .long("sort")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "emit-offsets",
+                        "follow",
+                        "group",
+                        "group-separator",
+                        "max-count",
+                        "max-uniques",
+                        "sample-unique",
+                        "statistics",
+                        "unique-only",
+                    ]),
+                // sort-reverse: --sort-reverse
+                Arg::new("sort-reverse")
+                    .help("Prints entries in reverse sorted order once all input is read")
+                    .long("sort-reverse")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "emit-offsets",
+                        "follow",
+                        "group",
+                        "group-separator",
+                        "max-count",
+                        "max-uniques",
+                        "sample-unique",
+                        "statistics",
+                        "unique-only",
+                    ]),
+                // state: --state <PATH>
+                Arg::new("state")
+                    .help("Loads and saves the state of the filter to a file")
+                    .long_help(
+                        "Loads and saves the state of the filter to a file.\n\n\
+                         Any existing state is loaded before filtering, and the updated \
+                         state is saved once all input has been filtered. This allows \
+                         for incremental filtering across separate runs, such as daily \
+                         batches of logs. State can only be loaded by the same filter \
+                         which saved it.",
+                    )
+                    .long("state")
+                    .num_args(1)
+                    .value_name("PATH")
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "group",
+                        "max-count",
+                        "max-memory",
+                        "min-count",
+                        "unique-only",
+                    ]),
                 // statistics: -s --statistics
                 Arg::new("statistics")
                     .help("Prints statistics instead of entries")
+                    .long_help(
+                        "Prints statistics instead of entries.\n\n\
+                         Statistics can include a histogram of how many times entries \
+                         were seen via --histogram. When multiple inputs are provided, \
+                         statistics are also broken down per input, along with a count \
+                         of duplicates first seen in a different input.",
+                    )
                     .short('s')
                     .long("statistics")
                     .action(ArgAction::SetTrue),
+                // statistics-format: --statistics-format [table]
+                Arg::new("statistics-format")
+                    .help("Format used when printing statistics")
+                    .long_help(
+                        "Format used when printing statistics.\n\n\
+                         The csv and tsv formats print a header and a single row of \
+                         values, so statistics from many runs can be appended to a file. \
+                         The occurrence histogram and per-input breakdown are only \
+                         included in the table format.",
+                    )
+                    .long("statistics-format")
+                    .num_args(1)
+                    .value_name("FORMAT")
+                    .value_parser(value_parser!(StatisticsFormat))
+                    .hide_default_value(true)
+                    .default_value("table")
+                    .ignore_case(true),
+                // stats-interval: --stats-interval <DURATION>
+                Arg::new("stats-interval")
+                    .help("Reports a snapshot of statistics to stderr every interval")
+                    .long_help(
+                        "Reports a snapshot of statistics to stderr every interval.\n\n\
+                         Each snapshot is written on its own line, including the number \
+                         of entries read, the uniques and duplicates found so far, and \
+                         the rate of entries being read. Entries are still written as \
+                         usual. Durations are a number of seconds, or a number with a \
+                         unit of ms, s, m, h or d (such as 30s or 12h).",
+                    )
+                    .long("stats-interval")
+                    .num_args(1)
+                    .value_name("DURATION")
+                    .value_parser(parse_duration)
+                    .conflicts_with("progress"),
+                // stats-stderr: --stats-stderr
+                Arg::new("stats-stderr")
+                    .help("Prints statistics to stderr, as well as entries")
+                    .long_help(
+                        "Prints statistics to stderr, as well as entries.\n\n\
+                         This is the same as --statistics, except that entries are still \
+                         written as usual and statistics are written to stderr once all \
+                         input has been read.",
+                    )
+                    .long("stats-stderr")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "count",
+                        "count-distinct",
+                        "frequency",
+                        "statistics",
+                        "top",
+                    ]),
+                // strict: --strict
+                Arg::new("strict")
+                    .help("Treats all warnings as errors")
+                    .long("strict")
+                    .action(ArgAction::SetTrue),
+                // threads: -j, --threads [1]
+                Arg::new("threads")
+                    .help("Number of threads used to read, filter and write entries")
+                    .long_help(
+                        "Number of threads used to read, filter and write entries.\n\n\
+                         When using multiple threads, inputs are opened and read on \
+                         separate threads, and entries are written from a separate \
+                         thread. Entries are still emitted in the order of the inputs, \
+                         unless --unordered is provided. When unordered and filtering \
+                         entire entries with the default filter, uniques are also \
+                         detected on the reading threads against a shared filter; in \
+                         this case an entry repeated across inputs may be emitted from \
+                         any of the inputs containing it.",
+                    )
+                    .short('j')
+                    .long("threads")
+                    .num_args(1)
+                    .value_name("N")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .hide_default_value(true)
+                    .default_value("1"),
+                // strip-ansi: --strip-ansi
+                Arg::new("strip-ansi")
+                    .help("Ignores ANSI escape sequences when detecting uniques")
+                    .long_help(
+                        "Ignores ANSI escape sequences when detecting uniques.\n\n\
+                         Escape sequences (such as terminal colors) are removed from \
+                         entries before keys are extracted, but entries are emitted \
+                         with their escape sequences intact.",
+                    )
+                    .long("strip-ansi")
+                    .action(ArgAction::SetTrue),
+                // strip-ansi-output: --strip-ansi-output
+                Arg::new("strip-ansi-output")
+                    .help("Removes ANSI escape sequences from entries entirely")
+                    .long_help(
+                        "Removes ANSI escape sequences from entries entirely.\n\n\
+                         This is the same as --strip-ansi, except that entries are \
+                         also emitted without their escape sequences.",
+                    )
+                    .long("strip-ansi-output")
+                    .action(ArgAction::SetTrue),
+                // tightening-ratio: --tightening-ratio <RATIO>
+                Arg::new("tightening-ratio")
+                    .help("Ratio by which the compact filter error rate tightens as it grows")
+                    .long("tightening-ratio")
+                    .num_args(1)
+                    .value_name("RATIO")
+                    .value_parser(parse_ratio),
+                // top: --top <N>
+                Arg::new("top")
+                    .help("Prints the N most frequent entries with their counts")
+                    .long_help(
+                        "Prints the N most frequent entries with their counts.\n\n\
+                         Entries are emitted once all input has been read, prefixed \
+                         by their number of occurrences (as with --count), from most \
+                         to least frequent. Entries with the same count are emitted \
+                         in the order they were first seen.",
+                    )
+                    .long("top")
+                    .num_args(1)
+                    .value_name("N")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .conflicts_with_all([
+                        "count-distinct",
+                        "emit-offsets",
+                        "group-separator",
+                        "lru",
+                        "max-uniques",
+                        "sample-unique",
+                        "sort",
+                        "statistics",
+                        "ttl",
+                        "window",
+                    ]),
+                // trim: --trim
+                Arg::new("trim")
+                    .help("Ignores leading and trailing whitespace in keys")
+                    .long("trim")
+                    .action(ArgAction::SetTrue),
+                // trim-end: --trim-end
+                Arg::new("trim-end")
+                    .help("Ignores trailing whitespace in keys")
+                    .long("trim-end")
+                    .action(ArgAction::SetTrue),
+                // trim-start: --trim-start
+                Arg::new("trim-start")
+                    .help("Ignores leading whitespace in keys")
+                    .long("trim-start")
+                    .action(ArgAction::SetTrue),
+                // ttl: --ttl <DURATION>
+                Arg::new("ttl")
+                    .help("Forgets entries once this duration has passed")
+                    .long_help(
+                        "Forgets entries once this duration has passed.\n\n\
+                         Entries are remembered for the duration after they were first \
+                         printed, after which they will be printed again if repeated. \
+                         Durations are a number of seconds, or a number with a unit of \
+                         ms, s, m, h or d (such as 30s or 12h).",
+                    )
+                    .long("ttl")
+                    .num_args(1)
+                    .value_name("DURATION")
+                    .value_parser(parse_duration)
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "filter",
+                        "group",
+                        "max-count",
+                        "max-memory",
+                        "min-count",
+                        "state",
+                        "unique-only",
+                        "window",
+                    ]),
+                // unique-only: -u, --unique-only
+                Arg::new("unique-only")
+                    .help("Only prints entries which are never repeated")
+                    .long_help(
+                        "Only prints entries which are never repeated.\n\n\
+                         This works in the same way as uniq -u, but across the whole \
+                         input rather than adjacent entries. As counts are only known \
+                         once all input has been read, every distinct entry is stored \
+                         in memory and entries are emitted at the end of input (in the \
+                         order they were first seen).",
+                    )
+                    .short('u')
+                    .long("unique-only")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([
+                        "emit-offsets",
+                        "frequency",
+                        "group-separator",
+                        "invert",
+                        "max-count",
+                        "min-count",
+                        "sample-unique",
+                        "statistics",
+                        "top",
+                    ]),
+                // unordered: --unordered
+                Arg::new("unordered")
+                    .help("Emits entries as inputs are read, in any order")
+                    .long("unordered")
+                    .action(ArgAction::SetTrue)
+                    .requires("threads")
+                    .conflicts_with_all(["assert-increasing", "emit-offsets", "group-separator"]),
+                // unsampled: --unsampled [pass]
+                Arg::new("unsampled")
+                    .help("Behaviour for entries excluded by --sample")
+                    .long("unsampled")
+                    .num_args(1)
+                    .value_parser(value_parser!(Unsampled))
+                    .hide_default_value(true)
+                    .default_value("pass")
+                    .ignore_case(true)
+                    .requires("sample"),
+                // window: --window <N>
+                Arg::new("window")
+                    .help("Only treats entries seen in the last N entries as duplicates")
+                    .long_help(
+                        "Only treats entries seen in the last N entries as duplicates.\n\n\
+                         Entries are forgotten once they fall out of the window, so a \
+                         repeated entry is printed again if it has not been seen within \
+                         the last N entries. Memory is bounded by the size of the window \
+                         rather than the number of uniques.",
+                    )
+                    .long("window")
+                    .num_args(1)
+                    .value_name("N")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .conflicts_with_all([
+                        "all-repeated",
+                        "count",
+                        "filter",
+                        "group",
+                        "max-count",
+                        "max-memory",
+                        "min-count",
+                        "state",
+                        "unique-only",
+                    ]),
+                // with-entries: --with-entries
+                Arg::new("with-entries")
+                    .help("Prints entries after their digest with --emit-hash")
+                    .long_help(
+                        "Prints entries after their digest with --emit-hash.\n\n\
+                         Each digest is followed by a tab and the entry itself.",
+                    )
+                    .long("with-entries")
+                    .action(ArgAction::SetTrue)
+                    .requires("emit-hash"),
+                // zero-terminated: -z, --zero-terminated
+                Arg::new("zero-terminated")
+                    .help("Separates records by NUL bytes rather than newlines")
+                    .short('z')
+                    .long("zero-terminated")
+                    .action(ArgAction::SetTrue),
                 // help: -h, --help
                 Arg::new("help")
                     .short('h')
@@ -110,9 +1904,287 @@ impl Options {
                     .action(ArgAction::HelpLong)
                     .hide(true),
             ])
+            // subcommands and their arguments
+            .subcommand(
+                Command::new("bench")
+                    .about("Compares the throughput and accuracy of all filters")
+                    .long_about(
+                        "Compares the throughput and accuracy of all filters.\n\n\
+                         Inputs are read into memory, and then passed through each \
+                         filter in turn. The time taken, peak memory used by the \
+                         filter, and difference in uniques found compared to the \
+                         exact simple filter are printed for each filter.",
+                    )
+                    .arg(
+                        Arg::new("inputs")
+                            .help("Input sources to benchmark against")
+                            .action(ArgAction::Append)
+                            .hide_default_value(true)
+                            .default_value("-"),
+                    ),
+            )
+            .subcommand(
+                Command::new("build-filter")
+                    .about("Builds a filter of entries to drop, for use with --deny")
+                    .long_about(
+                        "Builds a filter of entries to drop, for use with --deny.\n\n\
+                         Every entry in the inputs is added to an immutable filter, \
+                         which is written to the output path. The filter requires \
+                         roughly 5 bytes per unique entry, and can produce rare false \
+                         positives (roughly one in five billion entries).",
+                    )
+                    .arg(
+                        Arg::new("output")
+                            .help("Path to write the filter to")
+                            .short('o')
+                            .long("output")
+                            .num_args(1)
+                            .value_name("PATH")
+                            .required(true),
+                    )
+                    .arg(
+                        Arg::new("inputs")
+                            .help("Input sources to build the filter from")
+                            .action(ArgAction::Append)
+                            .hide_default_value(true)
+                            .default_value("-"),
+                    ),
+            )
             // settings required for parsing
+            .args_conflicts_with_subcommands(true)
             .disable_help_subcommand(true)
             .disable_help_flag(true)
-            .trailing_var_arg(true)
+            .trailing_var_arg(true);
+
+        // protobuf arguments only when enabled
+        #[cfg(feature = "protobuf")]
+        let parser = parser.args(&[
+            // protobuf: --protobuf
+            Arg::new("protobuf")
+                .help("Reads input as length-delimited protobuf messages")
+                .long("protobuf")
+                .action(ArgAction::SetTrue)
+                .requires_all(["descriptor", "message", "key"])
+                .conflicts_with_all([
+                    "crlf",
+                    "csv",
+                    "jsonl",
+                    "record-delimiter",
+                    "zero-terminated",
+                ]),
+            // descriptor: --descriptor <PATH>
+            Arg::new("descriptor")
+                .help("Path to a protobuf descriptor set")
+                .long("descriptor")
+                .num_args(1)
+                .value_name("PATH")
+                .requires("protobuf"),
+            // message: --message <NAME>
+            Arg::new("message")
+                .help("Fully qualified name of the protobuf message")
+                .long("message")
+                .num_args(1)
+                .value_name("NAME")
+                .requires("protobuf"),
+        ]);
+
+        // plugin arguments only when enabled
+        #[cfg(feature = "plugin")]
+        let parser = parser.arg(
+            // plugin: --plugin <PATH>
+            Arg::new("plugin")
+                .help("Filters via a custom filter loaded from a plugin")
+                .long_help(
+                    "Filters via a custom filter loaded from a plugin.\n\n\
+                     Plugins are dynamic libraries exporting a `runiq_plugin` function \
+                     which returns a table of filter functions, as described in the \
+                     runiq.h header. Plugins written in Rust can be exported via the \
+                     `runiq::export_plugin!` macro.",
+                )
+                .long("plugin")
+                .num_args(1)
+                .value_name("PATH")
+                .conflicts_with_all([
+                    "estimate",
+                    "filter",
+                    "lru",
+                    "max-memory",
+                    "state",
+                    "ttl",
+                    "window",
+                ]),
+        );
+
+        // store arguments only when enabled
+        #[cfg(feature = "store")]
+        let parser = parser.arg(
+            // store: --store <DIR>
+            Arg::new("store")
+                .help("Filters via a persistent store within a directory")
+                .long_help(
+                    "Filters via a persistent store within a directory.\n\n\
+                     Entries are stored in an embedded database which is kept after \
+                     runiq exits, so running again with the same store will filter \
+                     out any entries seen in previous runs. The store can grow far \
+                     larger than the available memory.",
+                )
+                .long("store")
+                .num_args(1)
+                .value_name("DIR")
+                .conflicts_with_all([
+                    "estimate",
+                    "filter",
+                    "lru",
+                    "max-memory",
+                    "state",
+                    "ttl",
+                    "window",
+                ]),
+        );
+
+        parser
+    }
+}
+
+/// Parses a sampling rate, ensuring it lies within `0.0..=1.0`.
+fn parse_rate(value: &str) -> Result<f64, String> {
+    let rate = value
+        .parse::<f64>()
+        .map_err(|_| format!("'{}' is not a valid rate", value))?;
+
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("{} is not in 0.0..=1.0", rate));
+    }
+
+    Ok(rate)
+}
+
+/// Parses a duration, which is a number of seconds or a number with a unit.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let split = value.find(|c: char| c.is_ascii_alphabetic());
+    let (number, unit) = value.split_at(split.unwrap_or(value.len()));
+
+    let millis = match unit {
+        "ms" => 1,
+        "" | "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        "d" => 24 * 60 * 60 * 1000,
+        _ => return Err(format!("'{}' is not a valid duration unit", unit)),
+    };
+
+    match number.trim().parse::<u64>() {
+        Ok(number) if number > 0 => Ok(Duration::from_millis(number.saturating_mul(millis))),
+        _ => Err(format!("'{}' is not a valid duration", value)),
     }
 }
+
+/// Parses a regular expression, validating that it compiles.
+fn parse_pattern(value: &str) -> Result<String, String> {
+    Regex::new(value)
+        .map(|_| value.to_string())
+        .map_err(|err| err.to_string())
+}
+
+/// Parses a hash seed, which is a number or "random".
+fn parse_seed(value: &str) -> Result<u64, String> {
+    if value.eq_ignore_ascii_case("random") {
+        return Ok(RandomState::new().build_hasher().finish());
+    }
+
+    value
+        .parse::<u64>()
+        .map_err(|_| format!("'{}' is not a valid seed", value))
+}
+
+/// Parses a ratio, which must be strictly between 0.0 and 1.0.
+fn parse_ratio(value: &str) -> Result<f64, String> {
+    let ratio = value
+        .parse::<f64>()
+        .map_err(|_| format!("'{}' is not a valid ratio", value))?;
+
+    if ratio <= 0.0 || ratio >= 1.0 {
+        return Err(format!("{} is not in 0.0..1.0 (exclusive)", ratio));
+    }
+
+    Ok(ratio)
+}
+
+/// Parses a comma separated list of field indices, starting from 1.
+fn parse_fields(value: &str) -> Result<Vec<usize>, String> {
+    value
+        .split(',')
+        .map(|field| match field.trim().parse::<usize>() {
+            Ok(index) if index > 0 => Ok(index),
+            _ => Err(format!("'{}' is not a valid field index", field)),
+        })
+        .collect()
+}
+
+/// Parses an inclusive range of bytes, starting from 1.
+fn parse_byte_range(value: &str) -> Result<Range<usize>, String> {
+    let invalid = || format!("'{}' is not a valid byte range", value);
+    let (start, end) = value.split_once(':').ok_or_else(invalid)?;
+
+    let start = match start.trim() {
+        "" => 1,
+        start => start.parse::<usize>().map_err(|_| invalid())?,
+    };
+    let end = match end.trim() {
+        "" => usize::MAX,
+        end => end.parse::<usize>().map_err(|_| invalid())?,
+    };
+
+    if start == 0 || end < start {
+        return Err(invalid());
+    }
+
+    Ok(start - 1..end)
+}
+
+/// Parses a list of CSV columns, which are either indices or names.
+fn parse_columns(value: &str) -> Result<Vec<String>, String> {
+    value
+        .split(',')
+        .map(|column| match column.trim() {
+            "" | "0" => Err(format!("'{}' is not a valid column", column)),
+            column => Ok(column.to_string()),
+        })
+        .collect()
+}
+
+/// Parses a record delimiter, expanding any escape sequences.
+fn parse_delimiter(value: &str) -> Result<Vec<u8>, String> {
+    let mut delimiter = Vec::new();
+    let mut bytes = value.bytes();
+
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            delimiter.push(byte);
+            continue;
+        }
+
+        delimiter.push(match bytes.next() {
+            Some(b'n') => b'\n',
+            Some(b'r') => b'\r',
+            Some(b't') => b'\t',
+            Some(b'0') => 0,
+            Some(b'\\') => b'\\',
+            Some(b'x') => {
+                let hex = [bytes.next().unwrap_or(0), bytes.next().unwrap_or(0)];
+                str::from_utf8(&hex)
+                    .ok()
+                    .filter(|hex| hex.bytes().all(|byte| byte.is_ascii_hexdigit()))
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| format!("'{}' contains an invalid \\x escape", value))?
+            }
+            _ => return Err(format!("'{}' contains an invalid escape", value)),
+        });
+    }
+
+    if delimiter.is_empty() {
+        return Err("delimiter must not be empty".to_string());
+    }
+
+    Ok(delimiter)
+}
@@ -0,0 +1,459 @@
+//! Output module used to route filtered entries to a destination.
+//!
+//! By default entries are written straight to stdout, but they can
+//! also be written to a file, or routed across a set of shard files
+//! (optionally compressed) in order to feed directly into distributed
+//! processing jobs.
+use crate::digest::Algorithm;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use xxhash_rust::xxh3::xxh3_64;
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, StdoutLock, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
+
+/// Default size of the buffer used when reading and writing entries.
+pub const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Number of batches buffered for a writer thread before blocking.
+const BATCH_BOUND: usize = 16;
+
+/// Output struct to write entries to a destination.
+///
+/// Each entry written is followed by a terminator, which defaults
+/// to a line ending but can be changed for non-line based inputs
+/// (such as custom record delimiters, or length-delimited messages).
+pub struct Output<'a> {
+    target: Target<'a>,
+    terminator: Vec<u8>,
+    flushing: bool,
+    digest: Option<(Algorithm, bool)>,
+    hashed: Vec<u8>,
+}
+
+/// Target enum to represent the destination of entries.
+///
+/// Stdout is the default destination; sharded output will route
+/// each entry to a shard based on the hash of the entry, ensuring
+/// that the same entry will always land in the same shard.
+enum Target<'a> {
+    /// Entries written to stdout.
+    Stdout(BufWriter<StdoutLock<'a>>),
+
+    /// Entries written to a file, optionally staged for a rename.
+    File(BufWriter<File>, Option<Staged>),
+
+    /// Entries routed across a set of shard writers.
+    Shards(Vec<Shard>),
+
+    /// Entries passed to an output on a writer thread.
+    Piped(Pipe),
+
+    /// Entries discarded without being written.
+    Sink,
+}
+
+impl<'a> Output<'a> {
+    /// Creates an output writing to stdout, using a buffer of `capacity` bytes.
+    pub fn stdout(stdout: StdoutLock<'a>, capacity: usize) -> Output<'a> {
+        Output {
+            target: Target::Stdout(BufWriter::with_capacity(capacity, stdout)),
+            terminator: b"\n".to_vec(),
+            flushing: false,
+            digest: None,
+            hashed: Vec::new(),
+        }
+    }
+
+    /// Creates an output which discards all entries.
+    pub fn sink() -> Output<'a> {
+        Output {
+            target: Target::Sink,
+            terminator: Vec::new(),
+            flushing: false,
+            digest: None,
+            hashed: Vec::new(),
+        }
+    }
+
+    /// Creates an output writing to a file.
+    ///
+    /// When appending, entries are written directly to the end of the
+    /// file. Otherwise entries are written to a temporary file in the
+    /// same directory, which replaces the file once output is finished.
+    pub fn file(path: &str, append: bool, capacity: usize) -> io::Result<Output<'a>> {
+        let cannot = |err: io::Error| {
+            io::Error::new(err.kind(), format!("cannot create '{}': {}", path, err))
+        };
+
+        let (file, staged) = if append {
+            let file = OpenOptions::new().create(true).append(true).open(path);
+            (file.map_err(cannot)?, None)
+        } else {
+            let staged = Staged::new(Path::new(path));
+            (File::create(&staged.temp).map_err(cannot)?, Some(staged))
+        };
+
+        Ok(Output {
+            target: Target::File(BufWriter::with_capacity(capacity, file), staged),
+            terminator: b"\n".to_vec(),
+            flushing: false,
+            digest: None,
+            hashed: Vec::new(),
+        })
+    }
+
+    /// Creates an output to replace a file in place.
+    ///
+    /// Entries are staged in the same way as `Output::file`, but the
+    /// permissions of the file are kept and the original file can be
+    /// kept as a backup by providing a suffix to append to the name.
+    pub fn in_place(path: &str, backup: Option<&str>, capacity: usize) -> io::Result<Output<'a>> {
+        let cannot = |err: io::Error| {
+            io::Error::new(err.kind(), format!("cannot replace '{}': {}", path, err))
+        };
+
+        let mut staged = Staged::new(Path::new(path));
+        let permissions = fs::metadata(path).map_err(cannot)?.permissions();
+
+        if let Some(suffix) = backup {
+            staged.backup = Some(PathBuf::from(format!("{}{}", path, suffix)));
+        }
+
+        let file = File::create(&staged.temp).map_err(cannot)?;
+        file.set_permissions(permissions).map_err(cannot)?;
+
+        Ok(Output {
+            target: Target::File(BufWriter::with_capacity(capacity, file), Some(staged)),
+            terminator: b"\n".to_vec(),
+            flushing: false,
+            digest: None,
+            hashed: Vec::new(),
+        })
+    }
+
+    /// Creates a set of shard outputs from a path pattern.
+    ///
+    /// Any `{}` in the pattern will be replaced with the shard index,
+    /// and any pattern ending in `.gz` will be written using gzip.
+    pub fn shards(pattern: &str, count: usize, capacity: usize) -> io::Result<Output<'a>> {
+        let mut shards = Vec::with_capacity(count);
+
+        for idx in 0..count {
+            let path = pattern.replace("{}", &idx.to_string());
+            let file = BufWriter::with_capacity(capacity, File::create(&path)?);
+
+            shards.push(if path.ends_with(".gz") {
+                Shard::Gzip(GzEncoder::new(file, Compression::default()))
+            } else {
+                Shard::Plain(file)
+            });
+        }
+
+        Ok(Output {
+            target: Target::Shards(shards),
+            terminator: b"\n".to_vec(),
+            flushing: false,
+            digest: None,
+            hashed: Vec::new(),
+        })
+    }
+
+    /// Creates an output which writes on a background thread.
+    ///
+    /// The inner output is created on the writer thread (as stdout can
+    /// only be locked by a single thread), and entries are passed across
+    /// in batches so that writing overlaps with reading and filtering.
+    /// This is only the final stage of the pipeline; entries are read on
+    /// the input workers (see `Sources::parallel`), which also hash and
+    /// detect them when keys allow, before reaching this writer.
+    pub fn piped<F>(create: F) -> io::Result<Output<'a>>
+    where
+        F: FnOnce() -> io::Result<Output<'static>> + Send + 'static,
+    {
+        let (ready, created) = mpsc::sync_channel(1);
+        let (sender, receiver) = mpsc::sync_channel::<Option<Batch>>(BATCH_BOUND);
+
+        let handle = thread::spawn(move || {
+            let mut output = match create() {
+                Ok(output) => {
+                    let _ = ready.send(Ok(()));
+                    output
+                }
+                Err(err) => {
+                    let _ = ready.send(Err(err));
+                    return Ok(());
+                }
+            };
+
+            // only finish once signalled, so failed runs are never persisted
+            while let Ok(batch) = receiver.recv() {
+                let batch = match batch {
+                    Some(batch) => batch,
+                    None => return output.finish(),
+                };
+
+                let mut start = 0;
+                for end in batch.ends {
+                    output.write_entry(&batch.data[start..end])?;
+                    start = end;
+                }
+            }
+
+            Ok(())
+        });
+
+        created
+            .recv()
+            .unwrap_or_else(|_| Err(io::Error::other("output writer failed")))?;
+
+        Ok(Output {
+            target: Target::Piped(Pipe {
+                batch: Batch::default(),
+                handle: Some(handle),
+                sender,
+            }),
+            terminator: Vec::new(),
+            flushing: false,
+            digest: None,
+            hashed: Vec::new(),
+        })
+    }
+
+    /// Sets the terminator written after each entry.
+    pub fn terminator(mut self, terminator: &[u8]) -> Output<'a> {
+        self.terminator = terminator.to_vec();
+        self
+    }
+
+    /// Sets whether the output is flushed after each entry.
+    pub fn flushing(mut self, flushing: bool) -> Output<'a> {
+        self.flushing = flushing;
+        self
+    }
+
+    /// Sets the algorithm used to write digests in place of entries.
+    ///
+    /// When keeping entries, each entry is written after its digest,
+    /// separated by a tab.
+    pub fn digest(mut self, algorithm: Algorithm, entries: bool) -> Output<'a> {
+        self.digest = Some((algorithm, entries));
+        self
+    }
+
+    /// Writes an entry to the output, followed by the terminator.
+    #[inline]
+    pub fn write_entry(&mut self, input: &[u8]) -> io::Result<()> {
+        let input = match self.digest {
+            Some((algorithm, entries)) => {
+                self.hashed.clear();
+                algorithm.write_hex(input, &mut self.hashed);
+                if entries {
+                    self.hashed.push(b'\t');
+                    self.hashed.extend_from_slice(input);
+                }
+                &self.hashed[..]
+            }
+            None => input,
+        };
+
+        let writer: &mut dyn Write = match &mut self.target {
+            Target::Stdout(stdout) => stdout,
+            Target::File(file, _) => file,
+            Target::Shards(shards) => {
+                let idx = xxh3_64(input) % shards.len() as u64;
+                &mut shards[idx as usize]
+            }
+            Target::Piped(pipe) => return pipe.write_entry(input),
+            Target::Sink => return Ok(()),
+        };
+
+        writer.write_all(input)?;
+        writer.write_all(&self.terminator)?;
+
+        if self.flushing {
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Finalizes the output, flushing all buffers.
+    ///
+    /// This must be called for compressed shards to be valid, as the
+    /// trailing data of the encoder is only written on completion.
+    pub fn finish(self) -> io::Result<()> {
+        match self.target {
+            Target::Stdout(mut stdout) => stdout.flush(),
+            Target::File(file, staged) => {
+                file.into_inner()?.sync_all()?;
+                match staged {
+                    Some(staged) => staged.persist(),
+                    None => Ok(()),
+                }
+            }
+            Target::Shards(shards) => {
+                for shard in shards {
+                    shard.finish()?;
+                }
+                Ok(())
+            }
+            Target::Piped(pipe) => pipe.finish(),
+            Target::Sink => Ok(()),
+        }
+    }
+}
+
+/// Batch of entries passed to a writer thread.
+#[derive(Default)]
+struct Batch {
+    data: Vec<u8>,
+    ends: Vec<usize>,
+}
+
+/// Pipe to pass batches of entries to a writer thread.
+struct Pipe {
+    batch: Batch,
+    handle: Option<JoinHandle<io::Result<()>>>,
+    sender: SyncSender<Option<Batch>>,
+}
+
+impl Pipe {
+    /// Buffers an entry, sending the batch once it's full.
+    #[inline]
+    fn write_entry(&mut self, input: &[u8]) -> io::Result<()> {
+        self.batch.data.extend_from_slice(input);
+        self.batch.ends.push(self.batch.data.len());
+
+        if self.batch.data.len() + self.batch.ends.len() < BUFFER_SIZE {
+            return Ok(());
+        }
+
+        let batch = mem::take(&mut self.batch);
+        self.send(Some(batch))
+    }
+
+    /// Sends the remaining entries, and waits for the writer to finish.
+    fn finish(mut self) -> io::Result<()> {
+        let batch = mem::take(&mut self.batch);
+        self.send(Some(batch))?;
+        self.send(None)?;
+        self.join()
+    }
+
+    /// Sends a message to the writer, surfacing any writer errors.
+    fn send(&mut self, batch: Option<Batch>) -> io::Result<()> {
+        match self.sender.send(batch) {
+            Ok(()) => Ok(()),
+            Err(_) => self.join(),
+        }
+    }
+
+    /// Waits for the writer thread, returning the result of writing.
+    fn join(&mut self) -> io::Result<()> {
+        match self.handle.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            _ => Err(io::Error::other("output writer failed")),
+        }
+    }
+}
+
+/// Staged file to be written via a temporary file.
+///
+/// The temporary file lives alongside the target, so that the final
+/// rename is atomic. If the staged file is dropped without being
+/// persisted, the temporary file is removed.
+pub struct Staged {
+    backup: Option<PathBuf>,
+    path: PathBuf,
+    temp: PathBuf,
+}
+
+impl Staged {
+    /// Creates a new `Staged` file for the provided path.
+    pub fn new(path: &Path) -> Staged {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".runiq-{}.tmp", process::id()));
+
+        Staged {
+            backup: None,
+            path: path.to_path_buf(),
+            temp: path.with_file_name(name),
+        }
+    }
+
+    /// Retrieves the path of the temporary file to write to.
+    pub fn temp(&self) -> &Path {
+        &self.temp
+    }
+
+    /// Persists the staged file by renaming it over the target.
+    ///
+    /// If a backup is required, the target is linked (or copied) to
+    /// the backup path first, so the target is never missing.
+    pub fn persist(self) -> io::Result<()> {
+        let cannot = |path: &Path, err: io::Error| {
+            let message = format!("cannot write '{}': {}", path.display(), err);
+            io::Error::new(err.kind(), message)
+        };
+
+        if let Some(ref backup) = self.backup {
+            let _ = fs::remove_file(backup);
+            fs::hard_link(&self.path, backup)
+                .or_else(|_| fs::copy(&self.path, backup).map(|_| ()))
+                .map_err(|err| cannot(backup, err))?;
+        }
+
+        fs::rename(&self.temp, &self.path).map_err(|err| cannot(&self.path, err))
+    }
+}
+
+/// Implement `Drop` to clean up any unpersisted temporary file.
+impl Drop for Staged {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.temp);
+    }
+}
+
+/// Shard enum to represent a single output file.
+pub enum Shard {
+    /// Shard written as plain text.
+    Plain(BufWriter<File>),
+
+    /// Shard written through a gzip encoder.
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl Shard {
+    /// Finalizes the shard, completing any encoding.
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Shard::Plain(mut file) => file.flush(),
+            Shard::Gzip(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+/// Implement `Write` to pass through to the inner writers.
+impl Write for Shard {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Shard::Plain(file) => file.write(buf),
+            Shard::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Shard::Plain(file) => file.flush(),
+            Shard::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Plugin module used to load custom filters from dynamic libraries.
+//!
+//! Plugins export a `runiq_plugin` function returning a `RuniqPlugin`
+//! table, either via `runiq::export_plugin!` or by hand from C.
+use runiq::ffi::{PluginFilter, RuniqPlugin};
+use runiq::Filter;
+
+use libloading::{Library, Symbol};
+
+use std::io::{self, Read, Write};
+
+/// Filter loaded from a plugin, keeping the library loaded while in use.
+pub struct Plugin {
+    // fields drop in order, so the filter is released before the library
+    filter: PluginFilter,
+    _library: Library,
+}
+
+/// Loads the filter plugin at the provided path, using a hash seed.
+pub fn load(path: &str, seed: u64) -> io::Result<Plugin> {
+    let cannot = |err: &dyn std::fmt::Display| {
+        io::Error::other(format!("cannot load plugin '{}': {}", path, err))
+    };
+
+    unsafe {
+        let library = Library::new(path).map_err(|err| cannot(&err))?;
+        let plugin = {
+            let entry: Symbol<extern "C" fn() -> *const RuniqPlugin> =
+                library.get(b"runiq_plugin\0").map_err(|err| cannot(&err))?;
+
+            match entry().as_ref() {
+                Some(plugin) => plugin,
+                None => return Err(cannot(&"no filter table provided")),
+            }
+        };
+
+        let filter = PluginFilter::new(plugin, seed).map_err(|err| cannot(&err))?;
+
+        Ok(Plugin {
+            filter,
+            _library: library,
+        })
+    }
+}
+
+impl Filter for Plugin {
+    #[inline]
+    fn detect(&mut self, input: &[u8]) -> bool {
+        self.filter.detect(input)
+    }
+
+    #[inline]
+    fn contains(&self, input: &[u8]) -> bool {
+        self.filter.contains(input)
+    }
+
+    fn clear(&mut self) {
+        self.filter.clear()
+    }
+
+    fn len(&self) -> u64 {
+        self.filter.len()
+    }
+
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.filter.save_state(writer)
+    }
+
+    fn load_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.filter.load_state(reader)
+    }
+}
@@ -0,0 +1,147 @@
+//! Progress module used to report the progress of long running jobs.
+//!
+//! Progress is rendered as a single line on stderr, and is refreshed
+//! periodically rather than on every entry to keep overhead low. When
+//! the total size of input is known, a progress bar is included.
+//!
+//! Progress can also be reported periodically, in which case a full
+//! line is written on each interval so that snapshots are kept (such
+//! as when stderr is written to a log file).
+use format_num::NumberFormat;
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Number of records between checks of whether to render.
+const CHECK_INTERVAL: u64 = 8192;
+
+/// Minimum duration between renders of progress.
+const RENDER_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Width (in characters) of the rendered progress bar.
+const BAR_WIDTH: usize = 24;
+
+/// Progress struct to track and render the progress of input.
+#[derive(Debug)]
+pub struct Progress {
+    bytes: u64,
+    lines: u64,
+    uniques: u64,
+    total: Option<u64>,
+    interval: Option<Duration>,
+    rendered: Instant,
+    started: Instant,
+}
+
+impl Progress {
+    /// Creates a new `Progress` tracker against an optional total size.
+    pub fn new(total: Option<u64>) -> Progress {
+        let now = Instant::now();
+        Progress {
+            bytes: 0,
+            lines: 0,
+            uniques: 0,
+            total,
+            interval: None,
+            rendered: now,
+            started: now,
+        }
+    }
+
+    /// Creates a new `Progress` tracker writing a snapshot every interval.
+    pub fn periodic(interval: Duration) -> Progress {
+        Progress {
+            interval: Some(interval),
+            ..Progress::new(None)
+        }
+    }
+
+    /// Records an entry of the provided size being read.
+    #[inline]
+    pub fn add_entry(&mut self, size: usize) {
+        self.bytes += size as u64;
+        self.lines += 1;
+
+        let interval = self.interval.unwrap_or(RENDER_INTERVAL);
+        if self.lines.is_multiple_of(CHECK_INTERVAL) && self.rendered.elapsed() >= interval {
+            self.render();
+        }
+    }
+
+    /// Records a unique entry being found.
+    #[inline]
+    pub fn add_unique(&mut self) {
+        self.uniques += 1;
+    }
+
+    /// Renders the final progress, and moves past the progress line.
+    pub fn finish(mut self) {
+        self.render();
+        if self.interval.is_none() {
+            eprintln!();
+        }
+    }
+
+    /// Renders the current progress to stderr.
+    fn render(&mut self) {
+        if self.interval.is_some() {
+            return self.snapshot();
+        }
+
+        let num = NumberFormat::new();
+        let elapsed = self.started.elapsed().as_secs_f64().max(1e-3);
+        let rate = self.lines as f64 / elapsed;
+
+        let mut line = match self.total {
+            Some(total) => {
+                let ratio = (self.bytes as f64 / total.max(1) as f64).min(1.0);
+                let filled = (ratio * BAR_WIDTH as f64) as usize;
+
+                format!(
+                    "[{}{}] {:>5.1}% {} / {}",
+                    "#".repeat(filled),
+                    " ".repeat(BAR_WIDTH - filled),
+                    ratio * 100.0,
+                    bytesize::to_string(self.bytes, false),
+                    bytesize::to_string(total, false),
+                )
+            }
+            None => bytesize::to_string(self.bytes, false),
+        };
+
+        line.push_str(&format!(
+            " | {} lines/s | {} uniques",
+            num.format(",.0", rate),
+            num.format(",.0", self.uniques as f64),
+        ));
+
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{}", line);
+        let _ = stderr.flush();
+
+        self.rendered = Instant::now();
+    }
+
+    /// Writes a snapshot of the current progress to stderr.
+    fn snapshot(&mut self) {
+        let num = NumberFormat::new();
+        let elapsed = self.started.elapsed();
+        let seconds = elapsed.as_secs();
+        let rate = self.lines as f64 / elapsed.as_secs_f64().max(1e-3);
+        let duplicates = self.lines - self.uniques;
+
+        eprintln!(
+            "[{:02}:{:02}:{:02}] {} lines | {} uniques | {} duplicates ({:.2}%) | {} lines/s",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60,
+            num.format(",.0", self.lines as f64),
+            num.format(",.0", self.uniques as f64),
+            num.format(",.0", duplicates as f64),
+            duplicates as f64 / self.lines.max(1) as f64 * 100.0,
+            num.format(",.0", rate),
+        );
+
+        self.rendered = Instant::now();
+    }
+}
@@ -0,0 +1,217 @@
+//! Protobuf module used to filter length-delimited messages.
+//!
+//! Messages are read as varint length-delimited records, and keys
+//! are extracted from a field of the message using a descriptor set
+//! provided by the user. The original message bytes are emitted.
+use prost_reflect::prost::{DecodeError, Message};
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, FieldDescriptor, Kind, MessageDescriptor, Value,
+};
+
+use crate::input::Records;
+
+use std::fs;
+use std::io::{self, BufRead, Read};
+
+/// Largest message length accepted, matching the limit of protobuf itself.
+const MAX_MESSAGE_SIZE: u64 = i32::MAX as u64;
+
+/// Reader for varint length-delimited protobuf messages.
+///
+/// Each record returned includes the length prefix, so that the
+/// records can be written back out exactly as they were read.
+pub struct Messages<B> {
+    reader: B,
+    buffer: Vec<u8>,
+    offset: u64,
+}
+
+impl<B: BufRead> Messages<B> {
+    /// Creates a new `Messages` reader from a buffered reader.
+    pub fn new(reader: B) -> Messages<B> {
+        Messages {
+            reader,
+            buffer: Vec::new(),
+            offset: 0,
+        }
+    }
+}
+
+/// Implement `Records` for length-delimited messages.
+impl<B: BufRead> Records for Messages<B> {
+    fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        self.buffer.clear();
+
+        let mut length = 0;
+        let mut shift = 0;
+
+        // read the varint length prefix
+        loop {
+            let mut byte = [0; 1];
+
+            match self.reader.read_exact(&mut byte) {
+                Ok(()) => self.buffer.push(byte[0]),
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                    if self.buffer.is_empty() {
+                        return Ok(None);
+                    }
+                    return Err(err);
+                }
+                Err(err) => return Err(err),
+            }
+
+            length |= ((byte[0] & 0x7F) as u64) << shift;
+
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+
+            shift += 7;
+
+            if shift >= 64 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid message length prefix",
+                ));
+            }
+        }
+
+        // lengths are untrusted, so are validated before reading
+        if length > MAX_MESSAGE_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "message length exceeds the protobuf limit",
+            ));
+        }
+
+        // read the message body after the prefix, which may be truncated
+        let read = (&mut self.reader)
+            .take(length)
+            .read_to_end(&mut self.buffer)?;
+
+        if (read as u64) < length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        self.offset += self.buffer.len() as u64;
+
+        Ok(Some(&self.buffer))
+    }
+
+    #[inline]
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+/// Extractor used to pull a key field out of a message.
+pub struct Extractor {
+    message: MessageDescriptor,
+    path: Vec<FieldDescriptor>,
+}
+
+impl Extractor {
+    /// Creates a new `Extractor` from a descriptor set.
+    ///
+    /// The field is provided as a dotted path through any nested
+    /// messages, and is validated against the descriptor up front.
+    pub fn new(descriptor: &str, message: &str, field: &str) -> io::Result<Extractor> {
+        let bytes = fs::read(descriptor).map_err(|err| {
+            io::Error::new(err.kind(), format!("cannot open '{}': {}", descriptor, err))
+        })?;
+
+        let pool = DescriptorPool::decode(&bytes[..])
+            .map_err(|err| invalid(format!("invalid descriptor '{}': {}", descriptor, err)))?;
+
+        let message = pool
+            .get_message_by_name(message)
+            .ok_or_else(|| invalid(format!("unknown message '{}'", message)))?;
+
+        let mut path = Vec::new();
+        let mut current = Some(message.clone());
+
+        for name in field.split('.') {
+            let field_desc = current
+                .and_then(|desc| desc.get_field_by_name(name))
+                .ok_or_else(|| invalid(format!("unknown field '{}'", field)))?;
+
+            if field_desc.is_list() || field_desc.is_map() {
+                return Err(invalid(format!("unsupported field '{}'", field)));
+            }
+
+            current = match field_desc.kind() {
+                Kind::Message(desc) => Some(desc),
+                _ => None,
+            };
+
+            path.push(field_desc);
+        }
+
+        Ok(Extractor { message, path })
+    }
+
+    /// Extracts the key field from a length-delimited message.
+    ///
+    /// The key is written into the provided buffer, to allow the
+    /// caller to reuse the same buffer across many messages.
+    pub fn extract(&self, input: &[u8], key: &mut Vec<u8>) -> Result<(), DecodeError> {
+        let mut message = DynamicMessage::new(self.message.clone());
+        message.merge_length_delimited(input)?;
+
+        let mut value = Value::Message(message);
+
+        for field in &self.path {
+            value = match value {
+                Value::Message(message) => message.get_field(field).into_owned(),
+                _ => unreachable!("field path validated on creation"),
+            };
+        }
+
+        match value {
+            Value::String(value) => key.extend_from_slice(value.as_bytes()),
+            Value::Bytes(value) => key.extend_from_slice(&value),
+            Value::Message(value) => value.encode(key).expect("vec has capacity"),
+            value => key.extend_from_slice(format!("{:?}", value).as_bytes()),
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates an error to represent invalid protobuf configuration.
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_reading() {
+        let mut messages = Messages::new(&b"\x02ab\x00\x02c"[..]);
+
+        assert_eq!(messages.next_record().unwrap(), Some(&b"\x02ab"[..]));
+        assert_eq!(messages.next_record().unwrap(), Some(&b"\x00"[..]));
+        assert_eq!(messages.offset(), 4);
+
+        let err = messages.next_record().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn oversized_message_lengths() {
+        // a length prefix of 2^62, followed by a small body
+        let input = b"\x80\x80\x80\x80\x80\x80\x80\x80\x40abc";
+        let mut messages = Messages::new(&input[..]);
+
+        let err = messages.next_record().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // a length within the limit, but far beyond the input
+        let mut messages = Messages::new(&b"\xFF\xFF\xFF\xFF\x07abc"[..]);
+
+        let err = messages.next_record().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
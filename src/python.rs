@@ -0,0 +1,102 @@
+//! Python bindings for use of filters from Python.
+use crate::filters::{Filter, Filters};
+
+use pyo3::exceptions::{PyIOError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::pybacked::{PyBackedBytes, PyBackedStr};
+
+/// Python wrapper around a boxed `Filter`.
+///
+/// Filters are created by name, using the same names as the CLI. Both
+/// `str` and `bytes` inputs are accepted, with `str` encoded as UTF-8.
+#[pyclass(name = "Filter", module = "runiq", unsendable)]
+pub struct PyFilter {
+    inner: Box<dyn Filter>,
+}
+
+#[pymethods]
+impl PyFilter {
+    /// Creates a new filter from the name of a filter kind.
+    #[new]
+    #[pyo3(signature = (kind = "quick"))]
+    pub fn new(kind: &str) -> PyResult<Self> {
+        let inner: Box<dyn Filter> = match Filters::from_name(kind) {
+            None => {
+                let message = format!("unsupported filter '{}'", kind);
+                return Err(PyValueError::new_err(message));
+            }
+            Some(kind) => kind
+                .try_into_filter()
+                .map_err(|err| PyIOError::new_err(err.to_string()))?,
+        };
+
+        Ok(Self { inner })
+    }
+
+    /// Detects if an input is unique, returning `True` if so.
+    pub fn detect(&mut self, input: &Bound<'_, PyAny>) -> PyResult<bool> {
+        with_bytes(input, |bytes| self.inner.detect(bytes))
+    }
+
+    /// Checks if an input has been seen, without recording it.
+    pub fn contains(&self, input: &Bound<'_, PyAny>) -> PyResult<bool> {
+        with_bytes(input, |bytes| self.inner.contains(bytes))
+    }
+
+    /// Clears all inputs recorded by the filter.
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    /// Retrieves the number of unique inputs recorded.
+    pub fn __len__(&self) -> usize {
+        self.inner.len() as usize
+    }
+
+    /// Checks if an input has been seen, via the `in` operator.
+    pub fn __contains__(&self, input: &Bound<'_, PyAny>) -> PyResult<bool> {
+        self.contains(input)
+    }
+}
+
+/// Filters the unique values from an iterable into a list.
+///
+/// Values are returned in the order they were first seen, and must
+/// each be either a `str` or `bytes` object.
+#[pyfunction]
+#[pyo3(signature = (iterable, kind = "quick"))]
+pub fn unique_lines<'py>(
+    iterable: &Bound<'py, PyAny>,
+    kind: &str,
+) -> PyResult<Vec<Bound<'py, PyAny>>> {
+    let mut filter = PyFilter::new(kind)?;
+    let mut unique = Vec::new();
+
+    for value in iterable.try_iter()? {
+        let value = value?;
+        if filter.detect(&value)? {
+            unique.push(value);
+        }
+    }
+
+    Ok(unique)
+}
+
+/// Python module containing the `Filter` class and `unique_lines`.
+#[pymodule]
+pub fn runiq(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyFilter>()?;
+    module.add_function(wrap_pyfunction!(unique_lines, module)?)?;
+    Ok(())
+}
+
+/// Passes the bytes of a `str` or `bytes` input through a closure.
+fn with_bytes<T>(input: &Bound<'_, PyAny>, f: impl FnOnce(&[u8]) -> T) -> PyResult<T> {
+    if let Ok(value) = input.extract::<PyBackedStr>() {
+        return Ok(f(value.as_bytes()));
+    }
+    if let Ok(value) = input.extract::<PyBackedBytes>() {
+        return Ok(f(&value));
+    }
+    Err(PyTypeError::new_err("input must be str or bytes"))
+}
@@ -0,0 +1,107 @@
+//! Sampling module used to select a random sample of entries.
+//!
+//! This module provides a reservoir sample, which allows for a
+//! uniform random sample of entries to be taken across a single
+//! pass of input, without knowing the size of the input upfront.
+
+/// Reservoir used to store a uniform sample of entries.
+///
+/// Sampling is driven by a seeded generator, so that the same input
+/// and seed will always result in the same sample being selected.
+#[derive(Debug)]
+pub struct Reservoir {
+    entries: Vec<(u64, Vec<u8>)>,
+    random: SplitMix64,
+    size: usize,
+    seen: u64,
+}
+
+impl Reservoir {
+    /// Creates a new `Reservoir` of the provided size.
+    pub fn new(size: usize, seed: u64) -> Reservoir {
+        Reservoir {
+            entries: Vec::with_capacity(size),
+            random: SplitMix64(seed),
+            size,
+            seen: 0,
+        }
+    }
+
+    /// Offers an entry to the reservoir.
+    ///
+    /// Each entry offered has a decreasing probability of being kept,
+    /// replacing a random entry previously stored in the reservoir.
+    pub fn offer(&mut self, input: &[u8]) {
+        let sequence = self.seen;
+        self.seen += 1;
+
+        if self.entries.len() < self.size {
+            self.entries.push((sequence, input.to_vec()));
+            return;
+        }
+
+        let index = self.random.below(self.seen) as usize;
+
+        if index < self.size {
+            self.entries[index] = (sequence, input.to_vec());
+        }
+    }
+
+    /// Consumes the reservoir, returning the sample in input order.
+    pub fn into_sample(mut self) -> Vec<Vec<u8>> {
+        self.entries.sort_unstable_by_key(|(sequence, _)| *sequence);
+        self.entries.into_iter().map(|(_, entry)| entry).collect()
+    }
+}
+
+/// Minimal SplitMix64 generator used to drive sampling.
+///
+/// This is used rather than an external generator to guarantee the
+/// same sequence for the same seed, regardless of any dependencies.
+#[derive(Debug)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Generates the next value in the sequence.
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Generates a value in the range `0..bound`.
+    fn below(&mut self, bound: u64) -> u64 {
+        ((self.next() as u128 * bound as u128) >> 64) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Reservoir;
+
+    #[test]
+    fn reservoir_smaller_than_input() {
+        let mut reservoir = Reservoir::new(3, 0);
+
+        for input in 0..100u8 {
+            reservoir.offer(&[input]);
+        }
+
+        let sample = reservoir.into_sample();
+
+        assert_eq!(sample.len(), 3);
+        assert!(sample.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn reservoir_larger_than_input() {
+        let mut reservoir = Reservoir::new(5, 0);
+
+        reservoir.offer(b"a");
+        reservoir.offer(b"b");
+
+        assert_eq!(reservoir.into_sample(), vec![b"a".to_vec(), b"b".to_vec()]);
+    }
+}
@@ -0,0 +1,221 @@
+//! Sequences module used to filter FASTA and FASTQ records.
+//!
+//! Records span multiple lines (a header, the sequence and, for FASTQ,
+//! the quality scores), so they're read as a whole and emitted exactly
+//! as they appear in the input. Keys are the sequence of each record.
+use crate::input::Records;
+
+use std::io::{self, BufRead};
+
+/// Format of sequence records within an input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum SequenceFormat {
+    /// Header lines starting with `>`, followed by lines of sequence.
+    Fasta,
+
+    /// Header, sequence, separator and quality lines.
+    Fastq,
+}
+
+impl SequenceFormat {
+    /// Extracts the sequence of a record into a buffer.
+    ///
+    /// Sequences spread across multiple lines in FASTA records are
+    /// joined together, so that wrapping does not affect the key.
+    pub fn extract(&self, record: &[u8], buffer: &mut Vec<u8>) {
+        let mut lines = record.split(|byte| *byte == b'\n').skip(1).map(strip);
+        match self {
+            SequenceFormat::Fasta => lines.for_each(|line| buffer.extend_from_slice(line)),
+            SequenceFormat::Fastq => buffer.extend_from_slice(lines.next().unwrap_or_default()),
+        }
+    }
+}
+
+/// Reader for FASTA records.
+///
+/// Each record starts at a header line beginning with `>`, and covers
+/// every line up until the next header line (or the end of input).
+pub struct Fasta<B> {
+    reader: B,
+    buffer: Vec<u8>,
+    offset: u64,
+}
+
+impl<B: BufRead> Fasta<B> {
+    /// Creates a new `Fasta` reader from a buffered reader.
+    pub fn new(reader: B) -> Fasta<B> {
+        Fasta {
+            reader,
+            buffer: Vec::new(),
+            offset: 0,
+        }
+    }
+}
+
+/// Implement `Records` for FASTA records.
+impl<B: BufRead> Records for Fasta<B> {
+    fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        self.buffer.clear();
+
+        // read lines until the start of the next header
+        loop {
+            let available = self.reader.fill_buf()?;
+
+            if available.is_empty() || (!self.buffer.is_empty() && available[0] == b'>') {
+                break;
+            }
+
+            self.reader.read_until(b'\n', &mut self.buffer)?;
+        }
+
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+
+        if self.buffer[0] != b'>' {
+            return Err(invalid("FASTA record does not start with '>'"));
+        }
+
+        self.offset += self.buffer.len() as u64;
+
+        Ok(Some(strip(&self.buffer)))
+    }
+
+    #[inline]
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+/// Reader for FASTQ records.
+///
+/// Each record covers exactly four lines; a header beginning with `@`,
+/// the sequence, a separator beginning with `+`, and the quality scores.
+pub struct Fastq<B> {
+    reader: B,
+    buffer: Vec<u8>,
+    offset: u64,
+}
+
+impl<B: BufRead> Fastq<B> {
+    /// Creates a new `Fastq` reader from a buffered reader.
+    pub fn new(reader: B) -> Fastq<B> {
+        Fastq {
+            reader,
+            buffer: Vec::new(),
+            offset: 0,
+        }
+    }
+}
+
+/// Implement `Records` for FASTQ records.
+impl<B: BufRead> Records for Fastq<B> {
+    fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        self.buffer.clear();
+
+        let mut starts = [0; 4];
+
+        for start in starts.iter_mut() {
+            *start = self.buffer.len();
+
+            if self.reader.read_until(b'\n', &mut self.buffer)? == 0 {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                return Err(invalid("FASTQ record is incomplete"));
+            }
+        }
+
+        if self.buffer[starts[0]] != b'@' {
+            return Err(invalid("FASTQ record does not start with '@'"));
+        }
+
+        if self.buffer[starts[2]] != b'+' {
+            return Err(invalid("FASTQ record has no '+' separator"));
+        }
+
+        self.offset += self.buffer.len() as u64;
+
+        Ok(Some(strip(&self.buffer)))
+    }
+
+    #[inline]
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+/// Strips a trailing line ending from a slice of bytes.
+fn strip(mut line: &[u8]) -> &[u8] {
+    if let [rest @ .., b'\n'] = line {
+        line = rest;
+    }
+    if let [rest @ .., b'\r'] = line {
+        line = rest;
+    }
+    line
+}
+
+/// Creates an error for invalid sequence records.
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fasta, Fastq, SequenceFormat};
+    use crate::input::Records;
+
+    /// Reads all records from a reader.
+    fn records<R: Records>(mut reader: R) -> Vec<Vec<u8>> {
+        let mut records = Vec::new();
+        while let Some(record) = reader.next_record().unwrap() {
+            records.push(record.to_vec());
+        }
+        records
+    }
+
+    #[test]
+    fn fasta_records() {
+        let input = &b">a\nAC\nGT\n>b\r\nACGT\r\n"[..];
+
+        assert_eq!(
+            records(Fasta::new(input)),
+            vec![b">a\nAC\nGT".to_vec(), b">b\r\nACGT".to_vec()]
+        );
+
+        let mut buffer = Vec::new();
+        SequenceFormat::Fasta.extract(b">a\nAC\nGT", &mut buffer);
+        assert_eq!(buffer, b"ACGT");
+
+        buffer.clear();
+        SequenceFormat::Fasta.extract(b">b\r\nACGT", &mut buffer);
+        assert_eq!(buffer, b"ACGT");
+    }
+
+    #[test]
+    fn fastq_records() {
+        let input = &b"@a\nACGT\n+\nIIII\n@b\nACGA\n+b\nIIII"[..];
+
+        assert_eq!(
+            records(Fastq::new(input)),
+            vec![
+                b"@a\nACGT\n+\nIIII".to_vec(),
+                b"@b\nACGA\n+b\nIIII".to_vec()
+            ]
+        );
+
+        let mut buffer = Vec::new();
+        SequenceFormat::Fastq.extract(b"@a\nACGT\n+\nIIII", &mut buffer);
+        assert_eq!(buffer, b"ACGT");
+    }
+
+    #[test]
+    fn invalid_records() {
+        assert!(Fasta::new(&b"ACGT\n"[..]).next_record().is_err());
+        assert!(Fastq::new(&b"@a\nACGT\n"[..]).next_record().is_err());
+        assert!(Fastq::new(&b"a\nACGT\n+\nIIII\n"[..])
+            .next_record()
+            .is_err());
+    }
+}
@@ -0,0 +1,414 @@
+//! Sources module used to read records across all inputs.
+//!
+//! Inputs are read sequentially by default, but can also be opened
+//! and split into records on a pool of worker threads. Workers pass
+//! back batches of records, either in input order or in the order they
+//! are read, and can also detect uniques against a shared filter so
+//! that detection happens in parallel with reading.
+use crate::input::{self, Format, Records};
+use runiq::ConcurrentFilter;
+
+use std::io;
+use std::mem;
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread;
+use std::vec;
+
+/// Target size (in bytes) of each batch of records sent by workers.
+const BATCH_SIZE: usize = 64 * 1024;
+
+/// Number of batches buffered per channel before workers block.
+const BATCH_BOUND: usize = 16;
+
+/// Sources enum to represent the strategy used to read inputs.
+///
+/// Each source yields the index of an input alongside the records of
+/// the input (or the error raised when opening the input). Records
+/// cover an entire input, except when reading in parallel without
+/// ordering, where each batch of records is yielded separately.
+pub enum Sources {
+    /// Inputs opened and read on the calling thread, in order.
+    Sequential {
+        capacity: usize,
+        follow: bool,
+        format: Format,
+        inputs: vec::IntoIter<String>,
+        index: usize,
+    },
+
+    /// Inputs read on worker threads, yielded in order.
+    Ordered {
+        receivers: vec::IntoIter<Receiver<Message>>,
+        index: usize,
+    },
+
+    /// Inputs read on worker threads, yielded as batches arrive.
+    Unordered {
+        receiver: Receiver<(usize, Message)>,
+    },
+}
+
+impl Sources {
+    /// Creates a source reading inputs sequentially.
+    pub fn sequential(inputs: Vec<String>, format: Format, capacity: usize) -> Sources {
+        Sources::Sequential {
+            capacity,
+            follow: false,
+            format,
+            inputs: inputs.into_iter(),
+            index: 0,
+        }
+    }
+
+    /// Creates a source reading inputs sequentially, following them.
+    ///
+    /// Inputs are polled for new data rather than ending, so only the
+    /// first input will ever be read from.
+    pub fn following(inputs: Vec<String>, format: Format, capacity: usize) -> Sources {
+        Sources::Sequential {
+            capacity,
+            follow: true,
+            format,
+            inputs: inputs.into_iter(),
+            index: 0,
+        }
+    }
+
+    /// Creates a source reading inputs on a pool of worker threads.
+    ///
+    /// When ordered, records are yielded exactly as they would be when
+    /// reading sequentially; otherwise batches are yielded as soon as
+    /// they have been read, regardless of the input they belong to.
+    ///
+    /// When a filter is provided, workers detect whether each record is
+    /// unique as it's read. As workers race to detect entries which are
+    /// repeated across inputs, any of the inputs may hold the occurrence
+    /// which is detected as unique, so this is only suitable when the
+    /// records are yielded unordered.
+    pub fn parallel(
+        inputs: Vec<String>,
+        format: Format,
+        capacity: usize,
+        threads: usize,
+        ordered: bool,
+        filter: Option<Arc<ConcurrentFilter>>,
+    ) -> Sources {
+        let workers = threads.min(inputs.len());
+        let inputs = Arc::new(inputs);
+        let next = Arc::new(AtomicUsize::new(0));
+
+        if ordered {
+            let (senders, receivers): (Vec<_>, Vec<_>) = (0..inputs.len())
+                .map(|_| mpsc::sync_channel(BATCH_BOUND))
+                .unzip();
+
+            let senders = Arc::new(senders);
+
+            for _ in 0..workers {
+                let (inputs, format, next) = (inputs.clone(), format.clone(), next.clone());
+                let (senders, filter) = (senders.clone(), filter.clone());
+
+                thread::spawn(move || {
+                    work(
+                        &inputs,
+                        &format,
+                        capacity,
+                        filter.as_deref(),
+                        &next,
+                        |index, message| senders[index].send(message).is_ok(),
+                    )
+                });
+            }
+
+            return Sources::Ordered {
+                receivers: receivers.into_iter(),
+                index: 0,
+            };
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(BATCH_BOUND * workers);
+
+        for _ in 0..workers {
+            let (inputs, format, next) = (inputs.clone(), format.clone(), next.clone());
+            let (sender, filter): (SyncSender<_>, _) = (sender.clone(), filter.clone());
+
+            thread::spawn(move || {
+                work(
+                    &inputs,
+                    &format,
+                    capacity,
+                    filter.as_deref(),
+                    &next,
+                    |index, message| sender.send((index, message)).is_ok(),
+                )
+            });
+        }
+
+        Sources::Unordered { receiver }
+    }
+}
+
+/// Implement `Iterator` to yield the records of each input.
+impl Iterator for Sources {
+    type Item = (usize, io::Result<Box<dyn Records + Send>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Sources::Sequential {
+                capacity,
+                follow,
+                format,
+                inputs,
+                index,
+            } => {
+                let name = inputs.next()?;
+                let records = match follow {
+                    true => input::follow(&name).map(|reader| format.records(reader, *capacity)),
+                    false => format.open(&name, *capacity),
+                };
+
+                *index += 1;
+                Some((*index - 1, records))
+            }
+
+            Sources::Ordered { receivers, index } => {
+                let receiver = receivers.next()?;
+
+                *index += 1;
+
+                let records = match receiver.recv() {
+                    Ok(Message::Opened(Ok(()))) => {
+                        let stream = Stream::new(Batch::new(0), Some(receiver));
+                        Ok(Box::new(stream) as Box<dyn Records + Send>)
+                    }
+                    Ok(Message::Opened(Err(err))) => Err(err),
+                    _ => Err(io::Error::other("input worker failed")),
+                };
+
+                Some((*index - 1, records))
+            }
+
+            Sources::Unordered { receiver } => loop {
+                match receiver.recv().ok()? {
+                    (index, Message::Batch(batch)) => {
+                        return Some((index, Ok(Box::new(Stream::new(batch, None)))));
+                    }
+                    (index, Message::Opened(Err(err))) => {
+                        return Some((index, Err(err)));
+                    }
+                    (index, Message::Failed(err)) => {
+                        let mut stream = Stream::new(Batch::new(0), None);
+                        stream.failure = Some(err);
+                        return Some((index, Ok(Box::new(stream))));
+                    }
+                    _ => continue,
+                }
+            },
+        }
+    }
+}
+
+/// Message enum to represent events sent by workers.
+pub enum Message {
+    /// Sent once an input has been opened (or failed to open).
+    Opened(io::Result<()>),
+
+    /// Sent for each batch of records read from an input.
+    Batch(Batch),
+
+    /// Sent when reading from an input fails.
+    Failed(io::Error),
+
+    /// Sent once an input has been read completely.
+    Done,
+}
+
+/// Batch of records read from an input.
+///
+/// Records are stored back to back in a single buffer, alongside the
+/// end position of each record and the input offset after the record.
+/// Whether each record is unique is also stored, when detected.
+pub struct Batch {
+    data: Vec<u8>,
+    records: Vec<(usize, u64)>,
+    uniques: Vec<bool>,
+    offset: u64,
+}
+
+impl Batch {
+    /// Creates a new `Batch` starting at the provided offset.
+    fn new(offset: u64) -> Batch {
+        Batch {
+            data: Vec::new(),
+            records: Vec::new(),
+            uniques: Vec::new(),
+            offset,
+        }
+    }
+
+    /// Determines whether the batch is large enough to be sent.
+    fn is_full(&self) -> bool {
+        self.data.len() + self.records.len() >= BATCH_SIZE
+    }
+}
+
+/// Stream of records received from a worker.
+///
+/// A stream without a receiver will only yield the records of its
+/// initial batch (and any failure), which is how unordered batches
+/// are yielded.
+struct Stream {
+    batch: Batch,
+    failure: Option<io::Error>,
+    receiver: Option<Receiver<Message>>,
+    position: usize,
+    start: usize,
+}
+
+impl Stream {
+    /// Creates a new `Stream` from an initial batch.
+    fn new(batch: Batch, receiver: Option<Receiver<Message>>) -> Stream {
+        Stream {
+            batch,
+            failure: None,
+            receiver,
+            position: 0,
+            start: 0,
+        }
+    }
+}
+
+/// Implement `Records` for streams of batches.
+impl Records for Stream {
+    fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        Ok(self.next_detected()?.map(|(record, _)| record))
+    }
+
+    fn next_detected(&mut self) -> io::Result<Option<(&[u8], Option<bool>)>> {
+        while self.position == self.batch.records.len() {
+            if let Some(err) = self.failure.take() {
+                return Err(err);
+            }
+
+            let receiver = match self.receiver {
+                Some(ref receiver) => receiver,
+                None => return Ok(None),
+            };
+
+            match receiver.recv() {
+                Ok(Message::Batch(batch)) => {
+                    self.batch = batch;
+                    self.position = 0;
+                    self.start = 0;
+                }
+                Ok(Message::Failed(err)) => return Err(err),
+                Ok(Message::Opened(_)) => continue,
+                Ok(Message::Done) => return Ok(None),
+                Err(_) => return Err(io::Error::other("input worker failed")),
+            }
+        }
+
+        let (end, offset) = self.batch.records[self.position];
+        let record = &self.batch.data[self.start..end];
+        let unique = self.batch.uniques.get(self.position).copied();
+
+        self.batch.offset = offset;
+        self.position += 1;
+        self.start = end;
+
+        Ok(Some((record, unique)))
+    }
+
+    #[inline]
+    fn offset(&self) -> u64 {
+        self.batch.offset
+    }
+}
+
+/// Reads inputs on a worker thread until no inputs remain.
+///
+/// Inputs are claimed in order via a shared counter, and messages are
+/// passed to the provided function until it signals that the receiving
+/// side has gone away. Records are detected against the filter, if any.
+fn work<F>(
+    inputs: &[String],
+    format: &Format,
+    capacity: usize,
+    filter: Option<&ConcurrentFilter>,
+    next: &AtomicUsize,
+    send: F,
+) where
+    F: Fn(usize, Message) -> bool,
+{
+    let textual = format.is_textual();
+
+    loop {
+        let index = next.fetch_add(1, atomic::Ordering::SeqCst);
+
+        if index >= inputs.len() {
+            return;
+        }
+
+        let mut records = match format.open(&inputs[index], capacity) {
+            Ok(records) => records,
+            Err(err) => {
+                if !send(index, Message::Opened(Err(err))) {
+                    return;
+                }
+                continue;
+            }
+        };
+
+        if !send(index, Message::Opened(Ok(()))) {
+            return;
+        }
+
+        let mut batch = Batch::new(0);
+
+        // read records into batches, sending each batch once full
+        let failure = loop {
+            let offset = records.offset();
+
+            match records.next_record() {
+                Ok(Some(record)) => {
+                    // detect uniques without any byte order mark
+                    if let Some(filter) = filter {
+                        let key = match textual && offset == 0 {
+                            true => input::unmark(record),
+                            false => record,
+                        };
+                        batch.uniques.push(filter.detect(key));
+                    }
+                    batch.data.extend_from_slice(record)
+                }
+                Ok(None) => break None,
+                Err(err) => break Some(err),
+            }
+
+            batch.records.push((batch.data.len(), records.offset()));
+
+            if batch.is_full() {
+                let full = mem::replace(&mut batch, Batch::new(records.offset()));
+                if !send(index, Message::Batch(full)) {
+                    return;
+                }
+            }
+        };
+
+        // flush the remaining batch, followed by the end of the input
+        if !batch.records.is_empty() && !send(index, Message::Batch(batch)) {
+            return;
+        }
+
+        let message = match failure {
+            Some(err) => Message::Failed(err),
+            None => Message::Done,
+        };
+
+        if !send(index, message) {
+            return;
+        }
+    }
+}
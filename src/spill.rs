@@ -0,0 +1,304 @@
+//! Spill module used to filter uniques within a memory budget.
+//!
+//! Hashes of entries are held in memory until the budget is reached,
+//! at which point they're sorted and written to a new run of hashes in
+//! a temporary file. Runs are merged in tiers (much like a log structured
+//! merge tree), so each hash is only rewritten a logarithmic number of
+//! times. Any hash missing from memory is then searched for within every
+//! run, so filtering remains exact regardless of the number of distinct
+//! entries.
+use identity_hash::BuildIdentityHasher;
+use xxhash_rust::xxh3::xxh3_64_with_seed;
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+/// Estimated bytes of memory required for each hash held in memory.
+///
+/// This accounts for the overhead of the set used to hold hashes, as
+/// well as the space required to sort hashes when spilling them.
+const BYTES_PER_HASH: u64 = 32;
+
+/// Number of hashes in each block of a run, read at once when searching.
+const BLOCK_HASHES: usize = 512;
+
+/// Number of runs within a tier which are merged into the next tier.
+const MERGE_FANOUT: usize = 4;
+
+/// Spill struct to detect uniques within a memory budget.
+#[derive(Debug)]
+pub struct Spill {
+    memory: HashSet<u64, BuildIdentityHasher<u64>>,
+    limit: usize,
+    runs: Vec<Run>,
+    seed: u64,
+}
+
+impl Spill {
+    /// Creates a new `Spill` with a budget (in bytes) of memory.
+    pub fn new(budget: u64, seed: u64) -> Spill {
+        Spill {
+            memory: HashSet::default(),
+            limit: (budget / BYTES_PER_HASH).max(1) as usize,
+            runs: Vec::new(),
+            seed,
+        }
+    }
+
+    /// Detects a unique key, spilling to disk when over budget.
+    ///
+    /// Errors are returned when spilling hashes to disk, or searching the
+    /// runs on disk, fails.
+    pub fn detect(&mut self, key: &[u8]) -> io::Result<bool> {
+        let hash = xxh3_64_with_seed(key, self.seed);
+
+        if self.memory.contains(&hash) {
+            return Ok(false);
+        }
+
+        for run in &self.runs {
+            if run.contains(hash)? {
+                return Ok(false);
+            }
+        }
+
+        self.memory.insert(hash);
+
+        if self.memory.len() >= self.limit {
+            self.spill()?;
+        }
+
+        Ok(true)
+    }
+
+    /// Spills all hashes in memory into a new run on disk.
+    ///
+    /// Once a tier holds enough runs, they're merged into a single run in
+    /// the next tier, which may in turn cause that tier to be merged.
+    fn spill(&mut self) -> io::Result<()> {
+        let mut hashes = self.memory.drain().collect::<Vec<_>>();
+
+        hashes.sort_unstable();
+
+        let mut writer = RunWriter::new()?;
+        for hash in hashes {
+            writer.push(hash)?;
+        }
+        self.runs.push(writer.finish(0)?);
+
+        // tiers only grow at the end, so all runs of a tier are adjacent
+        while let Some(tier) = self.runs.last().map(|run| run.tier) {
+            let count = self
+                .runs
+                .iter()
+                .rev()
+                .take_while(|run| run.tier == tier)
+                .count();
+
+            if count < MERGE_FANOUT {
+                break;
+            }
+
+            let runs = self.runs.split_off(self.runs.len() - count);
+            self.runs.push(merge(&runs, tier + 1)?);
+        }
+
+        Ok(())
+    }
+}
+
+/// Sorted run of hashes stored in a temporary file.
+///
+/// The first hash of each block is kept in memory, so that searching
+/// the run only ever requires reading a single block from the file.
+#[derive(Debug)]
+struct Run {
+    file: File,
+    fences: Vec<u64>,
+    len: usize,
+    tier: u32,
+}
+
+impl Run {
+    /// Checks whether the run contains a hash.
+    fn contains(&self, hash: u64) -> io::Result<bool> {
+        // locate the only block which could contain the hash
+        let block = match self.fences.partition_point(|fence| *fence <= hash) {
+            0 => return Ok(false),
+            block => block - 1,
+        };
+
+        let start = block * BLOCK_HASHES;
+        let count = BLOCK_HASHES.min(self.len - start);
+
+        let mut buffer = [0; BLOCK_HASHES * 8];
+        let buffer = &mut buffer[..count * 8];
+        let mut file = &self.file;
+
+        file.seek(SeekFrom::Start(start as u64 * 8))?;
+        file.read_exact(buffer)?;
+
+        Ok(search(buffer, hash))
+    }
+
+    /// Creates a buffered reader over all hashes in the run, in order.
+    fn reader(&self) -> io::Result<RunReader<'_>> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(0))?;
+
+        Ok(RunReader {
+            reader: BufReader::new(file),
+            remaining: self.len,
+        })
+    }
+}
+
+/// Buffered reader over the hashes of a run.
+struct RunReader<'a> {
+    reader: BufReader<&'a File>,
+    remaining: usize,
+}
+
+impl RunReader<'_> {
+    /// Reads the next hash of the run, if any remain.
+    fn next(&mut self) -> io::Result<Option<u64>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        let mut hash = [0; 8];
+        self.reader.read_exact(&mut hash)?;
+        self.remaining -= 1;
+
+        Ok(Some(u64::from_ne_bytes(hash)))
+    }
+}
+
+/// Buffered writer of sorted hashes into a new run.
+struct RunWriter {
+    writer: BufWriter<File>,
+    fences: Vec<u64>,
+    len: usize,
+}
+
+impl RunWriter {
+    /// Creates a new `RunWriter` backed by an anonymous temporary file.
+    fn new() -> io::Result<RunWriter> {
+        Ok(RunWriter {
+            writer: BufWriter::new(tempfile::tempfile()?),
+            fences: Vec::new(),
+            len: 0,
+        })
+    }
+
+    /// Writes the next hash, which must be larger than all previous hashes.
+    fn push(&mut self, hash: u64) -> io::Result<()> {
+        if self.len.is_multiple_of(BLOCK_HASHES) {
+            self.fences.push(hash);
+        }
+        self.writer.write_all(&hash.to_ne_bytes())?;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Flushes all hashes written, creating a run within a tier.
+    fn finish(self, tier: u32) -> io::Result<Run> {
+        Ok(Run {
+            file: self.writer.into_inner().map_err(|err| err.into_error())?,
+            fences: self.fences,
+            len: self.len,
+            tier,
+        })
+    }
+}
+
+/// Merges a set of runs (which never share a hash) into a new run.
+fn merge(runs: &[Run], tier: u32) -> io::Result<Run> {
+    let mut readers = runs
+        .iter()
+        .map(Run::reader)
+        .collect::<io::Result<Vec<_>>>()?;
+    let mut heads = readers
+        .iter_mut()
+        .map(RunReader::next)
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let mut writer = RunWriter::new()?;
+
+    // repeatedly take the smallest head, as there are only a few runs
+    while let Some((hash, index)) = heads
+        .iter()
+        .enumerate()
+        .filter_map(|(index, head)| head.map(|hash| (hash, index)))
+        .min()
+    {
+        writer.push(hash)?;
+        heads[index] = readers[index].next()?;
+    }
+
+    writer.finish(tier)
+}
+
+/// Searches a sorted run for a hash.
+fn search(run: &[u8], hash: u64) -> bool {
+    let read = |index: usize| u64::from_ne_bytes(run[index * 8..][..8].try_into().unwrap());
+    let (mut low, mut high) = (0, run.len() / 8);
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match read(mid).cmp(&hash) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => return true,
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Spill, MERGE_FANOUT};
+
+    #[test]
+    fn spilling_to_disk() {
+        let mut spill = Spill::new(64, 0);
+
+        for value in 0..100 {
+            assert!(spill.detect(value.to_string().as_bytes()).unwrap());
+            assert!(!spill.detect(value.to_string().as_bytes()).unwrap());
+        }
+
+        assert!(spill.memory.is_empty());
+        assert_eq!(spill.runs.iter().map(|run| run.len).sum::<usize>(), 100);
+
+        for value in 0..100 {
+            assert!(!spill.detect(value.to_string().as_bytes()).unwrap());
+        }
+    }
+
+    #[test]
+    fn merging_runs_in_tiers() {
+        let mut spill = Spill::new(32 * 100, 0);
+
+        for value in 0..10_000 {
+            assert!(spill.detect(value.to_string().as_bytes()).unwrap());
+        }
+
+        // 100 spills leave at most a few runs within each tier
+        assert!(spill.runs.len() < MERGE_FANOUT * 4);
+        assert!(spill
+            .runs
+            .windows(2)
+            .all(|runs| runs[0].tier >= runs[1].tier));
+
+        for value in 0..10_000 {
+            assert!(!spill.detect(value.to_string().as_bytes()).unwrap());
+        }
+
+        assert!(spill.detect(b"missing").unwrap());
+    }
+}
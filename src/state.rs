@@ -0,0 +1,110 @@
+//! State module used to persist filters between invocations.
+//!
+//! State files begin with a small header naming the filter which wrote
+//! them, so that state is never loaded into a different kind of filter.
+//! State is written via a staged file, so a failed run will never leave
+//! behind a partially written state file.
+use crate::output::Staged;
+use runiq::{Filter, Filters};
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Magic bytes used to identify (and version) state files.
+const MAGIC: &[u8; 8] = b"RUNIQST\x01";
+
+/// Loads the state of a filter from a path, if the path exists.
+pub fn load(path: &str, kind: Filters, filter: &mut dyn Filter) -> io::Result<()> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(cannot("open", path, err)),
+    };
+
+    let mut reader = BufReader::new(file);
+    let mut header = [0; 8];
+
+    reader
+        .read_exact(&mut header)
+        .map_err(|err| cannot("load", path, err))?;
+
+    if &header != MAGIC {
+        let message = format!("cannot load '{}': not a state file", path);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+
+    let mut name = [0; 1];
+    reader
+        .read_exact(&mut name)
+        .map_err(|err| cannot("load", path, err))?;
+
+    if name[0] != kind as u8 {
+        let message = format!("cannot load '{}': saved by a different filter", path);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+
+    filter
+        .load_state(&mut reader)
+        .map_err(|err| cannot("load", path, err))
+}
+
+/// Saves the state of a filter to a path, replacing any existing state.
+pub fn save(path: &str, kind: Filters, filter: &dyn Filter) -> io::Result<()> {
+    let staged = Staged::new(Path::new(path));
+    let file = File::create(staged.temp()).map_err(|err| cannot("create", path, err))?;
+    let mut writer = BufWriter::new(file);
+
+    writer
+        .write_all(MAGIC)
+        .and_then(|_| writer.write_all(&[kind as u8]))
+        .and_then(|_| filter.save_state(&mut writer))
+        .and_then(|_| writer.into_inner().map_err(|err| err.into_error()))
+        .and_then(|file| file.sync_all())
+        .map_err(|err| cannot("save", path, err))?;
+
+    staged.persist()
+}
+
+/// Creates an error for an operation on a state file.
+fn cannot(action: &str, path: &str, err: io::Error) -> io::Error {
+    let message = format!("cannot {} '{}': {}", action, path, err);
+    io::Error::new(err.kind(), message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, save};
+    use runiq::{Filter, Filters, QuickFilter};
+
+    use std::fs;
+
+    #[test]
+    fn fixed_filter_numbering() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state");
+        let path = path.to_str().unwrap();
+
+        let kinds = [
+            (Filters::Quick, 0),
+            (Filters::Simple, 1),
+            (Filters::Sorted, 2),
+            (Filters::Compact, 3),
+            (Filters::Disk, 4),
+            (Filters::Cuckoo, 5),
+            (Filters::Quick128, 7),
+        ];
+
+        for (kind, number) in kinds.iter() {
+            let mut filter = QuickFilter::default();
+            filter.detect(b"input");
+
+            save(path, *kind, &filter).unwrap();
+            assert_eq!(fs::read(path).unwrap()[8], *number);
+
+            let mut loaded = QuickFilter::default();
+            load(path, *kind, &mut loaded).unwrap();
+            assert!(loaded.contains(b"input"));
+        }
+    }
+}
@@ -1,110 +1,286 @@
 //! Statistics module for use when tracking unique rates.
 //!
-//! Very little is exposed from this module aside from the `Stats`
-//! struct which contains tracking based on unique counters.
+//! The `Stats` struct itself lives in the library, so this module only
+//! covers the rendering of statistics, and the breakdown per input.
 
 use cli_table::format::{Border, Justify, Separator};
-use cli_table::{print_stdout, Cell, Row, RowStruct, Table};
+use cli_table::{print_stderr, print_stdout, Cell, Row, RowStruct, Style, Table, TableStruct};
 use format_num::NumberFormat;
+use identity_hash::BuildIdentityHasher;
+use runiq::Stats;
 
-/// Statistics struct to store metrics.
-///
-/// Currently only provides the following:
-///
-/// - Total number of input entries
-/// - Total number of unique entries
-/// - Total number of duplicate entries
-/// - Rate (as a %) of duplicate entries
-///
-/// More might be added in future, but for now these are the only
-/// metrics surfaced on the `Stats` API.
-#[derive(Debug, Default)]
-pub struct Stats {
-    unique: u64,
-    total: u64,
-    size: u64,
-}
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
 
-impl Stats {
-    /// Creates a new `Stats` container using default values.
-    pub fn new() -> Stats {
-        Stats::default()
-    }
+/// Prints all statistics as a table to stdout, or stderr if requested.
+pub fn print(stats: &Stats, stderr: bool) {
+    let num = NumberFormat::new();
+    let mut table = vec![
+        create_row(
+            &num,
+            "File Size:",
+            stats.size() as f64,
+            ",.0",
+            &format!("\x08(~{})", bytesize::to_string(stats.size(), false)),
+        ),
+        create_row(&num, "Total Count:", stats.total() as f64, ",.0", ""),
+        create_row(&num, "Unique Count:", stats.uniques() as f64, ",.0", ""),
+        create_row(&num, "Dup Offset:", stats.duplicates() as f64, ",.0", ""),
+        create_row(
+            &num,
+            "Dup Rate:",
+            ((100.0 - stats.rate()) / 100.0) as f64,
+            ",.2%",
+            "",
+        ),
+    ];
 
-    /// Adds a unique entry to the stats count.
-    #[inline]
-    pub fn add_unique(&mut self) {
-        self.total += 1;
-        self.unique += 1;
-    }
+    // attach the time taken, and the throughput over that time
+    table.push(create_row(
+        &num,
+        "Elapsed Time:",
+        stats.elapsed().as_secs_f64(),
+        ",.3",
+        "seconds",
+    ));
+    table.push(create_row(
+        &num,
+        "Entry Rate:",
+        stats.entries_per_second(),
+        ",.0",
+        "entries/s",
+    ));
+    table.push(create_row(
+        &num,
+        "Byte Rate:",
+        stats.megabytes_per_second(),
+        ",.2",
+        "MB/s",
+    ));
 
-    /// Adds a duplicate entry to the stats count.
-    #[inline]
-    pub fn add_duplicate(&mut self) {
-        self.total += 1;
+    // attach the memory used, where it's known
+    let memory = [
+        ("Filter Memory:", stats.memory()),
+        ("Peak Memory:", peak_memory()),
+    ];
+
+    for (label, bytes) in memory.iter() {
+        if let Some(bytes) = bytes {
+            table.push(create_row(
+                &num,
+                label,
+                *bytes as f64,
+                ",.0",
+                &format!("\x08(~{})", bytesize::to_string(*bytes, false)),
+            ));
+        }
     }
 
-    /// Adds a size entry to the stats count.
-    #[inline]
-    pub fn add_size(&mut self, size: usize) {
-        self.size += size as u64
+    // attach a row for each bucket of the occurrence histogram
+    for (lower, upper, entries) in stats.histogram() {
+        let label = if lower == upper {
+            format!("Seen {}:", num.format(",.0", lower as f64))
+        } else {
+            format!(
+                "Seen {}-{}:",
+                num.format(",.0", lower as f64),
+                num.format(",.0", upper as f64)
+            )
+        };
+        table.push(create_row(&num, &label, entries as f64, ",.0", ""));
     }
 
-    /// Retrieves the total count of duplicate entries.
-    pub fn duplicates(&self) -> u64 {
-        self.total - self.unique
+    let table = table
+        .table()
+        .border(Border::builder().build())
+        .separator(Separator::builder().build());
+
+    emit(table, stderr)
+}
+
+/// Writes all statistics as a delimited header and row of values.
+pub fn write_delimited<W: Write>(stats: &Stats, mut writer: W, delimiter: char) -> io::Result<()> {
+    let values = [
+        ("size", stats.size().to_string()),
+        ("total", stats.total().to_string()),
+        ("uniques", stats.uniques().to_string()),
+        ("duplicates", stats.duplicates().to_string()),
+        (
+            "duplicate_rate",
+            format!("{:.4}", (100.0 - stats.rate()) / 100.0),
+        ),
+        (
+            "elapsed_seconds",
+            format!("{:.3}", stats.elapsed().as_secs_f64()),
+        ),
+        (
+            "entries_per_second",
+            format!("{:.0}", stats.entries_per_second()),
+        ),
+        (
+            "megabytes_per_second",
+            format!("{:.2}", stats.megabytes_per_second()),
+        ),
+        ("filter_memory", optional(stats.memory())),
+        ("peak_memory", optional(peak_memory())),
+    ];
+
+    let delimiter = delimiter.to_string();
+    let header = values.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    let row = values
+        .iter()
+        .map(|(_, value)| &value[..])
+        .collect::<Vec<_>>();
+
+    writeln!(writer, "{}", header.join(&delimiter))?;
+    writeln!(writer, "{}", row.join(&delimiter))
+}
+
+/// Formats an optional value, using an empty string when missing.
+fn optional(value: Option<u64>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Breakdown of statistics across each input.
+///
+/// Alongside the statistics of each input, this tracks the input in
+/// which each unique entry was first seen, in order to count entries
+/// which are duplicates of an entry found in another input.
+#[derive(Debug)]
+pub struct Breakdown {
+    inputs: Vec<(String, Stats)>,
+    origins: HashMap<u64, usize, BuildIdentityHasher<u64>>,
+    cross: u64,
+}
+
+impl Breakdown {
+    /// Creates a new `Breakdown` for the provided input names.
+    pub fn new(names: &[String]) -> Breakdown {
+        Breakdown {
+            inputs: names
+                .iter()
+                .map(|name| (name.clone(), Stats::new()))
+                .collect(),
+            origins: HashMap::default(),
+            cross: 0,
+        }
     }
 
-    /// Retrieves the rate of receiving duplicates.
-    pub fn rate(&self) -> f32 {
-        ((self.unique as f64 / self.total as f64) * 100.0) as f32
+    /// Adds a unique entry (via the hash of the entry) to an input.
+    #[inline]
+    pub fn add_unique(&mut self, index: usize, hash: u64) {
+        self.inputs[index].1.add_unique();
+        self.origins.insert(hash, index);
     }
 
-    /// Retrieves the total size of input entries.
-    pub fn size(&self) -> u64 {
-        self.size
+    /// Adds a duplicate entry (via the hash of the entry) to an input.
+    #[inline]
+    pub fn add_duplicate(&mut self, index: usize, hash: u64) {
+        self.inputs[index].1.add_duplicate();
+        if self
+            .origins
+            .get(&hash)
+            .is_some_and(|origin| *origin != index)
+        {
+            self.cross += 1;
+        }
     }
 
-    /// Retrieves the total count of input entries.
-    pub fn total(&self) -> u64 {
-        self.total
+    /// Adds a size entry to an input.
+    #[inline]
+    pub fn add_size(&mut self, index: usize, size: usize) {
+        self.inputs[index].1.add_size(size);
     }
 
-    /// Retrieves the total count of unique entries.
-    pub fn uniques(&self) -> u64 {
-        self.unique
+    /// Retrieves the count of duplicates first seen in another input.
+    pub fn cross_duplicates(&self) -> u64 {
+        self.cross
     }
 
-    /// Prints all statistics to stdout.
-    pub fn print(&self) {
+    /// Prints the statistics of all inputs to stdout, or stderr if requested.
+    pub fn print(&self, stderr: bool) {
         let num = NumberFormat::new();
-        let table = vec![
-            create_row(
-                &num,
-                "File Size:",
-                self.size() as f64,
-                ",.0",
-                &format!("\x08(~{})", bytesize::to_string(self.size(), false)),
-            ),
-            create_row(&num, "Total Count:", self.total() as f64, ",.0", ""),
-            create_row(&num, "Unique Count:", self.uniques() as f64, ",.0", ""),
-            create_row(&num, "Dup Offset:", self.duplicates() as f64, ",.0", ""),
-            create_row(
-                &num,
-                "Dup Rate:",
-                ((100.0 - self.rate()) / 100.0) as f64,
-                ",.2%",
-                "",
-            ),
-        ]
+        let table = self
+            .inputs
+            .iter()
+            .map(|(name, stats)| {
+                let rate = if stats.total() == 0 {
+                    0.0
+                } else {
+                    stats.duplicates() as f64 / stats.total() as f64
+                };
+                vec![
+                    name.cell(),
+                    num.format(",.0", stats.total() as f64)
+                        .cell()
+                        .justify(Justify::Right),
+                    num.format(",.0", stats.uniques() as f64)
+                        .cell()
+                        .justify(Justify::Right),
+                    num.format(",.0", stats.duplicates() as f64)
+                        .cell()
+                        .justify(Justify::Right),
+                    num.format(",.2%", rate).cell().justify(Justify::Right),
+                ]
+            })
+            .table()
+            .title(vec![
+                "Input".cell().bold(true),
+                "Total".cell().bold(true).justify(Justify::Right),
+                "Unique".cell().bold(true).justify(Justify::Right),
+                "Dups".cell().bold(true).justify(Justify::Right),
+                "Dup Rate".cell().bold(true).justify(Justify::Right),
+            ])
+            .border(Border::builder().build())
+            .separator(Separator::builder().build());
+
+        newline(stderr);
+        emit(table, stderr);
+        newline(stderr);
+
+        let cross = vec![create_row(
+            &num,
+            "Cross-Input Dups:",
+            self.cross_duplicates() as f64,
+            ",.0",
+            "",
+        )]
         .table()
         .border(Border::builder().build())
         .separator(Separator::builder().build());
 
-        print_stdout(table).expect("unable to print stats table")
+        emit(cross, stderr)
+    }
+}
+
+/// Prints a table to stdout, or stderr if requested.
+fn emit(table: TableStruct, stderr: bool) {
+    let result = if stderr {
+        print_stderr(table)
+    } else {
+        print_stdout(table)
+    };
+    result.expect("unable to print stats table")
+}
+
+/// Prints an empty line to stdout, or stderr if requested.
+fn newline(stderr: bool) {
+    if stderr {
+        eprintln!();
+    } else {
+        println!();
     }
 }
 
+/// Retrieves the peak resident memory of the process, where available.
+fn peak_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kilobytes * 1024)
+}
+
 /// Constructs a table row using a label and value.
 fn create_row(num: &NumberFormat, label: &str, value: f64, fmt: &str, ext: &str) -> RowStruct {
     vec![
@@ -117,57 +293,44 @@ fn create_row(num: &NumberFormat, label: &str, value: f64, fmt: &str, ext: &str)
 
 #[cfg(test)]
 mod tests {
-    use super::Stats;
-
-    #[test]
-    fn default_creation() {
-        let stats = Stats::new();
-
-        assert_eq!(stats.total(), 0);
-        assert_eq!(stats.uniques(), 0);
-        assert_eq!(stats.duplicates(), 0);
-    }
+    use super::{write_delimited, Breakdown};
+    use runiq::Stats;
 
     #[test]
-    fn addition_of_uniques() {
-        let mut stats = Stats::new();
+    fn breakdown_of_inputs() {
+        let names = vec!["a.txt".to_string(), "b.txt".to_string()];
+        let mut breakdown = Breakdown::new(&names);
 
-        stats.add_unique();
-        stats.add_unique();
-        stats.add_unique();
+        breakdown.add_unique(0, 1);
+        breakdown.add_unique(0, 2);
+        breakdown.add_duplicate(0, 1);
+        breakdown.add_duplicate(1, 2);
+        breakdown.add_unique(1, 3);
+        breakdown.add_duplicate(1, 3);
 
-        assert_eq!(stats.total(), 3);
-        assert_eq!(stats.uniques(), 3);
-        assert_eq!(stats.duplicates(), 0);
+        assert_eq!(breakdown.inputs[0].1.total(), 3);
+        assert_eq!(breakdown.inputs[0].1.uniques(), 2);
+        assert_eq!(breakdown.inputs[1].1.total(), 3);
+        assert_eq!(breakdown.inputs[1].1.uniques(), 1);
+        assert_eq!(breakdown.inputs[1].1.duplicates(), 2);
+        assert_eq!(breakdown.cross_duplicates(), 1);
     }
 
     #[test]
-    fn addition_of_duplicates() {
+    fn delimited_output() {
         let mut stats = Stats::new();
+        let mut output = Vec::new();
 
+        stats.add_unique();
         stats.add_duplicate();
-        stats.add_duplicate();
-        stats.add_duplicate();
-
-        assert_eq!(stats.total(), 3);
-        assert_eq!(stats.uniques(), 0);
-        assert_eq!(stats.duplicates(), 3);
-    }
-
-    #[test]
-    fn generate_of_rates() {
-        let mut stats = Stats::new();
+        stats.add_size(4);
+        write_delimited(&stats, &mut output, ',').unwrap();
 
-        stats.add_duplicate();
-        stats.add_duplicate();
-        stats.add_duplicate();
-        stats.add_unique();
-        stats.add_unique();
-        stats.add_unique();
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
 
-        assert_eq!(stats.total(), 6);
-        assert_eq!(stats.uniques(), 3);
-        assert_eq!(stats.duplicates(), 3);
-        assert_eq!(stats.rate() as u16, 50);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("size,total,uniques,duplicates,duplicate_rate,"));
+        assert!(lines[1].starts_with("4,2,1,1,0.5000,"));
     }
 }
@@ -0,0 +1,256 @@
+//! Statistics module for use when tracking unique rates.
+//!
+//! Very little is exposed from this module aside from the `Stats`
+//! struct which contains tracking based on unique counters.
+use identity_hash::BuildIdentityHasher;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Statistics struct to store metrics.
+///
+/// Currently only provides the following:
+///
+/// - Total number of input entries
+/// - Total number of unique entries
+/// - Total number of duplicate entries
+/// - Rate (as a %) of duplicate entries
+/// - Histogram of the number of occurrences of each entry
+/// - Approximate memory used by the filter
+/// - Elapsed time, and throughput of entries and bytes
+///
+/// More might be added in future, but for now these are the only
+/// metrics surfaced on the `Stats` API.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    unique: u64,
+    total: u64,
+    size: u64,
+    occurrences: HashMap<u64, u64, BuildIdentityHasher<u64>>,
+    memory: Option<u64>,
+    elapsed: Duration,
+}
+
+impl Stats {
+    /// Creates a new `Stats` container using default values.
+    pub fn new() -> Stats {
+        Stats::default()
+    }
+
+    /// Adds a unique entry to the stats count.
+    #[inline]
+    pub fn add_unique(&mut self) {
+        self.total += 1;
+        self.unique += 1;
+    }
+
+    /// Adds a duplicate entry to the stats count.
+    #[inline]
+    pub fn add_duplicate(&mut self) {
+        self.total += 1;
+    }
+
+    /// Adds a size entry to the stats count.
+    #[inline]
+    pub fn add_size(&mut self, size: usize) {
+        self.size += size as u64
+    }
+
+    /// Adds an occurrence of an entry (via its hash) to the stats count.
+    #[inline]
+    pub fn add_occurrence(&mut self, hash: u64) {
+        *self.occurrences.entry(hash).or_insert(0) += 1;
+    }
+
+    /// Retrieves a histogram of the number of occurrences of entries.
+    ///
+    /// Buckets are returned as an inclusive range of occurrences along
+    /// with the number of entries within the range, growing by a factor
+    /// of 10 (i.e. 1, 2-10, 11-100, etc.) up to the largest occurrence.
+    pub fn histogram(&self) -> Vec<(u64, u64, u64)> {
+        let mut buckets: Vec<(u64, u64, u64)> = Vec::new();
+
+        for count in self.occurrences.values() {
+            let mut index = 0;
+            let mut upper = 1;
+
+            while *count > upper {
+                index += 1;
+                upper = upper.saturating_mul(10);
+            }
+
+            while buckets.len() <= index {
+                let upper = 10u64.saturating_pow(buckets.len() as u32);
+                let lower = if upper == 1 { 1 } else { upper / 10 + 1 };
+                buckets.push((lower, upper, 0));
+            }
+
+            buckets[index].2 += 1;
+        }
+
+        buckets
+    }
+
+    /// Sets the approximate memory used by the filter, if known.
+    pub fn set_memory(&mut self, memory: Option<u64>) {
+        self.memory = memory;
+    }
+
+    /// Sets the time elapsed while filtering entries.
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+
+    /// Retrieves the time elapsed while filtering entries.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Retrieves the number of entries filtered per second.
+    pub fn entries_per_second(&self) -> f64 {
+        per_second(self.total as f64, self.elapsed)
+    }
+
+    /// Retrieves the number of megabytes filtered per second.
+    pub fn megabytes_per_second(&self) -> f64 {
+        per_second(self.size as f64 / 1_000_000.0, self.elapsed)
+    }
+
+    /// Retrieves the total count of duplicate entries.
+    pub fn duplicates(&self) -> u64 {
+        self.total - self.unique
+    }
+
+    /// Retrieves the rate of receiving duplicates.
+    pub fn rate(&self) -> f32 {
+        ((self.unique as f64 / self.total as f64) * 100.0) as f32
+    }
+
+    /// Retrieves the approximate memory used by the filter, if known.
+    pub fn memory(&self) -> Option<u64> {
+        self.memory
+    }
+
+    /// Retrieves the total size of input entries.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Retrieves the total count of input entries.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Retrieves the total count of unique entries.
+    pub fn uniques(&self) -> u64 {
+        self.unique
+    }
+}
+
+/// Divides an amount by a duration in seconds, or zero if no time passed.
+fn per_second(amount: f64, elapsed: Duration) -> f64 {
+    let seconds = elapsed.as_secs_f64();
+    if seconds == 0.0 {
+        return 0.0;
+    }
+    amount / seconds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stats;
+    use std::time::Duration;
+
+    #[test]
+    fn default_creation() {
+        let stats = Stats::new();
+
+        assert_eq!(stats.total(), 0);
+        assert_eq!(stats.uniques(), 0);
+        assert_eq!(stats.duplicates(), 0);
+    }
+
+    #[test]
+    fn addition_of_uniques() {
+        let mut stats = Stats::new();
+
+        stats.add_unique();
+        stats.add_unique();
+        stats.add_unique();
+
+        assert_eq!(stats.total(), 3);
+        assert_eq!(stats.uniques(), 3);
+        assert_eq!(stats.duplicates(), 0);
+    }
+
+    #[test]
+    fn addition_of_duplicates() {
+        let mut stats = Stats::new();
+
+        stats.add_duplicate();
+        stats.add_duplicate();
+        stats.add_duplicate();
+
+        assert_eq!(stats.total(), 3);
+        assert_eq!(stats.uniques(), 0);
+        assert_eq!(stats.duplicates(), 3);
+    }
+
+    #[test]
+    fn generate_of_rates() {
+        let mut stats = Stats::new();
+
+        stats.add_duplicate();
+        stats.add_duplicate();
+        stats.add_duplicate();
+        stats.add_unique();
+        stats.add_unique();
+        stats.add_unique();
+
+        assert_eq!(stats.total(), 6);
+        assert_eq!(stats.uniques(), 3);
+        assert_eq!(stats.duplicates(), 3);
+        assert_eq!(stats.rate() as u16, 50);
+    }
+
+    #[test]
+    fn generate_of_throughput() {
+        let mut stats = Stats::new();
+
+        assert_eq!(stats.entries_per_second(), 0.0);
+
+        stats.add_unique();
+        stats.add_unique();
+        stats.add_size(4_000_000);
+        stats.set_elapsed(Duration::from_secs(2));
+
+        assert_eq!(stats.entries_per_second(), 1.0);
+        assert_eq!(stats.megabytes_per_second(), 2.0);
+    }
+
+    #[test]
+    fn tracking_memory() {
+        let mut stats = Stats::new();
+
+        assert_eq!(stats.memory(), None);
+
+        stats.set_memory(Some(1024));
+
+        assert_eq!(stats.memory(), Some(1024));
+    }
+
+    #[test]
+    fn histogram_of_occurrences() {
+        let mut stats = Stats::new();
+
+        stats.add_occurrence(1);
+        stats.add_occurrence(2);
+        stats.add_occurrence(2);
+
+        for _ in 0..11 {
+            stats.add_occurrence(3);
+        }
+
+        assert_eq!(stats.histogram(), vec![(1, 1, 1), (2, 10, 1), (11, 100, 1)]);
+    }
+}
@@ -0,0 +1,149 @@
+//! Store module used to filter values via an embedded database.
+use crate::filters::Filter;
+
+use sled::{Db, Tree};
+use xxhash_rust::xxh3::xxh3_64_with_seed;
+
+use std::io;
+use std::path::Path;
+
+/// Exact filter implementation backed by an embedded database.
+///
+/// This filter stores hashes in the same way as the `QuickFilter`, but
+/// in a `sled` database within a directory. Unlike the `DiskFilter`, the
+/// database is kept when the filter is dropped, so opening the same
+/// directory again continues filtering from where it left off.
+///
+/// As detection cannot fail, any I/O error raised when accessing the
+/// database will cause a panic; `try_detect` can be used to handle these
+/// errors instead. Writes are only flushed to disk when the filter is
+/// dropped; `flush` can be used to flush at any other time.
+#[derive(Debug)]
+pub struct StoreFilter {
+    db: Db,
+    hashes: Tree,
+    seed: u64,
+}
+
+impl StoreFilter {
+    /// Opens a `StoreFilter` within a directory, creating it if needed.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        Self::open_with_seed(dir, 0)
+    }
+
+    /// Opens a `StoreFilter` within a directory using a custom hash seed.
+    ///
+    /// Hashes are only comparable when using the same seed, so this will
+    /// return an error if the store was created with a different seed.
+    pub fn open_with_seed<P: AsRef<Path>>(dir: P, seed: u64) -> io::Result<Self> {
+        // writes are flushed on drop, so there's no need for the
+        // background flusher (which can outlive the store's lock)
+        let db = sled::Config::new().path(dir).flush_every_ms(None).open()?;
+        Self::with_db(db, seed)
+    }
+
+    /// Creates a `StoreFilter` within an opened database.
+    fn with_db(db: Db, seed: u64) -> io::Result<Self> {
+        let hashes = db.open_tree("hashes")?;
+
+        match db.get("seed")? {
+            Some(existing) if existing != seed.to_le_bytes() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "store was created with a different seed",
+                ));
+            }
+            Some(_) => (),
+            None => {
+                db.insert("seed", &seed.to_le_bytes())?;
+            }
+        }
+
+        Ok(Self { db, hashes, seed })
+    }
+
+    /// Flushes all pending writes to disk.
+    pub fn flush(&self) -> io::Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Detects a unique value, returning any I/O errors raised.
+    pub fn try_detect(&mut self, input: &[u8]) -> io::Result<bool> {
+        let hash = xxh3_64_with_seed(input, self.seed);
+        Ok(self.hashes.insert(hash.to_be_bytes(), &[])?.is_none())
+    }
+
+    /// Checks whether a value has been seen, returning any I/O errors raised.
+    pub fn try_contains(&self, input: &[u8]) -> io::Result<bool> {
+        let hash = xxh3_64_with_seed(input, self.seed);
+        Ok(self.hashes.contains_key(hash.to_be_bytes())?)
+    }
+
+    /// Removes all values from the filter, returning any I/O errors raised.
+    pub fn try_clear(&mut self) -> io::Result<()> {
+        self.hashes.clear()?;
+        Ok(())
+    }
+}
+
+/// Implement all trait methods.
+impl Filter for StoreFilter {
+    fn detect(&mut self, input: &[u8]) -> bool {
+        self.try_detect(input)
+            .expect("unable to access store filter")
+    }
+
+    fn try_detect(&mut self, input: &[u8]) -> io::Result<bool> {
+        StoreFilter::try_detect(self, input)
+    }
+
+    fn contains(&self, input: &[u8]) -> bool {
+        self.try_contains(input)
+            .expect("unable to access store filter")
+    }
+
+    fn clear(&mut self) {
+        self.try_clear().expect("unable to access store filter")
+    }
+
+    /// Counting requires a scan of the store, so this is linear in time.
+    fn len(&self) -> u64 {
+        self.hashes.len() as u64
+    }
+}
+
+/// Implement `Drop` to flush any pending writes.
+impl Drop for StoreFilter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_filter_detection() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+
+        {
+            let mut filter = StoreFilter::with_db(db.clone(), 0).unwrap();
+
+            assert!(filter.detect(b"input1"));
+            assert!(!filter.detect(b"input1"));
+            assert!(filter.detect(b"input2"));
+            assert_eq!(filter.len(), 2);
+        }
+
+        let mut filter = StoreFilter::with_db(db.clone(), 0).unwrap();
+
+        assert!(!filter.detect(b"input1"));
+        assert!(filter.contains(b"input2"));
+        assert!(filter.detect(b"input3"));
+
+        let err = StoreFilter::with_db(db, 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
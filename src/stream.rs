@@ -0,0 +1,83 @@
+//! Stream module used to filter unique lines from async readers.
+use crate::filters::Filter;
+
+use std::io;
+use std::mem;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// Async adapter yielding unique lines from an `AsyncBufRead`.
+///
+/// Lines are yielded without their trailing line terminator, and are
+/// checked against a `Filter` before being returned. Filtering itself
+/// never blocks, so this is safe to use directly within async tasks.
+#[derive(Debug)]
+pub struct UniqueLines<R, F> {
+    reader: R,
+    filter: F,
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncBufRead + Unpin, F: Filter> UniqueLines<R, F> {
+    /// Creates a new `UniqueLines` around a reader and filter.
+    pub fn new(reader: R, filter: F) -> Self {
+        Self {
+            reader,
+            filter,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Retrieves the next unique line from the reader.
+    ///
+    /// This returns `None` once the reader has been exhausted.
+    pub async fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            self.buffer.clear();
+
+            if self.reader.read_until(b'\n', &mut self.buffer).await? == 0 {
+                return Ok(None);
+            }
+
+            if self.buffer.ends_with(b"\n") {
+                self.buffer.pop();
+                if self.buffer.ends_with(b"\r") {
+                    self.buffer.pop();
+                }
+            }
+
+            if self.filter.detect(&self.buffer) {
+                return Ok(Some(mem::take(&mut self.buffer)));
+            }
+        }
+    }
+
+    /// Retrieves a reference to the internal filter.
+    pub fn as_filter(&self) -> &F {
+        &self.filter
+    }
+
+    /// Consumes the adapter, returning the internal filter.
+    pub fn into_filter(self) -> F {
+        self.filter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::{Filter, QuickFilter};
+
+    #[tokio::test]
+    async fn unique_lines() {
+        let input: &[u8] = b"a\nb\r\na\nc\nb\nd";
+        let mut lines = UniqueLines::new(input, QuickFilter::default());
+        let mut uniques = Vec::new();
+
+        while let Some(line) = lines.next_line().await.unwrap() {
+            uniques.push(line);
+        }
+
+        assert_eq!(uniques, vec![b"a", b"b", b"c", b"d"]);
+        assert_eq!(lines.as_filter().len(), 4);
+    }
+}
@@ -0,0 +1,91 @@
+//! JavaScript bindings for use of filters from WebAssembly.
+use crate::filters::{Filter, Filters};
+
+use wasm_bindgen::prelude::*;
+
+/// JavaScript wrapper around a boxed `Filter`.
+///
+/// Filters are created by name, using the same names as the CLI. The
+/// `disk` filter is unavailable, as there is no file system to use.
+#[wasm_bindgen(js_name = Filter)]
+pub struct WasmFilter {
+    inner: Box<dyn Filter>,
+}
+
+#[wasm_bindgen(js_class = Filter)]
+impl WasmFilter {
+    /// Creates a new filter from the name of a filter kind.
+    #[wasm_bindgen(constructor)]
+    pub fn new(name: &str) -> Result<WasmFilter, JsError> {
+        let kind = match Filters::from_name(name) {
+            Some(Filters::Disk) | None => {
+                return Err(JsError::new(&format!("unsupported filter '{}'", name)));
+            }
+            Some(kind) => kind,
+        };
+
+        let inner = kind
+            .try_into_filter()
+            .map_err(|err| JsError::new(&err.to_string()))?;
+
+        Ok(Self { inner })
+    }
+
+    /// Detects if an input is unique, returning `true` if so.
+    pub fn detect(&mut self, input: &str) -> bool {
+        self.inner.detect(input.as_bytes())
+    }
+
+    /// Checks if an input has been seen, without recording it.
+    pub fn contains(&self, input: &str) -> bool {
+        self.inner.contains(input.as_bytes())
+    }
+
+    /// Clears all inputs recorded by the filter.
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    /// Retrieves the number of unique inputs recorded.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.inner.len() as usize
+    }
+
+    /// Filters the unique lines from a block of text.
+    ///
+    /// Lines are split on `\n` (removing any trailing `\r`), and the
+    /// unique lines are joined back together with `\n`.
+    #[wasm_bindgen(js_name = uniqueLines)]
+    pub fn unique_lines(&mut self, text: &str) -> String {
+        unique_lines_via(&mut *self.inner, text)
+    }
+}
+
+/// Filters the unique lines from a block of text using a filter name.
+#[wasm_bindgen(js_name = uniqueLines)]
+pub fn unique_lines(text: &str, name: &str) -> Result<String, JsError> {
+    WasmFilter::new(name).map(|mut filter| filter.unique_lines(text))
+}
+
+/// Filters the unique lines from a block of text through a filter.
+fn unique_lines_via(filter: &mut dyn Filter, text: &str) -> String {
+    text.lines()
+        .filter(|line| filter.detect(line.as_bytes()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::QuickFilter;
+
+    #[test]
+    fn unique_lines_via_filter() {
+        let mut filter = QuickFilter::default();
+        let output = unique_lines_via(&mut filter, "a\r\nb\na\nc\nb\n");
+
+        assert_eq!(output, "a\nb\nc");
+    }
+}
@@ -0,0 +1,153 @@
+//! Writer module used to filter unique lines written to a `Write`.
+use crate::filters::Filter;
+
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, Write};
+
+/// Writer adapter which only forwards unique lines to an inner writer.
+///
+/// Bytes are buffered until a line is terminated, at which point the line
+/// is checked against a `Filter` and written if it has not been seen. Any
+/// unterminated line is written when the writer is finished or dropped.
+pub struct UniqueWriter<W: Write, F: Filter> {
+    inner: Option<W>,
+    filter: F,
+    pending: Vec<u8>,
+}
+
+impl<W: Write, F: Filter> UniqueWriter<W, F> {
+    /// Creates a new `UniqueWriter` around a writer and filter.
+    pub fn new(inner: W, filter: F) -> Self {
+        Self {
+            inner: Some(inner),
+            filter,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Retrieves a reference to the internal filter.
+    pub fn as_filter(&self) -> &F {
+        &self.filter
+    }
+
+    /// Retrieves a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().expect("writer must exist")
+    }
+
+    /// Consumes the writer, returning the inner writer.
+    ///
+    /// Any unterminated line is written before the writer is returned.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.finish()?;
+        Ok(self.inner.take().expect("writer must exist"))
+    }
+
+    /// Writes any unterminated line to the inner writer.
+    fn finish(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let line = std::mem::take(&mut self.pending);
+        self.forward(&line, &line)?;
+        self.flush()
+    }
+
+    /// Forwards a line to the inner writer, if the key is unique.
+    fn forward(&mut self, key: &[u8], line: &[u8]) -> io::Result<()> {
+        if !self.filter.detect(key) {
+            return Ok(());
+        }
+        self.inner
+            .as_mut()
+            .expect("writer must exist")
+            .write_all(line)
+    }
+}
+
+/// Implement `Write` to forward unique lines.
+impl<W: Write, F: Filter> Write for UniqueWriter<W, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut remaining = buf;
+
+        while let Some(offset) = remaining.iter().position(|byte| *byte == b'\n') {
+            let (line, rest) = remaining.split_at(offset + 1);
+            remaining = rest;
+
+            if self.pending.is_empty() {
+                self.forward(&line[..offset], line)?;
+                continue;
+            }
+
+            // join with the start of the line from a previous write
+            let mut joined = std::mem::take(&mut self.pending);
+            joined.extend_from_slice(line);
+            self.forward(&joined[..joined.len() - 1], &joined)?;
+            joined.clear();
+            self.pending = joined;
+        }
+
+        self.pending.extend_from_slice(remaining);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().expect("writer must exist").flush()
+    }
+}
+
+/// Implement `Drop` to write any unterminated line.
+impl<W: Write, F: Filter> Drop for UniqueWriter<W, F> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.finish();
+        }
+    }
+}
+
+/// Implement `Debug` without requiring the filter to implement it.
+impl<W: Write + Debug, F: Filter> Debug for UniqueWriter<W, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UniqueWriter")
+            .field("inner", &self.inner)
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::{Filter, QuickFilter};
+
+    #[test]
+    fn unique_writing() {
+        let mut writer = UniqueWriter::new(Vec::new(), QuickFilter::default());
+
+        writer.write_all(b"a\nb\na").unwrap();
+        writer.write_all(b"\nc\nb\n").unwrap();
+        writer.write_all(b"d").unwrap();
+
+        assert_eq!(writer.get_ref(), b"a\nb\nc\n");
+        assert_eq!(writer.as_filter().len(), 3);
+
+        let output = writer.into_inner().unwrap();
+
+        assert_eq!(output, b"a\nb\nc\nd");
+    }
+
+    #[test]
+    fn unique_writing_on_drop() {
+        let mut output = Vec::new();
+
+        {
+            let mut writer = UniqueWriter::new(&mut output, QuickFilter::default());
+            writeln!(writer, "a").unwrap();
+            write!(writer, "a").unwrap();
+        }
+
+        assert_eq!(output, b"a\n");
+    }
+}
@@ -0,0 +1,224 @@
+//! Xor module used to build immutable filters from known values.
+use xxhash_rust::xxh3::xxh3_64;
+
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
+
+/// Magic bytes used to identify (and version) serialized filters.
+const MAGIC: &[u8; 8] = b"RUNIQXF\x01";
+
+/// Immutable filter built from a known set of values.
+///
+/// This is an implementation of a Xor Filter, which is built once from a
+/// set of values and can only check membership afterwards. Fingerprints
+/// are 32 bits, so roughly 5 bytes are required per value, with a false
+/// positive rate of roughly `2e-10`; there are no false negatives.
+///
+/// As the filter cannot be changed once built, it does not implement the
+/// `Filter` trait. It's intended to be built ahead of time (such as from
+/// a list of values to deny), written via `save`, and read via `load`.
+#[derive(Clone, Debug)]
+pub struct XorFilter {
+    fingerprints: Vec<u32>,
+    seed: u64,
+}
+
+impl XorFilter {
+    /// Builds a new `XorFilter` from a set of values.
+    ///
+    /// Duplicate values are ignored, so the values do not need to be unique.
+    pub fn build<I, V>(values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: AsRef<[u8]>,
+    {
+        let hashes = values
+            .into_iter()
+            .map(|value| xxh3_64(value.as_ref()))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let block = (hashes.len() as f64 * 1.23 / 3.0).ceil() as usize + 11;
+
+        // construction can fail for a given seed, so retry until it doesn't
+        for seed in 0.. {
+            if let Some(fingerprints) = construct(&hashes, block, seed) {
+                return Self { fingerprints, seed };
+            }
+        }
+
+        unreachable!("construction must succeed for some seed")
+    }
+
+    /// Checks whether a value was in the set used to build the filter.
+    pub fn contains(&self, input: &[u8]) -> bool {
+        let hash = mix(xxh3_64(input), self.seed);
+        let block = self.fingerprints.len() / 3;
+        let [a, b, c] = slots(hash, block);
+
+        fingerprint(hash) == self.fingerprints[a] ^ self.fingerprints[b] ^ self.fingerprints[c]
+    }
+
+    /// Writes the filter to a writer, to be read via `load`.
+    pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&self.seed.to_le_bytes())?;
+        writer.write_all(&(self.fingerprints.len() as u64).to_le_bytes())?;
+        for fingerprint in &self.fingerprints {
+            writer.write_all(&fingerprint.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// Reads a filter from a reader, as written via `save`.
+    pub fn load<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+
+        if &header != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a filter file",
+            ));
+        }
+
+        let mut word = [0; 8];
+        reader.read_exact(&mut word)?;
+        let seed = u64::from_le_bytes(word);
+        reader.read_exact(&mut word)?;
+        let len = u64::from_le_bytes(word);
+
+        if len == 0 || len % 3 != 0 {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+
+        let mut bytes = Vec::new();
+        reader.take(len * 4).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len * 4 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let fingerprints = bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+
+        Ok(Self { fingerprints, seed })
+    }
+}
+
+/// Attempts to construct the fingerprints for a set of unique hashes.
+fn construct(hashes: &[u64], block: usize, seed: u64) -> Option<Vec<u32>> {
+    let capacity = block * 3;
+    let mut counts = vec![0u32; capacity];
+    let mut masks = vec![0u64; capacity];
+
+    for hash in hashes {
+        let hash = mix(*hash, seed);
+        for slot in slots(hash, block).iter() {
+            counts[*slot] += 1;
+            masks[*slot] ^= hash;
+        }
+    }
+
+    // peel slots containing a single hash until none remain
+    let mut queue = (0..capacity)
+        .filter(|slot| counts[*slot] == 1)
+        .collect::<Vec<_>>();
+    let mut stack = Vec::with_capacity(hashes.len());
+
+    while let Some(slot) = queue.pop() {
+        if counts[slot] != 1 {
+            continue;
+        }
+
+        let hash = masks[slot];
+        stack.push((hash, slot));
+
+        for other in slots(hash, block).iter() {
+            counts[*other] -= 1;
+            masks[*other] ^= hash;
+            if counts[*other] == 1 {
+                queue.push(*other);
+            }
+        }
+    }
+
+    if stack.len() != hashes.len() {
+        return None;
+    }
+
+    // assign in reverse order, so each slot is only written once
+    let mut fingerprints = vec![0u32; capacity];
+
+    for (hash, slot) in stack.into_iter().rev() {
+        let [a, b, c] = slots(hash, block);
+        fingerprints[slot] =
+            fingerprint(hash) ^ fingerprints[a] ^ fingerprints[b] ^ fingerprints[c];
+    }
+
+    Some(fingerprints)
+}
+
+/// Mixes a hash with a seed, to give a new hash per construction attempt.
+fn mix(hash: u64, seed: u64) -> u64 {
+    let mut hash = hash.wrapping_add(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    hash = (hash ^ (hash >> 33)).wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash = (hash ^ (hash >> 33)).wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+/// Retrieves the fingerprint of a hash.
+fn fingerprint(hash: u64) -> u32 {
+    (hash ^ (hash >> 32)) as u32
+}
+
+/// Retrieves the three slots (one per block) used by a hash.
+fn slots(hash: u64, block: usize) -> [usize; 3] {
+    let reduce = |hash: u64| ((hash as u32 as u64 * block as u64) >> 32) as usize;
+    [
+        reduce(hash),
+        reduce(hash.rotate_left(21)) + block,
+        reduce(hash.rotate_left(42)) + block * 2,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xor_filter_membership() {
+        let values = (0..10_000)
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>();
+        let filter = XorFilter::build(values.iter().chain(values.iter()));
+
+        for value in &values {
+            assert!(filter.contains(value.as_bytes()));
+        }
+
+        let misses = (10_000..110_000)
+            .filter(|value| filter.contains(value.to_string().as_bytes()))
+            .count();
+
+        assert!(misses < 5, "{} false positives", misses);
+
+        let mut bytes = Vec::new();
+        filter.save(&mut bytes).unwrap();
+
+        let loaded = XorFilter::load(&bytes[..]).unwrap();
+
+        assert!(loaded.contains(b"9999"));
+        assert!(XorFilter::load(&bytes[..20]).is_err());
+        assert!(XorFilter::load(&b"RUNIQST\x01"[..]).is_err());
+    }
+
+    #[test]
+    fn xor_filter_empty() {
+        let filter = XorFilter::build(Vec::<&[u8]>::new());
+
+        assert!(!filter.contains(b"input1"));
+    }
+}
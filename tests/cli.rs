@@ -0,0 +1,1759 @@
+use flate2::read::GzDecoder;
+
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+/// Creates a command for the CLI, isolated from any user configuration.
+fn command() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_runiq"));
+
+    for (name, _) in env::vars_os() {
+        if name.to_string_lossy().starts_with("RUNIQ_") {
+            command.env_remove(name);
+        }
+    }
+
+    // configuration is looked up within an empty directory
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("config");
+    fs::create_dir_all(&dir).unwrap();
+
+    command.env("XDG_CONFIG_HOME", dir);
+    command
+}
+
+/// Executes the CLI with the provided arguments and stdin.
+fn runiq(args: &[&str], input: &[u8]) -> Output {
+    let mut child = command()
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // the process may exit before reading all input
+    let _ = child.stdin.take().unwrap().write_all(input);
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn gzip_shard_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let pattern = dir.path().join("out-{}.gz");
+
+    let input = b"a\nb\nc\na\nd\nb\ne\nf\nc\n";
+    let output = runiq(
+        &["--shard-output", pattern.to_str().unwrap(), "--shards", "2"],
+        input,
+    );
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let mut lines = Vec::new();
+
+    for idx in 0..2 {
+        let path = dir.path().join(format!("out-{}.gz", idx));
+        let mut decoded = String::new();
+
+        GzDecoder::new(File::open(path).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+
+        lines.extend(decoded.lines().map(str::to_owned));
+    }
+
+    let expected = ["a", "b", "c", "d", "e", "f"];
+    let uniques = lines.iter().map(String::as_str).collect::<HashSet<_>>();
+
+    assert_eq!(lines.len(), expected.len());
+    assert_eq!(uniques, expected.iter().copied().collect());
+}
+
+#[test]
+fn file_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out.txt");
+    let path = path.to_str().unwrap();
+
+    let output = runiq(&["-o", path], b"a\nb\na\n");
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(fs::read(path).unwrap(), b"a\nb\n");
+
+    let output = runiq(&["--output", path, "--append"], b"c\nc\n");
+
+    assert!(output.status.success());
+    assert_eq!(fs::read(path).unwrap(), b"a\nb\nc\n");
+
+    let output = runiq(&["-o", path, "missing.txt"], b"");
+
+    assert!(!output.status.success());
+    assert_eq!(fs::read(path).unwrap(), b"a\nb\nc\n");
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn in_place_filtering() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.txt");
+    let path = path.to_str().unwrap();
+
+    fs::write(path, b"a\nb\na\n").unwrap();
+
+    let output = runiq(&["--in-place", path], b"");
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(fs::read(path).unwrap(), b"a\nb\n");
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    fs::write(path, b"c\nc\n").unwrap();
+
+    let output = runiq(&["--in-place", "--backup", ".bak", path], b"");
+
+    assert!(output.status.success());
+    assert_eq!(fs::read(path).unwrap(), b"c\n");
+    assert_eq!(fs::read(format!("{}.bak", path)).unwrap(), b"c\nc\n");
+
+    let output = runiq(&["--in-place", path, path], b"");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+#[cfg(feature = "net")]
+fn http_url_inputs() {
+    use std::io::BufRead;
+    use std::net::TcpListener;
+    use std::thread;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+        for (idx, stream) in listener.incoming().take(2).enumerate() {
+            let mut stream = stream.unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+
+            let response: &[u8] = match idx {
+                0 => b"HTTP/1.1 200 OK\r\nContent-Length: 8\r\n\r\na\nb\na\nc\n",
+                _ => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+            };
+
+            stream.write_all(response).unwrap();
+        }
+    });
+
+    let url = format!("http://{}/input.txt", address);
+    let output = runiq(&[&url], b"");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\nc\n");
+
+    let output = runiq(&[&url], b"");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        stderr.trim(),
+        format!("runiq: cannot open '{}': HTTP 404 Not Found", url)
+    );
+
+    server.join().unwrap();
+}
+
+#[test]
+#[cfg(feature = "s3")]
+fn s3_object_inputs() {
+    use std::io::BufRead;
+    use std::net::TcpListener;
+    use std::thread;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+        let mut stream = listener.incoming().next().unwrap().unwrap();
+        let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+        let mut request = String::new();
+
+        reader.read_line(&mut request).unwrap();
+
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            line.clear();
+        }
+
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\na\nb\na\n")
+            .unwrap();
+
+        request
+    });
+
+    let output = command()
+        .arg("s3://bucket/path/input.txt")
+        .env("AWS_ENDPOINT_URL", format!("http://{}", address))
+        .env("AWS_ACCESS_KEY_ID", "key")
+        .env("AWS_SECRET_ACCESS_KEY", "secret")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\n");
+
+    let request = server.join().unwrap();
+
+    assert!(request.starts_with("GET /bucket/path/input.txt?"));
+    assert!(request.contains("X-Amz-Signature="));
+}
+
+#[test]
+fn expanded_inputs() {
+    let dir = tempfile::tempdir().unwrap();
+
+    fs::create_dir_all(dir.path().join("nested/deeper")).unwrap();
+    fs::write(dir.path().join("b.log"), b"b\nc\n").unwrap();
+    fs::write(dir.path().join("a.log"), b"a\nb\n").unwrap();
+    fs::write(dir.path().join("a.txt"), b"x\n").unwrap();
+    fs::write(dir.path().join("nested/c.log"), b"c\nd\n").unwrap();
+    fs::write(dir.path().join("nested/deeper/e.log"), b"e\n").unwrap();
+
+    let pattern = dir.path().join("*.log");
+    let output = runiq(&[pattern.to_str().unwrap()], b"");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\nc\n");
+
+    let root = dir.path().to_str().unwrap();
+    let output = runiq(&["--recursive", root], b"");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\nx\nc\nd\ne\n");
+
+    let output = runiq(&[root], b"");
+
+    assert!(!output.status.success());
+
+    let literal = dir.path().join("data[1].txt");
+
+    fs::write(&literal, b"literal\n").unwrap();
+    fs::write(dir.path().join("data1.txt"), b"matched\n").unwrap();
+
+    let output = runiq(&[literal.to_str().unwrap()], b"");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"literal\n");
+}
+
+#[test]
+fn parallel_inputs() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut paths = Vec::new();
+    let mut disjoint = Vec::new();
+
+    for idx in 0..8 {
+        let path = dir.path().join(format!("{}.txt", idx));
+        let lines = (0..20_000)
+            .map(|line| format!("{}\n", (line * (idx + 1)) % 25_000))
+            .collect::<String>();
+
+        fs::write(&path, lines).unwrap();
+        paths.push(path.to_str().unwrap().to_owned());
+
+        let path = dir.path().join(format!("disjoint-{}.txt", idx));
+        let lines = (0..20_000)
+            .map(|line| format!("{}-{}\n", idx, line % 5_000))
+            .collect::<String>();
+
+        fs::write(&path, lines).unwrap();
+        disjoint.push(path.to_str().unwrap().to_owned());
+    }
+
+    let lines = |output: &[u8]| {
+        let mut lines = output.split(|byte| *byte == b'\n').collect::<Vec<_>>();
+        lines.sort_unstable();
+        lines.iter().map(|line| line.to_vec()).collect::<Vec<_>>()
+    };
+
+    let inputs = paths.iter().map(String::as_str).collect::<Vec<_>>();
+    let sequential = runiq(&inputs, b"");
+
+    assert!(sequential.status.success());
+
+    let ordered = runiq(&[&["-j", "4"], &inputs[..]].concat(), b"");
+
+    assert!(ordered.status.success());
+    assert_eq!(ordered.stdout, sequential.stdout);
+
+    // entries repeated across inputs may be emitted from any input
+    let unordered = runiq(&[&["-j", "4", "--unordered"], &inputs[..]].concat(), b"");
+
+    assert!(unordered.status.success());
+    assert_eq!(lines(&unordered.stdout), lines(&sequential.stdout));
+
+    let path = dir.path().join("out.txt");
+    let path = path.to_str().unwrap();
+    let piped = runiq(&[&["-j", "2", "-o", path], &inputs[..]].concat(), b"");
+
+    assert!(piped.status.success());
+    assert_eq!(fs::read(path).unwrap(), sequential.stdout);
+
+    // entries are detected on the main thread when keys are transformed
+    let sequential = runiq(&[&["--ignore-case"], &inputs[..]].concat(), b"");
+    let ordered = runiq(&[&["-j", "4", "--ignore-case"], &inputs[..]].concat(), b"");
+
+    assert!(ordered.status.success());
+    assert_eq!(ordered.stdout, sequential.stdout);
+
+    // entries are emitted in the order of the inputs
+    let inputs = disjoint.iter().map(String::as_str).collect::<Vec<_>>();
+    let sequential = runiq(&inputs, b"");
+    let ordered = runiq(&[&["-j", "4"], &inputs[..]].concat(), b"");
+
+    assert!(ordered.status.success());
+    assert_eq!(ordered.stdout, sequential.stdout);
+
+    // entries repeated across inputs are emitted from the first input
+    let first = dir.path().join("first.txt");
+    let second = dir.path().join("second.txt");
+
+    fs::write(&first, b"x\ny\n").unwrap();
+    fs::write(&second, b"x\nz\n").unwrap();
+
+    for _ in 0..10 {
+        let output = runiq(
+            &["-j", "2", first.to_str().unwrap(), second.to_str().unwrap()],
+            b"",
+        );
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"x\ny\nz\n");
+    }
+
+    let output = runiq(&["-j", "2", &paths[0], "missing.txt"], b"");
+
+    assert!(!output.status.success());
+
+    let output = runiq(&["--unordered", &paths[0]], b"");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn progress_reporting() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.txt");
+    let path = path.to_str().unwrap();
+
+    fs::write(path, b"a\nb\na\nc\n").unwrap();
+
+    let output = runiq(&["--progress", path], b"");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\nc\n");
+    assert!(stderr.contains("100.0% 8 B / 8 B"));
+    assert!(stderr.contains("3 uniques"));
+
+    let output = runiq(&["--progress"], b"a\nb\na\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\n");
+    assert!(!stderr.contains('%'));
+    assert!(stderr.contains("2 uniques"));
+}
+
+#[test]
+fn periodic_statistics() {
+    let output = runiq(&["--stats-interval", "1h"], b"a\nb\na\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\n");
+    assert!(stderr.contains("3 lines | 2 uniques | 1 duplicates (33.33%)"));
+    assert_eq!(stderr.lines().count(), 1);
+}
+
+#[test]
+fn memory_bounded_filtering() {
+    let input = (0..1000)
+        .map(|value| format!("{}\n", value % 300))
+        .collect::<String>();
+
+    let expected = runiq(&[], input.as_bytes());
+    let output = runiq(&["--max-memory", "1KiB"], input.as_bytes());
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected.stdout);
+
+    let output = runiq(&["--max-memory", "1KiB", "-f", "simple"], b"");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn sized_buffers() {
+    let input = (0..1000)
+        .map(|value| format!("{}\n", value % 300))
+        .collect::<String>();
+
+    let expected = runiq(&[], input.as_bytes());
+    let output = runiq(&["--buffer-size", "16"], input.as_bytes());
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected.stdout);
+
+    let output = runiq(&["--buffer-size", "0"], b"");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn disk_filtering() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    let output = runiq(&["-f", "disk", "--disk-dir", path], b"a\nb\na\nc\nb\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\nc\n");
+    assert_eq!(fs::read_dir(path).unwrap().count(), 0);
+
+    let output = runiq(&["--disk-dir", path], b"a\n");
+
+    assert!(!output.status.success());
+
+    let missing = dir.path().join("missing");
+    let output = runiq(
+        &["-f", "disk", "--disk-dir", missing.to_str().unwrap()],
+        b"a\n",
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert!(stderr.starts_with("runiq: "));
+}
+
+#[test]
+fn persisted_filter_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("runiq.state");
+    let path = path.to_str().unwrap();
+
+    for filter in &["quick", "quick128", "simple", "compact", "cuckoo", "disk"] {
+        let _ = fs::remove_file(path);
+
+        let output = runiq(&["-f", filter, "--state", path], b"a\nb\na\n");
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"a\nb\n");
+
+        let output = runiq(&["-f", filter, "--state", path], b"b\nc\na\nd\n");
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"c\nd\n");
+    }
+
+    let output = runiq(&["-f", "simple", "--state", path], b"a\n");
+
+    assert!(!output.status.success());
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn followed_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.log");
+
+    fs::write(&path, b"a\nb\na\n").unwrap();
+
+    let mut child = command()
+        .args(["--follow", path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut lines = Vec::new();
+
+    for _ in 0..2 {
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        lines.push(line);
+    }
+
+    let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(b"c\nb\nd").unwrap();
+    file.write_all(b"\n").unwrap();
+
+    for _ in 0..2 {
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        lines.push(line);
+    }
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert_eq!(lines, vec!["a\n", "b\n", "c\n", "d\n"]);
+
+    let output = runiq(&["--follow", "-"], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn line_buffered_output() {
+    let mut child = command()
+        .args(["--line-buffered"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut lines = Vec::new();
+
+    for input in [&b"a\n"[..], b"a\nb\n"] {
+        stdin.write_all(input).unwrap();
+        stdin.flush().unwrap();
+
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        lines.push(line);
+    }
+
+    drop(stdin);
+    child.wait().unwrap();
+
+    assert_eq!(lines, vec!["a\n", "b\n"]);
+}
+
+#[test]
+fn byte_range_keys() {
+    let input = b"001 apple\n002 apple\n003 pear\n4\n5\n";
+
+    let output = runiq(&["--key-bytes", "5:9"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"001 apple\n003 pear\n4\n");
+
+    let output = runiq(&["--key-bytes", ":3"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, input);
+
+    let output = runiq(&["--key-bytes", "9:5"], input);
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn sequence_records() {
+    let input = b">a\nACGT\n>b\nAC\nGT\n>c\nTTTT\n";
+
+    let output = runiq(&["--format", "fasta"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b">a\nACGT\n>c\nTTTT\n");
+
+    let input = b"@a\nACGT\n+\nIIII\n@b\nACGT\n+\nJJJJ\n@c\nTTTT\n+\nIIII\n";
+
+    let output = runiq(&["--format", "fastq"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"@a\nACGT\n+\nIIII\n@c\nTTTT\n+\nIIII\n");
+
+    let output = runiq(&["--format", "fastq"], b"ACGT\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn hashed_entries() {
+    let output = runiq(&["--emit-hash=md5"], b"abc\nabc\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"900150983cd24fb0d6963f7d28e17f72\n");
+
+    let output = runiq(&["--emit-hash=sha256", "--with-entries"], b"abc\n");
+
+    assert!(output.status.success());
+    assert_eq!(
+        output.stdout,
+        &b"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\tabc\n"[..]
+    );
+
+    let output = runiq(&["--emit-hash"], b"abc\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout.len(), 17);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("file.txt");
+    let path = path.to_str().unwrap();
+
+    fs::write(path, b"abc\nabc\n").unwrap();
+
+    let output = runiq(&["--emit-hash", path], b"");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout.len(), 17);
+}
+
+#[test]
+fn csv_column_filtering() {
+    let dir = tempfile::tempdir().unwrap();
+    let path1 = dir.path().join("input1.csv");
+    let path2 = dir.path().join("input2.csv");
+    let path1 = path1.to_str().unwrap();
+    let path2 = path2.to_str().unwrap();
+
+    fs::write(path1, b"id,name\n1,\"a,b\"\n2,\"multi\nline\"\n3,\"a,b\"\n").unwrap();
+    fs::write(path2, b"id,name\n4,\"multi\nline\"\n5,c\n").unwrap();
+
+    let output = runiq(&["--csv", "--column", "name", path1, path2], b"");
+
+    assert!(output.status.success());
+    assert_eq!(
+        output.stdout,
+        b"id,name\n1,\"a,b\"\n2,\"multi\nline\"\n5,c\n"
+    );
+
+    let output = runiq(&["--csv", "--column", "2", "-j", "2", path1, path2], b"");
+
+    assert!(output.status.success());
+    assert_eq!(
+        output.stdout,
+        b"id,name\n1,\"a,b\"\n2,\"multi\nline\"\n5,c\n"
+    );
+
+    let output = runiq(&["--csv", "--column", "missing", path1], b"");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn jsonl_key_filtering() {
+    let input = concat!(
+        "{\"event_id\":1,\"data\":\"a\"}\n",
+        "{\"data\":\"b\",\"event_id\":2}\n",
+        "{\"event_id\":1,\"data\":\"c\"}\n",
+        "not json\n",
+        "{\"event_id\":3}\n",
+        "{\"event_id\":\"3\"}\n",
+        "{\"event_id\":\"\"}\n",
+        "{}\n",
+    );
+
+    let output = runiq(&["--jsonl", "--key", "event_id"], input.as_bytes());
+
+    assert!(!output.status.success());
+
+    let output = runiq(
+        &["--jsonl", "--key", "event_id", "--on-error", "skip"],
+        input.as_bytes(),
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        output.stdout,
+        concat!(
+            "{\"event_id\":1,\"data\":\"a\"}\n",
+            "{\"data\":\"b\",\"event_id\":2}\n",
+            "{\"event_id\":3}\n",
+            "{\"event_id\":\"3\"}\n",
+            "{\"event_id\":\"\"}\n",
+            "{}\n",
+        )
+        .as_bytes()
+    );
+}
+
+#[test]
+fn normalized_input() {
+    let input = "caf\u{e9}\ncafe\u{301}\n\u{fb01}\nfi\n";
+
+    let output = runiq(&["--normalize", "nfc"], input.as_bytes());
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, "caf\u{e9}\n\u{fb01}\nfi\n".as_bytes());
+
+    let output = runiq(&["--normalize", "NFKC"], input.as_bytes());
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, "caf\u{e9}\n\u{fb01}\n".as_bytes());
+}
+
+#[test]
+fn ansi_stripped_input() {
+    let input = b"\x1B[32mok\x1B[0m\nok\n\x1B[31mfail\x1B[0m\n";
+
+    let output = runiq(&["--strip-ansi"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"\x1B[32mok\x1B[0m\n\x1B[31mfail\x1B[0m\n");
+
+    let output = runiq(&["--strip-ansi-output"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"ok\nfail\n");
+}
+
+#[test]
+fn crlf_line_endings() {
+    let input = b"a\r\nb\na\nb\r\nc";
+
+    let output = runiq(&[], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\nc\n");
+
+    let output = runiq(&["--crlf"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\r\nb\r\nc\r\n");
+}
+
+#[test]
+fn byte_order_marks() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.txt");
+    let path = path.to_str().unwrap();
+
+    fs::write(path, b"\xEF\xBB\xBFb\na\n").unwrap();
+
+    let output = runiq(&["-", path], b"\xEF\xBB\xBFa\nb\nc\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\nc\n");
+
+    let output = runiq(&["--keep-bom", "-", path], b"\xEF\xBB\xBFa\nb\nc\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"\xEF\xBB\xBFa\nb\nc\n");
+}
+
+#[test]
+fn duplicate_exit_codes() {
+    let output = runiq(&["--exit-code"], b"a\nb\na\n");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"a\nb\n");
+
+    let output = runiq(&["--exit-code"], b"a\nb\nc\n");
+
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = runiq(&["--exit-code", "--min-count", "2"], b"a\nb\na\n");
+
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = runiq(&["--exit-code", "missing.txt"], b"");
+
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = runiq(&["missing.txt"], b"");
+
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn filter_benchmarking() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.txt");
+
+    fs::write(&path, "b\na\nb\nc\n").unwrap();
+
+    let output = runiq(&["bench", path.to_str().unwrap()], b"");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+
+    for filter in ["Simple", "Quick", "Compact", "Cuckoo", "Sorted", "Disk"] {
+        assert!(stdout.contains(filter), "missing {}", filter);
+    }
+
+    let output = runiq(&["bench", "--count", path.to_str().unwrap()], b"");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn seeded_hashing() {
+    for seed in ["1", "random", "RANDOM"] {
+        let output = runiq(&["--seed", seed], b"a\nb\na\n");
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"a\nb\n");
+    }
+
+    let output = runiq(&["--seed", "seed"], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn denied_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("deny.txt");
+    let filter = dir.path().join("deny.filter");
+
+    fs::write(&input, "b\nd\nb\n").unwrap();
+
+    let output = runiq(
+        &[
+            "build-filter",
+            "-o",
+            filter.to_str().unwrap(),
+            input.to_str().unwrap(),
+        ],
+        b"",
+    );
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = runiq(&["--deny", filter.to_str().unwrap()], b"a\nb\nc\nb\nd\na\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nc\n");
+
+    let output = runiq(&["--deny", input.to_str().unwrap()], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn capacity_hints() {
+    for filter in ["quick", "simple", "compact", "cuckoo", "sorted", "disk"] {
+        let output = runiq(&["-f", filter, "--capacity", "100000"], b"a\na\nb\nc\n");
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"a\nb\nc\n", "filter {}", filter);
+    }
+
+    let expected = runiq(&["--count"], b"a\nb\na\n");
+    let output = runiq(&["--count", "--capacity", "10"], b"a\nb\na\n");
+
+    assert_eq!(output.stdout, expected.stdout);
+}
+
+#[test]
+fn bloom_parameters() {
+    let args = [
+        "-f",
+        "compact",
+        "--error-rate",
+        "0.001",
+        "--growth-factor",
+        "4",
+        "--tightening-ratio",
+        "0.8",
+    ];
+    let output = runiq(&args, b"a\nb\na\nc\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\nc\n");
+
+    let output = runiq(&["-f", "quick", "--error-rate", "0.001"], b"a\n");
+
+    assert!(!output.status.success());
+
+    let output = runiq(&["-f", "compact", "--error-rate", "1.5"], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn windowed_filtering() {
+    let output = runiq(&["--window", "2"], b"a\nb\na\nc\nd\na\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\nc\nd\na\n");
+
+    let output = runiq(&["--window", "2", "-f", "simple"], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+#[cfg(feature = "store")]
+fn persistent_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    let output = runiq(&["--store", path], b"a\nb\na\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\n");
+
+    let output = runiq(&["--store", path], b"b\nc\na\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"c\n");
+
+    let output = runiq(&["--store", path, "--seed", "1"], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+#[cfg(feature = "plugin")]
+fn plugin_conflicts() {
+    let output = runiq(&["--plugin", "plugin.so", "--state", "state.bin"], b"a\n");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn recent_filtering() {
+    let output = runiq(&["--lru", "2"], b"a\nb\na\nc\na\nd\nb\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\nc\nd\nb\n");
+
+    let output = runiq(&["--lru", "2", "--window", "2"], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn expiring_filtering() {
+    let output = runiq(&["--ttl", "1h"], b"a\nb\na\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\n");
+
+    let output = runiq(&["--ttl", "5y"], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn ignored_patterns() {
+    let input = b"10:00 pid=1 started\n10:05 pid=2 started\n10:06 pid=3 stopped\n";
+    let args = [
+        "--ignore-pattern",
+        r"^\d{2}:\d{2}",
+        "--ignore-pattern",
+        r"pid=\d+",
+    ];
+    let output = runiq(&args, input);
+
+    assert!(output.status.success());
+    assert_eq!(
+        output.stdout,
+        b"10:00 pid=1 started\n10:06 pid=3 stopped\n".to_vec()
+    );
+
+    let output = runiq(&["--ignore-pattern", "("], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn sorted_output() {
+    let output = runiq(&["--sort"], b"c\na\nb\na\nc\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\nc\n");
+
+    let output = runiq(&["--sort-reverse"], b"c\na\nb\na\n");
+
+    assert_eq!(output.stdout, b"c\nb\na\n");
+
+    let output = runiq(&["--sort", "--numeric"], b"10\n9\nx\n100\n9\n");
+
+    assert_eq!(output.stdout, b"9\n10\n100\nx\n");
+
+    let output = runiq(&["--sort", "--invert"], b"b\na\nb\na\n");
+
+    assert_eq!(output.stdout, b"a\nb\n");
+}
+
+#[test]
+fn configured_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+
+    fs::write(&config, "ignore-case = true\nfilter = \"simple\"\n").unwrap();
+
+    let run = |args: &[&str], vars: &[(&str, &str)]| {
+        command()
+            .args(args)
+            .arg("-")
+            .env("RUNIQ_CONFIG", &config)
+            .envs(vars.iter().copied())
+            .stdin(File::open(dir.path().join("input.txt")).unwrap())
+            .output()
+            .unwrap()
+    };
+
+    fs::write(dir.path().join("input.txt"), "a\nA\nb\n").unwrap();
+
+    // configuration applies when nothing else is provided
+    assert_eq!(run(&[], &[]).stdout, b"a\nb\n");
+
+    // environment variables override the configuration
+    assert_eq!(
+        run(&[], &[("RUNIQ_IGNORE_CASE", "false")]).stdout,
+        b"a\nA\nb\n"
+    );
+
+    // flags override the environment
+    let output = run(&["--filter", "quick"], &[("RUNIQ_FILTER", "invalid")]);
+
+    assert!(output.status.success());
+
+    // flags with a default can be disabled explicitly
+    assert_eq!(run(&["--ignore-case=false"], &[]).stdout, b"a\nA\nb\n");
+
+    // defaults never conflict with flags provided by the user
+    let output = run(&["--statistics"], &[("RUNIQ_COUNT", "true")]);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Unique Count:"));
+
+    fs::write(&config, "unknown = 1\n").unwrap();
+
+    let output = run(&[], &[]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("unknown option 'unknown'"));
+}
+
+#[test]
+fn distinct_counting() {
+    let output = runiq(&["--count-distinct"], b"a\nb\na\nc\nb\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"3\n");
+
+    let output = runiq(&["--count-distinct"], b"");
+
+    assert_eq!(output.stdout, b"0\n");
+}
+
+#[test]
+fn distinct_estimation() {
+    let output = runiq(&["--estimate"], b"a\nb\na\nc\nb\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"3\n");
+
+    let output = runiq(&["--estimate", "--count-distinct"], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn per_input_statistics() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("first.txt");
+    let second = dir.path().join("second.txt");
+
+    fs::write(&first, b"a\nb\na\n").unwrap();
+    fs::write(&second, b"b\nc\nc\n").unwrap();
+
+    let inputs = [first.to_str().unwrap(), second.to_str().unwrap()];
+    let output = runiq(&["--statistics", inputs[0], inputs[1]], b"");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.contains("first.txt"));
+    assert!(stdout.contains("second.txt"));
+    assert!(stdout.contains("66.67%"));
+    assert!(stdout.contains("Cross-Input Dups:"));
+
+    let output = runiq(&["--statistics", inputs[0]], b"");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(!stdout.contains("Cross-Input Dups:"));
+}
+
+#[test]
+fn statistics_histogram() {
+    let output = runiq(&["--statistics"], b"a\nb\na\n");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(!stdout.contains("Seen"));
+
+    let output = runiq(&["--statistics", "--histogram"], b"a\nb\na\n");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.contains("Seen 1:"));
+    assert!(stdout.contains("Seen 2-10:"));
+
+    let output = runiq(&["--histogram"], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn delimited_statistics() {
+    let args = ["--statistics", "--statistics-format", "tsv"];
+    let output = runiq(&args, b"a\nb\na\n");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+
+    assert!(output.status.success());
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("size\ttotal\tuniques\tduplicates\t"));
+    assert!(lines[1].starts_with("6\t3\t2\t1\t"));
+
+    let output = runiq(&["--statistics-format", "csv"], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn statistics_to_stderr() {
+    let args = ["--stats-stderr", "--statistics-format", "csv"];
+    let output = runiq(&args, b"a\nb\na\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\n");
+    assert!(stderr.lines().nth(1).unwrap().starts_with("6,3,2,1,"));
+
+    let output = runiq(&["--stats-stderr", "--statistics"], b"a\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn top_entries() {
+    let output = runiq(&["--top", "2"], b"a\nb\nc\nb\nc\nc\nd\nd\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"      3 c\n      2 b\n");
+
+    let output = runiq(&["--top", "5", "--min-count", "2"], b"a\nb\nb\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"      2 b\n");
+}
+
+#[test]
+fn maximum_uniques() {
+    let output = runiq(&["--max-uniques", "2"], b"a\na\nb\nc\nd\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\n");
+
+    let output = runiq(&["--max-uniques", "5"], b"a\na\nb\n");
+
+    assert_eq!(output.stdout, b"a\nb\n");
+}
+
+#[test]
+fn quiet_output() {
+    let output = runiq(&["--quiet", "--exit-code"], b"a\nb\na\n");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    let output = runiq(&["-q", "--exit-code"], b"a\nb\nc\n");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn strict_unsorted_input() {
+    let input = b"b\na\nb\n";
+
+    let output = runiq(&["--filter", "sorted"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"b\na\nb\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("warning: input '-' is not sorted"));
+
+    let output = runiq(&["--filter", "sorted", "--strict"], input);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert_eq!(stderr.trim(), "runiq: input '-' is not sorted at line 2");
+}
+
+#[test]
+fn missing_input_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.txt");
+    let missing = dir.path().join("missing.txt");
+
+    std::fs::write(&path, b"a\nb\na\n").unwrap();
+
+    let path = path.to_str().unwrap();
+    let missing = missing.to_str().unwrap();
+
+    let output = runiq(&[path, missing], b"");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, b"a\nb\n");
+    assert!(stderr.starts_with(&format!("runiq: cannot open '{}'", missing)));
+
+    let output = runiq(&["--skip-missing", missing, path], b"");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\n");
+    assert!(stderr.starts_with(&format!("runiq: warning: cannot open '{}'", missing)));
+
+    let output = runiq(&["--continue-on-error", missing, path], b"");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\n");
+}
+
+#[test]
+fn occurrence_counts() {
+    let input = b"b\na\nb\nc\nb\na\n";
+
+    let output = runiq(&["-c"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"      3 b\n      2 a\n      1 c\n");
+
+    let output = runiq(&["-c", "-i"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"      3 b\n      2 a\n");
+
+    let output = runiq(&["-c", "--min-count", "3"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"      3 b\n");
+}
+
+#[test]
+fn frequency_table() {
+    let input = b"a\nb\nc\nb\na\nb\n";
+
+    let output = runiq(&["--frequency"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"3\tb\n2\ta\n1\tc\n");
+
+    let output = runiq(&["--frequency", "--top", "1"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"3\tb\n");
+}
+
+#[test]
+fn annotated_occurrences() {
+    let input = b"a\nb\na\na\nb\n";
+
+    let output = runiq(&["--annotate"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"      1 a\n      1 b\n");
+
+    let output = runiq(&["--annotate", "-i"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"      2 a\n      3 a\n      2 b\n");
+}
+
+#[test]
+fn occurrence_thresholds() {
+    let input = b"b\na\nb\nc\nb\na\nd\nd\n";
+
+    let output = runiq(&["--min-count", "2"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"b\na\nd\n");
+
+    let output = runiq(&["--max-count", "2"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nc\nd\n");
+
+    let output = runiq(&["--min-count", "2", "--max-count", "2"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nd\n");
+
+    let output = runiq(&["--min-count", "1"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, runiq(&[], input).stdout);
+
+    let output = runiq(&["-c", "--min-count", "2"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"      3 b\n      2 a\n      2 d\n");
+
+    let output = runiq(&["-c", "--min-count", "1"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, runiq(&["-c"], input).stdout);
+
+    let output = runiq(&["-i", "--min-count", "2"], input);
+
+    assert!(!output.status.success());
+
+    let output = runiq(&["--min-count", "3", "--max-count", "2"], input);
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn all_repeated_entries() {
+    let input = b"b\na\nb\nc\nb\ne\nd\nd\n";
+
+    let output = runiq(&["--all-repeated"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"b\nb\nb\nd\nd\n");
+
+    let output = runiq(&["-D", "-i"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nc\ne\n");
+
+    let output = runiq(&["-D", "--min-count", "3"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"b\nb\nb\n");
+}
+
+#[test]
+fn grouped_entries() {
+    let input = b"b\na\nb\nc\nb\nd\nd\n";
+
+    let output = runiq(&["--group"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"b\nb\nb\n\na\n\nc\n\nd\nd\n");
+
+    let output = runiq(&["--group", "--min-count", "2"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"b\nb\nb\n\nd\nd\n");
+}
+
+#[test]
+fn unique_only_entries() {
+    let input = b"b\na\nb\nc\nb\ne\nd\nd\n";
+
+    let output = runiq(&["--unique-only"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nc\ne\n");
+
+    let output = runiq(&["-u", "-c"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"      1 a\n      1 c\n      1 e\n");
+
+    let output = runiq(&["-u", "--min-count", "2"], input);
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn field_key_selection() {
+    let input = b"1 a x\n2 b x\n3  a   y\n4 c x\n";
+
+    let output = runiq(&["-k", "2"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"1 a x\n2 b x\n4 c x\n");
+
+    let output = runiq(&["--field", "2,3"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"1 a x\n2 b x\n3  a   y\n4 c x\n");
+
+    let output = runiq(&["-k", "2", "-d", ","], b"1,a\n2,a\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"1,a\n");
+
+    let output = runiq(&["-k", "0"], input);
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn case_insensitive_input() {
+    let input = b"Example.com\nexample.COM\nother.com\nEXAMPLE.com\n";
+
+    let output = runiq(&["--ignore-case"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"Example.com\nother.com\n");
+
+    let output = runiq(&["--ignore-case", "-c"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"      3 Example.com\n      1 other.com\n");
+}
+
+#[test]
+fn trimmed_input() {
+    let input = b"a\na \t\n  a\n b \n";
+
+    let output = runiq(&["--trim"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\n b \n");
+
+    let output = runiq(&["--trim-end"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\n  a\n b \n");
+
+    let output = runiq(&["--trim-start"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\na \t\n b \n");
+}
+
+#[test]
+fn skipped_key_chars() {
+    let input = b"10:00 start\n10:05 start\n10:07 stop\n1\n";
+
+    let output = runiq(&["--skip-chars", "6"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"10:00 start\n10:07 stop\n1\n");
+}
+
+#[test]
+fn checked_key_chars() {
+    let input = b"A001 x\nA001 y\nA002 x\nA00\n";
+
+    let output = runiq(&["--check-chars", "4"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"A001 x\nA002 x\nA00\n");
+
+    let output = runiq(&["--skip-chars", "1", "--check-chars", "2"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"A001 x\n");
+}
+
+#[test]
+fn zero_terminated_records() {
+    let output = runiq(&["-z"], b"a\nb\0c\0a\nb\0c");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nb\0c\0");
+}
+
+#[test]
+fn custom_record_delimiters() {
+    let output = runiq(&["--record-delimiter", ";"], b"a;b;a;c");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a;b;c;");
+
+    let output = runiq(&["--record-delimiter", "\\r\\n"], b"a\r\nb\nc\r\na\r\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\r\nb\nc\r\n");
+
+    let output = runiq(&["--record-delimiter", "\\x1e"], b"a\x1eb\x1ea");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\x1eb\x1e");
+
+    let output = runiq(&["--record-delimiter", "\\xZZ"], b"");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn assert_increasing_input() {
+    let args = ["-f", "sorted", "--numeric", "--assert-increasing"];
+
+    let output = runiq(&args, b"1\n2\n2\n10\n10\n11\n");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"1\n2\n10\n11\n");
+
+    let output = runiq(&args, b"1\n2\n2\n10\n3\n11\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, b"1\n2\n10\n");
+    assert_eq!(
+        stderr.trim(),
+        "runiq: input '-' is not increasing at line 5"
+    );
+
+    let output = runiq(&args[2..], b"1\n2\n2\n10\n3\n11\n");
+
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, b"1\n2\n10\n");
+
+    let output = runiq(&["--assert-increasing"], b"1\n2\n10\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        stderr.trim(),
+        "runiq: input '-' is not increasing at line 3"
+    );
+
+    let output = runiq(&["-f", "sorted", "--numeric"], b"1\nx\n2\ny\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"1\nx\n2\ny\n");
+    assert_eq!(
+        stderr.trim(),
+        "runiq: warning: input '-' is not numeric at line 2"
+    );
+
+    let output = runiq(&["-f", "sorted", "--numeric", "--strict"], b"1\nx\n");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn sampled_filtering() {
+    let input = (0..2000)
+        .map(|value| format!("{}\n", value % 1000))
+        .collect::<String>();
+
+    let args = ["--sample", "0.5", "--unsampled", "drop"];
+    let output = runiq(&args, input.as_bytes());
+
+    assert!(output.status.success());
+
+    let lines = output
+        .stdout
+        .split(|b| *b == b'\n')
+        .filter(|l| !l.is_empty());
+    let sampled = lines.collect::<Vec<_>>();
+    let unique = sampled.iter().collect::<HashSet<_>>();
+
+    assert!(sampled.len() > 400 && sampled.len() < 600);
+    assert_eq!(unique.len(), sampled.len());
+    assert_eq!(runiq(&args, input.as_bytes()).stdout, output.stdout);
+
+    let seeded = ["--sample", "0.5", "--unsampled", "drop", "--seed", "7"];
+    let output = runiq(&seeded, input.as_bytes());
+
+    assert!(output.status.success());
+    assert_eq!(runiq(&seeded, input.as_bytes()).stdout, output.stdout);
+    assert_ne!(runiq(&args, input.as_bytes()).stdout, output.stdout);
+
+    let output = runiq(&["--sample", "0", "--unsampled", "drop"], input.as_bytes());
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = runiq(&["--sample", "0"], input.as_bytes());
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, input.as_bytes());
+
+    let output = runiq(&["--sample", "1"], input.as_bytes());
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, runiq(&[], input.as_bytes()).stdout);
+}
+
+#[test]
+fn reservoir_sampled_uniques() {
+    let input = (0..1000)
+        .map(|value| format!("{}\n", value % 100))
+        .collect::<String>();
+
+    let args = ["--sample-unique", "10", "--seed", "42"];
+
+    let output = runiq(&args, input.as_bytes());
+    let sample = String::from_utf8(output.stdout).unwrap();
+    let entries = sample.lines().collect::<Vec<_>>();
+
+    assert!(output.status.success());
+    assert_eq!(entries.len(), 10);
+    assert_eq!(entries.iter().collect::<HashSet<_>>().len(), 10);
+
+    let output = runiq(&args, input.as_bytes());
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), sample);
+
+    let output = runiq(&["--sample-unique", "10", "--seed", "7"], input.as_bytes());
+
+    assert_ne!(String::from_utf8(output.stdout).unwrap(), sample);
+}
+
+#[test]
+fn sorted_group_separators() {
+    let input = b"a\na\nb\nc\nc\nc\n";
+
+    let output = runiq(&["-f", "sorted", "--group-separator", "=="], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\n==\nb\n==\nc\n");
+
+    let output = runiq(&["-f", "sorted", "-i", "--group-separator"], input);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\n\nc\nc\n");
+
+    let output = runiq(&["-f", "quick", "--group-separator", "=="], input);
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn emitted_entry_offsets() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.txt");
+    let offsets = dir.path().join("offsets.txt");
+
+    let contents = b"a\nbb\r\na\nccc\nbb\n";
+    std::fs::write(&input, contents).unwrap();
+
+    let input = input.to_str().unwrap();
+    let offsets = offsets.to_str().unwrap();
+
+    let output = runiq(&["--emit-offsets", offsets, input], b"");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"a\nbb\nccc\n");
+
+    let written = std::fs::read_to_string(offsets).unwrap();
+    let entries = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(written, "0\n2\n8\n");
+
+    for (offset, entry) in written.lines().zip(entries.lines()) {
+        let start = offset.parse::<usize>().unwrap();
+        assert!(contents[start..].starts_with(entry.as_bytes()));
+    }
+
+    let output = runiq(
+        &[
+            "--emit-offsets",
+            offsets,
+            "--offset-scope",
+            "file",
+            input,
+            input,
+        ],
+        b"",
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(offsets).unwrap(),
+        "0\t0\n0\t2\n0\t8\n"
+    );
+
+    let output = runiq(&["--emit-offsets", offsets, "-i", input, input], b"");
+
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(offsets).unwrap(),
+        "6\n12\n15\n17\n21\n23\n27\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "protobuf")]
+fn protobuf_field_filtering() {
+    use prost_reflect::prost::Message;
+    use prost_reflect::prost_types::field_descriptor_proto::Type;
+    use prost_reflect::prost_types::{
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    };
+
+    let field = |name: &str, number| FieldDescriptorProto {
+        name: Some(name.to_owned()),
+        number: Some(number),
+        r#type: Some(Type::String as i32),
+        ..Default::default()
+    };
+
+    let descriptors = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("event.proto".to_owned()),
+            package: Some("test".to_owned()),
+            syntax: Some("proto3".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("Event".to_owned()),
+                field: vec![field("id", 1), field("body", 2)],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("event.desc");
+
+    std::fs::write(&path, descriptors.encode_to_vec()).unwrap();
+
+    let event = |id: &str, body: &str| {
+        let mut message = vec![0x0A, id.len() as u8];
+        message.extend_from_slice(id.as_bytes());
+        message.extend_from_slice(&[0x12, body.len() as u8]);
+        message.extend_from_slice(body.as_bytes());
+        message.insert(0, message.len() as u8);
+        message
+    };
+
+    let mut input = Vec::new();
+    input.extend(event("a", "first"));
+    input.extend(event("b", "second"));
+    input.extend(event("a", "third"));
+    input.extend([0x02, 0x0A, 0x05]);
+
+    let mut expected = Vec::new();
+    expected.extend(event("a", "first"));
+    expected.extend(event("b", "second"));
+
+    let descriptor = path.to_str().unwrap();
+    let args = [
+        "--protobuf",
+        "--descriptor",
+        descriptor,
+        "--message",
+        "test.Event",
+        "--field",
+        "id",
+    ];
+
+    let output = runiq(&args, &input);
+
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, expected);
+
+    let output = runiq(&[&args[..], &["--on-error", "skip"]].concat(), &input);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+    assert!(stderr.starts_with("runiq: warning: cannot decode '-' at message 4"));
+}